@@ -0,0 +1,503 @@
+//! Compare the languages of regular expressions: the sets of strings that
+//! they match as a whole, as an XSD pattern facet does.
+//!
+//! These checks answer questions such as whether a type derived by
+//! restriction only accepts values that its base type accepts:
+//!
+//! ```
+//! use regexml::{analysis, Regex};
+//!
+//! let base = Regex::xsd(r"\d{3}-\d{4}", "").unwrap();
+//! let derived = Regex::xsd(r"555-\d{4}", "").unwrap();
+//! assert!(analysis::is_subset(&derived, &base).unwrap().holds);
+//!
+//! let wider = Regex::xsd(r"\d{3}-?\d{4}", "").unwrap();
+//! let verdict = analysis::is_subset(&wider, &base).unwrap();
+//! assert!(!verdict.holds);
+//! assert_eq!(verdict.example.as_deref(), Some("0000000"));
+//! ```
+//!
+//! The checks are exact. They build automata whose transitions are labelled
+//! with sets of characters, split into the smallest sets that every
+//! character class in the patterns either contains or excludes, so that
+//! large classes such as `\p{L}` cost no more than a single character.
+//! The number of states can still grow exponentially with the patterns;
+//! if it exceeds a fixed limit, [`Error::LimitExceeded`] is returned.
+//!
+//! Regular expressions with back-references don't describe regular
+//! languages, so they are rejected with [`Error::Unsupported`], as are `^`
+//! and `$` with the `m` flag.
+
+use ahash::{HashMap, HashMapExt};
+use icu_casemap::{CaseMapCloser, CaseMapper};
+use icu_collections::codepointinvlist::CodePointInversionListBuilder;
+use std::collections::VecDeque;
+
+use crate::character_class::{CharacterClass, CharacterClassBuilder};
+use crate::operation::{Operation, RepeatOperation};
+use crate::re_compiler::Error;
+use crate::Regex;
+
+// The maximum number of states in an automaton for one regular expression,
+// and in the combination of the automata that a check explores.
+const MAX_STATES: usize = 100_000;
+
+/// The result of a check, with an example string that shows why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    /// Whether the property that was checked holds
+    pub holds: bool,
+    /// A string that demonstrates the answer, if there is one. Each check
+    /// describes what it gives. The example is as short as possible.
+    pub example: Option<String>,
+}
+
+/// Check whether every string that `a` matches is also matched by `b`.
+///
+/// If not, the example is a string that `a` matches and `b` doesn't.
+pub fn is_subset(a: &Regex, b: &Regex) -> Result<Verdict, Error> {
+    let example = Check::new(&[a, b])?.find(|accepts| accepts[0] && !accepts[1])?;
+    Ok(Verdict {
+        holds: example.is_none(),
+        example,
+    })
+}
+
+/// Check whether there is a string that both `a` and `b` match.
+///
+/// If so, the example is such a string.
+pub fn intersects(a: &Regex, b: &Regex) -> Result<Verdict, Error> {
+    let example = Check::new(&[a, b])?.find(|accepts| accepts[0] && accepts[1])?;
+    Ok(Verdict {
+        holds: example.is_some(),
+        example,
+    })
+}
+
+/// Check whether `a` and `b` match exactly the same strings.
+///
+/// If not, the example is a string that one of them matches and the other
+/// doesn't.
+pub fn equivalent(a: &Regex, b: &Regex) -> Result<Verdict, Error> {
+    let example = Check::new(&[a, b])?.find(|accepts| accepts[0] != accepts[1])?;
+    Ok(Verdict {
+        holds: example.is_none(),
+        example,
+    })
+}
+
+/// Check whether `a` matches no string at all, as with `[a-z-[a-z]]`.
+///
+/// If it matches something, the example is a string that it matches.
+pub fn is_empty_language(a: &Regex) -> Result<Verdict, Error> {
+    let example = Check::new(&[a])?.find(|accepts| accepts[0])?;
+    Ok(Verdict {
+        holds: example.is_none(),
+        example,
+    })
+}
+
+// An epsilon-NFA for the strings that a compiled program matches as a whole.
+struct Automaton {
+    edges: Vec<Vec<(Label, usize)>>,
+    start: usize,
+    accept: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Label {
+    Epsilon,
+    // only at the start or the end of the string
+    Bol,
+    Eol,
+    // a character in one of the classes of the alphabet
+    Class(usize),
+}
+
+// The character classes on the transitions of the automata, shared between
+// the automata of a check.
+#[derive(Default)]
+struct Alphabet {
+    classes: Vec<CharacterClass>,
+    indexes: HashMap<Vec<(u32, u32)>, usize>,
+}
+
+impl Alphabet {
+    fn add(&mut self, class: CharacterClass) -> usize {
+        let key = class
+            .as_code_point_inversion_list()
+            .iter_ranges()
+            .map(|range| (*range.start(), *range.end()))
+            .collect::<Vec<_>>();
+        let next = self.classes.len();
+        *self.indexes.entry(key).or_insert_with(|| {
+            self.classes.push(class);
+            next
+        })
+    }
+
+    // The sets of characters that no class splits, as the classes each of
+    // them is in, with an example character of each.
+    fn symbols(&self) -> Vec<Symbol> {
+        let mut points = vec![0, 0xD800, 0xE000, 0x110000];
+        for class in &self.classes {
+            for range in class.as_code_point_inversion_list().iter_ranges() {
+                points.push(*range.start());
+                points.push(*range.end() + 1);
+            }
+        }
+        points.sort_unstable();
+        points.dedup();
+        let mut symbols: Vec<Symbol> = Vec::new();
+        let mut by_classes: HashMap<Vec<bool>, usize> = HashMap::new();
+        for window in points.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            if (0xD800..0xE000).contains(&start) {
+                // surrogates aren't characters
+                continue;
+            }
+            let classes = self
+                .classes
+                .iter()
+                .map(|class| class.as_code_point_inversion_list().contains32(start))
+                .collect::<Vec<_>>();
+            if !classes.contains(&true) {
+                continue;
+            }
+            // prefer printable ASCII as an example, as it's easy to read
+            let printable = (start.max(0x21) < end.min(0x7F)).then_some(start.max(0x21));
+            match by_classes.get(&classes) {
+                Some(&i) => {
+                    if let Some(printable) = printable {
+                        if !symbols[i].example.is_ascii_graphic() {
+                            symbols[i].example = char::from_u32(printable).unwrap_or('?');
+                        }
+                    }
+                }
+                None => {
+                    let example = char::from_u32(printable.unwrap_or(start)).unwrap_or('?');
+                    by_classes.insert(classes.clone(), symbols.len());
+                    symbols.push(Symbol { classes, example });
+                }
+            }
+        }
+        symbols
+    }
+}
+
+struct Symbol {
+    classes: Vec<bool>,
+    example: char,
+}
+
+// Builds the automaton for a program with Thompson's construction,
+// unrolling repeats.
+struct Builder<'a> {
+    alphabet: &'a mut Alphabet,
+    edges: Vec<Vec<(Label, usize)>>,
+    case_map_closer: Option<CaseMapCloser<CaseMapper>>,
+}
+
+impl Builder<'_> {
+    fn automaton(alphabet: &mut Alphabet, regex: &Regex) -> Result<Automaton, Error> {
+        let program = regex.re_program();
+        if regex.has_back_references() {
+            return Err(Error::Unsupported(
+                "back-references don't describe a regular language".to_string(),
+            ));
+        }
+        let mut builder = Builder {
+            alphabet,
+            edges: Vec::new(),
+            case_map_closer: program.flags.is_case_independent().then(CaseMapCloser::new),
+        };
+        let multi_line = program.flags.is_multi_line();
+        let start = builder.state()?;
+        let accept = builder.state()?;
+        builder.operation(&program.operation, start, accept, multi_line)?;
+        Ok(Automaton {
+            edges: builder.edges,
+            start,
+            accept,
+        })
+    }
+
+    fn state(&mut self) -> Result<usize, Error> {
+        if self.edges.len() >= MAX_STATES {
+            return Err(Error::LimitExceeded(format!(
+                "the automaton has more than {} states",
+                MAX_STATES
+            )));
+        }
+        self.edges.push(Vec::new());
+        Ok(self.edges.len() - 1)
+    }
+
+    fn edge(&mut self, from: usize, label: Label, to: usize) {
+        self.edges[from].push((label, to));
+    }
+
+    fn class(&mut self, from: usize, class: CharacterClass, to: usize) {
+        let index = self.alphabet.add(class);
+        self.edge(from, Label::Class(index), to);
+    }
+
+    fn operation(
+        &mut self,
+        operation: &Operation,
+        from: usize,
+        to: usize,
+        multi_line: bool,
+    ) -> Result<(), Error> {
+        match operation {
+            Operation::Atom(atom) => {
+                let mut current = from;
+                for c in &atom.atom {
+                    let next = self.state()?;
+                    let class = match &self.case_map_closer {
+                        Some(closer) => {
+                            let mut builder = CodePointInversionListBuilder::new();
+                            builder.add_char(*c);
+                            closer.add_case_closure_to(*c, &mut builder);
+                            CharacterClass::new(builder.build())
+                        }
+                        None => CharacterClassBuilder::from_char(*c).build(),
+                    };
+                    self.class(current, class, next);
+                    current = next;
+                }
+                self.edge(current, Label::Epsilon, to);
+            }
+            Operation::CharClass(char_class) => {
+                self.class(from, char_class.character_class.clone(), to)
+            }
+            Operation::BackReference(_) => {
+                return Err(Error::Unsupported(
+                    "back-references don't describe a regular language".to_string(),
+                ))
+            }
+            Operation::WordBoundary(_) | Operation::Lookahead(_) => {
+                return Err(Error::Unsupported(
+                    "word boundaries and lookaheads aren't supported".to_string(),
+                ))
+            }
+            Operation::Bol(_) | Operation::Eol(_) if multi_line => {
+                return Err(Error::Unsupported(
+                    "'^' and '$' with the 'm' flag aren't supported".to_string(),
+                ))
+            }
+            Operation::Bol(_) => self.edge(from, Label::Bol, to),
+            Operation::Eol(_) => self.edge(from, Label::Eol, to),
+            Operation::Nothing(_) | Operation::EndProgram(_) => self.edge(from, Label::Epsilon, to),
+            Operation::Capture(capture) => {
+                self.operation(&capture.child_op, from, to, multi_line)?
+            }
+            Operation::Choice(choice) => {
+                for branch in &choice.branches {
+                    self.operation(branch, from, to, multi_line)?;
+                }
+            }
+            Operation::Sequence(sequence) => {
+                let mut current = from;
+                for operation in &sequence.operations {
+                    let next = self.state()?;
+                    self.operation(operation, current, next, multi_line)?;
+                    current = next;
+                }
+                self.edge(current, Label::Epsilon, to);
+            }
+            Operation::Repeat(_)
+            | Operation::GreedyFixed(_)
+            | Operation::ReluctantFixed(_)
+            | Operation::UnambiguousRepeat(_) => {
+                let repeat = operation
+                    .repeat_operation()
+                    .ok_or_else(|| Error::internal("repeat without bounds"))?;
+                self.repeat(repeat, from, to, multi_line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn repeat(
+        &mut self,
+        repeat: &dyn RepeatOperation,
+        from: usize,
+        to: usize,
+        multi_line: bool,
+    ) -> Result<(), Error> {
+        let child = repeat.child();
+        let (min, max) = (repeat.min(), repeat.max());
+        let mut current = self.state()?;
+        self.edge(from, Label::Epsilon, current);
+        for _ in 0..min {
+            let next = self.state()?;
+            self.operation(&child, current, next, multi_line)?;
+            current = next;
+        }
+        if max == usize::MAX {
+            // a loop of its own, so that it can't lead back into the copies
+            // before it
+            let body = self.state()?;
+            self.edge(current, Label::Epsilon, body);
+            self.operation(&child, body, body, multi_line)?;
+            self.edge(body, Label::Epsilon, to);
+        } else {
+            for _ in min..max {
+                let next = self.state()?;
+                self.edge(current, Label::Epsilon, to);
+                self.operation(&child, current, next, multi_line)?;
+                current = next;
+            }
+            self.edge(current, Label::Epsilon, to);
+        }
+        Ok(())
+    }
+}
+
+// Explores the automata of several regular expressions in step, one
+// character at a time.
+struct Check {
+    automata: Vec<Automaton>,
+    symbols: Vec<Symbol>,
+}
+
+// The states of each automaton after reading some string, before following
+// epsilon transitions, and whether nothing has been read yet.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Position {
+    states: Vec<Vec<usize>>,
+    at_start: bool,
+}
+
+impl Check {
+    fn new(regexes: &[&Regex]) -> Result<Self, Error> {
+        let mut alphabet = Alphabet::default();
+        let automata = regexes
+            .iter()
+            .map(|regex| Builder::automaton(&mut alphabet, regex))
+            .collect::<Result<Vec<_>, _>>()?;
+        let symbols = alphabet.symbols();
+        Ok(Self { automata, symbols })
+    }
+
+    // The shortest string for which the automata accept as `goal` wants,
+    // given whether each of them accepts, if there is one.
+    fn find(&self, goal: impl Fn(&[bool]) -> bool) -> Result<Option<String>, Error> {
+        let start = Position {
+            states: self
+                .automata
+                .iter()
+                .map(|automaton| vec![automaton.start])
+                .collect(),
+            at_start: true,
+        };
+        // every position that was reached, with the one it was reached from
+        // and the character that led to it
+        let mut reached = vec![(start.clone(), None)];
+        let mut indexes = HashMap::new();
+        indexes.insert(start, 0);
+        let mut queue = VecDeque::from([0]);
+        while let Some(index) = queue.pop_front() {
+            let position = reached[index].0.clone();
+            let accepts = self
+                .automata
+                .iter()
+                .zip(&position.states)
+                .map(|(automaton, states)| {
+                    automaton
+                        .closure(states, position.at_start, true)
+                        .contains(&automaton.accept)
+                })
+                .collect::<Vec<_>>();
+            if goal(&accepts) {
+                return Ok(Some(self.example(&reached, index)));
+            }
+            let closures = self
+                .automata
+                .iter()
+                .zip(&position.states)
+                .map(|(automaton, states)| automaton.closure(states, position.at_start, false))
+                .collect::<Vec<_>>();
+            for (i, symbol) in self.symbols.iter().enumerate() {
+                let states = self
+                    .automata
+                    .iter()
+                    .zip(&closures)
+                    .map(|(automaton, closure)| automaton.step(closure, &symbol.classes))
+                    .collect::<Vec<_>>();
+                if states.iter().all(Vec::is_empty) {
+                    continue;
+                }
+                let next = Position {
+                    states,
+                    at_start: false,
+                };
+                if indexes.contains_key(&next) {
+                    continue;
+                }
+                if reached.len() >= MAX_STATES {
+                    return Err(Error::LimitExceeded(format!(
+                        "the check needs more than {} states",
+                        MAX_STATES
+                    )));
+                }
+                indexes.insert(next.clone(), reached.len());
+                queue.push_back(reached.len());
+                reached.push((next, Some((index, i))));
+            }
+        }
+        Ok(None)
+    }
+
+    fn example(&self, reached: &[(Position, Option<(usize, usize)>)], mut index: usize) -> String {
+        let mut chars = Vec::new();
+        while let Some((previous, symbol)) = reached[index].1 {
+            chars.push(self.symbols[symbol].example);
+            index = previous;
+        }
+        chars.into_iter().rev().collect()
+    }
+}
+
+impl Automaton {
+    // the states reachable without reading a character
+    fn closure(&self, states: &[usize], at_start: bool, at_end: bool) -> Vec<usize> {
+        let mut seen = vec![false; self.edges.len()];
+        let mut stack = states.to_vec();
+        let mut closure = Vec::new();
+        while let Some(state) = stack.pop() {
+            if std::mem::replace(&mut seen[state], true) {
+                continue;
+            }
+            closure.push(state);
+            for (label, target) in &self.edges[state] {
+                let passes = match label {
+                    Label::Epsilon => true,
+                    Label::Bol => at_start,
+                    Label::Eol => at_end,
+                    Label::Class(_) => false,
+                };
+                if passes {
+                    stack.push(*target);
+                }
+            }
+        }
+        closure
+    }
+
+    // the states reached by reading a character in the given classes
+    fn step(&self, closure: &[usize], classes: &[bool]) -> Vec<usize> {
+        let mut states = closure
+            .iter()
+            .flat_map(|state| &self.edges[*state])
+            .filter_map(|(label, target)| match label {
+                Label::Class(class) if classes[*class] => Some(*target),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        states.sort_unstable();
+        states.dedup();
+        states
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::io;
+
+use crate::analyze_string::{AnalyzeEntry, MatchEntry};
+
+/// The namespace of the XML that `fn:analyze-string` returns.
+pub const FN_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions";
+
+/// Write the results of [`Regex::analyze`](crate::Regex::analyze) as the
+/// XML that XPath's `fn:analyze-string` returns.
+///
+/// This is an `analyze-string-result` element in the
+/// [`FN_NAMESPACE`](crate::FN_NAMESPACE) namespace, with a `match` or
+/// `non-match` element for each entry and `group nr="N"` elements for the
+/// groups within matches. No whitespace is added, as it would be part of the
+/// text.
+///
+/// Carriage returns are written as character references so that they survive
+/// the line ending normalization of XML parsers. Control characters that XML
+/// 1.0 doesn't allow are written as character references too, which only
+/// XML 1.1 accepts.
+pub fn write_analyze_xml<W: fmt::Write>(
+    out: &mut W,
+    entries: impl IntoIterator<Item = AnalyzeEntry>,
+) -> fmt::Result {
+    let mut entries = entries.into_iter().peekable();
+    write!(out, "<analyze-string-result xmlns=\"{}\"", FN_NAMESPACE)?;
+    if entries.peek().is_none() {
+        return out.write_str("/>");
+    }
+    out.write_char('>')?;
+    for entry in entries {
+        match entry {
+            AnalyzeEntry::Match(match_entries) => {
+                xml_element(out, "match", "", &match_entries)?;
+            }
+            AnalyzeEntry::NonMatch(text) => {
+                out.write_str("<non-match>")?;
+                xml_text(out, &text)?;
+                out.write_str("</non-match>")?;
+            }
+        }
+    }
+    out.write_str("</analyze-string-result>")
+}
+
+/// Write the results of [`Regex::analyze`](crate::Regex::analyze) as XML to
+/// an [`io::Write`]. See [`write_analyze_xml`] for the format; the XML is
+/// encoded as UTF-8.
+pub fn write_analyze_xml_io<W: io::Write>(
+    out: &mut W,
+    entries: impl IntoIterator<Item = AnalyzeEntry>,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter::new(out);
+    let result = write_analyze_xml(&mut adapter, entries);
+    adapter.finish(result)
+}
+
+/// Write the results of [`Regex::analyze`](crate::Regex::analyze) as JSON.
+///
+/// The results are an array with an object for each entry: a non-match is
+/// `{"non-match": "text"}` and a match is `{"match": [...]}`, where the
+/// array holds strings and groups. A group is `{"nr": N, "value": [...]}`,
+/// with its content in the same form. Matching `a(b)` against `"xab"` gives:
+///
+/// ```json
+/// [{"non-match":"x"},{"match":["a",{"nr":1,"value":["b"]}]}]
+/// ```
+pub fn write_analyze_json<W: fmt::Write>(
+    out: &mut W,
+    entries: impl IntoIterator<Item = AnalyzeEntry>,
+) -> fmt::Result {
+    out.write_char('[')?;
+    for (i, entry) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        match entry {
+            AnalyzeEntry::Match(match_entries) => {
+                out.write_str("{\"match\":")?;
+                json_entries(out, &match_entries)?;
+                out.write_char('}')?;
+            }
+            AnalyzeEntry::NonMatch(text) => {
+                out.write_str("{\"non-match\":")?;
+                json_string(out, &text)?;
+                out.write_char('}')?;
+            }
+        }
+    }
+    out.write_char(']')
+}
+
+/// Write the results of [`Regex::analyze`](crate::Regex::analyze) as JSON to
+/// an [`io::Write`]. See [`write_analyze_json`] for the format; the JSON is
+/// encoded as UTF-8.
+pub fn write_analyze_json_io<W: io::Write>(
+    out: &mut W,
+    entries: impl IntoIterator<Item = AnalyzeEntry>,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter::new(out);
+    let result = write_analyze_json(&mut adapter, entries);
+    adapter.finish(result)
+}
+
+fn xml_element<W: fmt::Write>(
+    out: &mut W,
+    name: &str,
+    attributes: &str,
+    entries: &[MatchEntry],
+) -> fmt::Result {
+    write!(out, "<{}{}", name, attributes)?;
+    if entries.is_empty() {
+        return out.write_str("/>");
+    }
+    out.write_char('>')?;
+    for entry in entries {
+        match entry {
+            MatchEntry::String(text) => xml_text(out, text)?,
+            MatchEntry::Group { nr, value } => {
+                xml_element(out, "group", &format!(" nr=\"{}\"", nr), value)?
+            }
+        }
+    }
+    write!(out, "</{}>", name)
+}
+
+fn xml_text<W: fmt::Write>(out: &mut W, text: &str) -> fmt::Result {
+    for c in text.chars() {
+        match c {
+            '&' => out.write_str("&amp;")?,
+            '<' => out.write_str("&lt;")?,
+            '>' => out.write_str("&gt;")?,
+            '\t' | '\n' => out.write_char(c)?,
+            c if c.is_control() => write!(out, "&#x{:X};", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+fn json_entries<W: fmt::Write>(out: &mut W, entries: &[MatchEntry]) -> fmt::Result {
+    out.write_char('[')?;
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        match entry {
+            MatchEntry::String(text) => json_string(out, text)?,
+            MatchEntry::Group { nr, value } => {
+                write!(out, "{{\"nr\":{},\"value\":", nr)?;
+                json_entries(out, value)?;
+                out.write_char('}')?;
+            }
+        }
+    }
+    out.write_char(']')
+}
+
+fn json_string<W: fmt::Write>(out: &mut W, text: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+// Lets the writers above write to an io::Write, keeping the error that
+// fmt::Error can't carry.
+struct IoAdapter<'a, W: io::Write> {
+    out: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> IoAdapter<'a, W> {
+    fn new(out: &'a mut W) -> Self {
+        Self { out, error: None }
+    }
+
+    fn finish(self, result: fmt::Result) -> io::Result<()> {
+        match (result, self.error) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Some(error)) => Err(error),
+            (Err(_), None) => Err(io::Error::other("formatting failed")),
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
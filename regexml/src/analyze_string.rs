@@ -32,17 +32,15 @@ pub struct AnalyzeIter<'a> {
     matcher: ReMatcher<'a>,
     next_substring: Option<Vec<char>>,
     prev_end: Option<usize>,
-    nesting_table: HashMap<usize, usize>,
     skip: bool,
 }
 
 impl<'a> AnalyzeIter<'a> {
-    pub(crate) fn new(pattern: &'a [char], matcher: ReMatcher<'a>) -> Self {
+    pub(crate) fn new(matcher: ReMatcher<'a>) -> Self {
         AnalyzeIter {
             matcher,
             next_substring: None,
             prev_end: Some(0),
-            nesting_table: Self::compute_nesting_table(pattern),
             skip: false,
         }
     }
@@ -68,24 +66,28 @@ impl<'a> AnalyzeIter<'a> {
             // the "actions" in each list are: +N: start group N, -N: end group N.
             let mut actions: HashMap<usize, Vec<isize>> = HashMap::new();
             for i in 1..=c {
-                if let (Some(start_i), Some(start_0)) = (
+                if let (Some(start_i), Some(end_i), Some(start_0)) = (
                     self.matcher.get_paren_start(i),
+                    self.matcher.get_paren_end(i),
                     self.matcher.get_paren_start(0),
                 ) {
+                    // a group that didn't take part in this match
+                    if start_i < start_0 {
+                        continue;
+                    }
                     let start = start_i - start_0;
-                    let end = self.matcher.get_paren_end(i).unwrap() - start_0;
+                    let end = end_i.saturating_sub(start_0);
                     if start < end {
                         // add the start action after all other actions on the
                         // list for the same position
                         let s = actions
                             .entry(start)
                             .or_insert_with(|| Vec::with_capacity(4));
-                        s.push(i.try_into().unwrap());
+                        s.push(i as isize);
                         // add the end action after all other actions on the
                         // list for the same position
                         let e = actions.entry(end).or_insert_with(|| Vec::with_capacity(4));
-                        let i: isize = i.try_into().unwrap();
-                        e.insert(0, -i);
+                        e.insert(0, -(i as isize));
                     } else {
                         // zero-length group (start==end). The problem here is
                         // that the information available by itself isn't
@@ -94,7 +96,13 @@ impl<'a> AnalyzeIter<'a> {
                         // both give the same result for group 2 (start=1,
                         // end=1). So we need to go back to the original regex
                         // to determine the group nesting
-                        let parent_group = self.nesting_table.get(&i).unwrap();
+                        let parent_group = self
+                            .matcher
+                            .program
+                            .nesting_table
+                            .get(&i)
+                            .copied()
+                            .unwrap_or(0);
                         // insert the start and end events immediately before
                         // the end event for the parent group, if present;
                         // otherwise after all existing events for this
@@ -104,21 +112,20 @@ impl<'a> AnalyzeIter<'a> {
                             .and_modify(|v| {
                                 let mut pos = v.len();
                                 for e in 0..v.len() {
-                                    let parent_group: isize = (*parent_group).try_into().unwrap();
-                                    if v.get(e) == Some(&-parent_group) {
+                                    if v.get(e) == Some(&-(parent_group as isize)) {
                                         pos = e;
                                         break;
                                     }
                                 }
-                                let i: isize = i.try_into().unwrap();
+                                let i = i as isize;
                                 v.insert(pos, -i);
                                 v.insert(pos, i);
                             })
                             .or_insert_with(|| {
                                 let mut v = Vec::with_capacity(4);
-                                let i: isize = i.try_into().unwrap();
-                                v.push(-i);
+                                let i = i as isize;
                                 v.push(i);
+                                v.push(-i);
                                 v
                             });
                     }
@@ -159,55 +166,16 @@ impl<'a> AnalyzeIter<'a> {
             if let Some(buf) = buf.take() {
                 regex_match_handler.characters(buf);
             }
-            regex_match_handler.stack.pop().unwrap().entries
-        }
-    }
-
-    fn compute_nesting_table(pattern: &'a [char]) -> HashMap<usize, usize> {
-        let mut nesting_table = HashMap::new();
-        let mut stack = vec![0; pattern.len()];
-        let mut tos = 0;
-        let mut capture_stack = vec![false; pattern.len()];
-        let mut capture_tos = 0;
-        let mut group = 1;
-        let mut in_brackets = 0;
-        tos += 1;
-        let mut i = 0;
-        while i < pattern.len() {
-            let ch = pattern[i];
-            match ch {
-                '\\' => {
-                    i += 1;
-                }
-                '[' => {
-                    in_brackets += 1;
-                }
-                ']' => {
-                    in_brackets -= 1;
-                }
-                '(' if in_brackets == 0 => {
-                    let capture = pattern[i + 1] != '?';
-                    capture_stack[capture_tos] = capture;
-                    capture_tos += 1;
-                    if capture {
-                        nesting_table.insert(group, stack[tos - 1]);
-                        stack[tos] = group;
-                        tos += 1;
-                        group += 1;
-                    }
-                }
-                ')' if in_brackets == 0 => {
-                    capture_tos -= 1;
-                    let capture = capture_stack[capture_tos];
-                    if capture {
-                        tos -= 1;
-                    }
-                }
-                _ => {}
+            // close groups whose end lies beyond the match
+            while regex_match_handler.stack.len() > 1 {
+                regex_match_handler.on_group_end();
             }
-            i += 1;
+            regex_match_handler
+                .stack
+                .pop()
+                .map(|group_info| group_info.entries)
+                .unwrap_or_default()
         }
-        nesting_table
     }
 }
 
@@ -242,26 +210,35 @@ impl Iterator for AnalyzeIter<'_> {
                     }
                 }
 
-                if self.matcher.matches(search_start) {
-                    let start = self.matcher.get_paren_start(0).unwrap();
-                    let end = self.matcher.get_paren_end(0).unwrap();
+                let found = if self.matcher.matches(search_start) {
+                    self.matcher
+                        .get_paren_start(0)
+                        .zip(self.matcher.get_paren_end(0))
+                } else {
+                    None
+                };
+                if let Some((start, end)) = found {
                     self.skip = start == end;
                     if prev_end == start {
                         // there's no intervening non-matching string to return
                         self.next_substring = None;
                         self.prev_end = Some(end);
-                        Some(self.analyze_entry(&self.matcher.search[start..end]))
+                        Some(self.analyze_entry(&self.matcher.search.slice(start..end)))
                     } else {
                         // return the non-matching substring first
-                        self.next_substring = Some(self.matcher.search[start..end].to_vec());
-                        Some(self.analyze_entry(&self.matcher.search[prev_end..start]))
+                        self.next_substring = Some(self.matcher.search.slice(start..end));
+                        Some(self.analyze_entry(&self.matcher.search.slice(prev_end..start)))
                     }
                 } else {
                     // there are no more regex matches, we must return the final non-match
                     if prev_end < self.matcher.search.len() {
                         self.next_substring = None;
                         let non_match = AnalyzeEntry::NonMatch(
-                            self.matcher.search[prev_end..].iter().collect(),
+                            self.matcher
+                                .search
+                                .slice(prev_end..self.matcher.search.len())
+                                .into_iter()
+                                .collect(),
                         );
                         self.prev_end = None;
                         Some(non_match)
@@ -291,7 +268,9 @@ struct RegexMatchHandler {
 
 impl RegexMatchHandler {
     fn top(&mut self) -> &mut Vec<MatchEntry> {
-        &mut self.stack.last_mut().unwrap().entries
+        // the fake group is never popped, so the stack is never empty
+        let last = self.stack.len() - 1;
+        &mut self.stack[last].entries
     }
 
     fn characters(&mut self, s: String) {
@@ -306,10 +285,212 @@ impl RegexMatchHandler {
     }
 
     fn on_group_end(&mut self) {
-        let group_info = self.stack.pop().unwrap();
+        if self.stack.len() == 1 {
+            // the end of a group that was never started
+            return;
+        }
+        let Some(group_info) = self.stack.pop() else {
+            return;
+        };
         self.top().push(MatchEntry::Group {
             nr: group_info.nr,
             value: group_info.entries,
         });
     }
 }
+
+/// Where a piece of the analyzed string is in the input, both in characters
+/// (Unicode code points) and in bytes of its UTF-8 encoding. The ends are
+/// exclusive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Position {
+    /// The character offset of the start
+    pub start: usize,
+    /// The character offset just past the end
+    pub end: usize,
+    /// The byte offset of the start
+    pub byte_start: usize,
+    /// The byte offset just past the end
+    pub byte_end: usize,
+}
+
+/// An [`AnalyzeEntry`] with its position in the input, as returned by
+/// [`Regex::analyze_positioned`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PositionedAnalyzeEntry {
+    /// Match entries of a match
+    Match {
+        /// Where the match is
+        position: Position,
+        /// Match entries of the match
+        entries: Vec<PositionedMatchEntry>,
+    },
+    /// A non-match
+    NonMatch {
+        /// Where the non-match is
+        position: Position,
+        /// The text of the non-match
+        text: String,
+    },
+}
+
+/// A [`MatchEntry`] with its position in the input.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PositionedMatchEntry {
+    /// A match with a string
+    String {
+        /// Where the string is
+        position: Position,
+        /// The text of the string
+        text: String,
+    },
+    /// A match with a group. This contains further match entries.
+    Group {
+        /// The group number
+        nr: usize,
+        /// Where the group is
+        position: Position,
+        /// Match entries of the group
+        value: Vec<PositionedMatchEntry>,
+    },
+}
+
+/// The [`Regex::analyze_positioned`] method returns an iterator over the
+/// results of analyzing a string, with the position of each entry.
+#[derive(Debug)]
+pub struct PositionedAnalyzeIter<'a> {
+    entries: AnalyzeIter<'a>,
+    // where the next entry starts
+    offset: usize,
+    // the position last turned into a byte offset, and that byte offset
+    byte_cursor: (usize, usize),
+}
+
+impl<'a> PositionedAnalyzeIter<'a> {
+    pub(crate) fn new(entries: AnalyzeIter<'a>) -> Self {
+        Self {
+            entries,
+            offset: 0,
+            byte_cursor: (0, 0),
+        }
+    }
+
+    fn position(&mut self, start: usize, end: usize) -> Position {
+        Position {
+            start,
+            end,
+            byte_start: self.byte_offset(start),
+            byte_end: self.byte_offset(end),
+        }
+    }
+
+    // positions are asked for in about the order they appear in, so the
+    // byte offset is found by stepping from the one asked for before
+    fn byte_offset(&mut self, position: usize) -> usize {
+        let search = &self.entries.matcher.search;
+        let (mut current, mut byte) = self.byte_cursor;
+        while current < position {
+            match search.get(current) {
+                Some(c) => byte += c.len_utf8(),
+                None => break,
+            }
+            current += 1;
+        }
+        while current > position {
+            current -= 1;
+            byte -= search.get(current).map_or(0, char::len_utf8);
+        }
+        self.byte_cursor = (current, byte);
+        byte
+    }
+
+    // where a group matched, within the range of what contains it; a group
+    // can end beyond the match, in which case it's cut off at its end
+    fn group_range(&self, nr: usize, start: usize, end: usize) -> (usize, usize) {
+        let matcher = &self.entries.matcher;
+        let group_start = matcher
+            .get_paren_start(nr)
+            .unwrap_or(start)
+            .clamp(start, end);
+        let group_end = matcher
+            .get_paren_end(nr)
+            .unwrap_or(group_start)
+            .clamp(group_start, end);
+        (group_start, group_end)
+    }
+
+    // the entries of a match or a group that covers start to end; a string
+    // runs up to the group that follows it, or to the end
+    fn match_entries(
+        &mut self,
+        entries: Vec<MatchEntry>,
+        start: usize,
+        end: usize,
+    ) -> Vec<PositionedMatchEntry> {
+        let mut offset = start;
+        let mut positioned = Vec::with_capacity(entries.len());
+        let mut entries = entries.into_iter().peekable();
+        while let Some(entry) = entries.next() {
+            match entry {
+                MatchEntry::String(text) => {
+                    let text_end = match entries.peek() {
+                        Some(MatchEntry::Group { nr, .. }) => self.group_range(*nr, offset, end).0,
+                        _ => end,
+                    };
+                    positioned.push(PositionedMatchEntry::String {
+                        position: self.position(offset, text_end),
+                        text,
+                    });
+                    offset = text_end;
+                }
+                MatchEntry::Group { nr, value } => {
+                    let (group_start, group_end) = self.group_range(nr, offset, end);
+                    let position = self.position(group_start, group_end);
+                    positioned.push(PositionedMatchEntry::Group {
+                        nr,
+                        position,
+                        value: self.match_entries(value, group_start, group_end),
+                    });
+                    offset = group_end;
+                }
+            }
+        }
+        positioned
+    }
+}
+
+impl Iterator for PositionedAnalyzeIter<'_> {
+    type Item = PositionedAnalyzeEntry;
+
+    // the positions are taken from the matcher: while a match is returned
+    // it holds the positions of the match and its groups, and while a
+    // non-match is returned it holds the match that follows it
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        let matcher = &self.entries.matcher;
+        Some(match entry {
+            AnalyzeEntry::Match(entries) => {
+                let start = matcher.get_paren_start(0).unwrap_or(self.offset);
+                let end = matcher.get_paren_end(0).unwrap_or(start);
+                self.offset = end;
+                PositionedAnalyzeEntry::Match {
+                    position: self.position(start, end),
+                    entries: self.match_entries(entries, start, end),
+                }
+            }
+            AnalyzeEntry::NonMatch(text) => {
+                let start = self.offset;
+                let end = if self.entries.next_substring.is_some() {
+                    matcher.get_paren_start(0).unwrap_or(start)
+                } else {
+                    matcher.search.len()
+                };
+                self.offset = end;
+                PositionedAnalyzeEntry::NonMatch {
+                    position: self.position(start, end),
+                    text,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,316 @@
+//! A syntax tree for regular expressions.
+//!
+//! [`parse`] turns a pattern into an [`Expr`] without compiling it, so tools
+//! can inspect the structure of a regular expression. Every node carries the
+//! [`Span`] of the pattern it was parsed from.
+//!
+//! The parser applies the same rules as [`Regex`](crate::Regex): a pattern
+//! that parses is a pattern that compiles, and a pattern that doesn't parse
+//! gives the same error as compiling it would.
+
+use crate::diagnostics::Span;
+use crate::explain::{Explainer, Explanation};
+use crate::printer::Printer;
+use crate::re_compiler::Error;
+use crate::re_flags::{Language, ReFlags};
+use crate::re_parser::ReParser;
+
+/// Parse a regular expression into a syntax tree, following the rules of
+/// the given language.
+///
+/// The flags are the same as for [`Regex`](crate::Regex). The `x` flag
+/// strips whitespace before parsing, but spans still refer to the pattern as
+/// it was passed in. With the `q` flag every character in the pattern is a
+/// literal.
+pub fn parse(pattern: &str, flags: &str, language: Language) -> Result<Expr, Error> {
+    let re_flags = ReFlags::new(flags, language)?;
+    let mut parser = ReParser::new(pattern.chars().collect(), &re_flags);
+    parser.parse()
+}
+
+/// Describe a regular expression in natural language.
+///
+/// The flags and language are the same as for [`parse`]. The explanation is
+/// a tree that follows the structure of the pattern, so that each part can
+/// be shown next to the part of the pattern it describes.
+pub fn explain(pattern: &str, flags: &str, language: Language) -> Result<Explanation, Error> {
+    let re_flags = ReFlags::new(flags, language)?;
+    let mut parser = ReParser::new(pattern.chars().collect(), &re_flags);
+    let expr = parser.parse()?;
+    Ok(Explainer::new(&re_flags).explain(&expr))
+}
+
+/// A list of alternatives, separated by `|`.
+///
+/// This is the top-level node of a regular expression, and the content of
+/// a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    /// The alternatives; there is always at least one, which may be empty
+    pub branches: Vec<Branch>,
+    /// The span of all branches, including the `|` separators
+    pub span: Span,
+}
+
+impl Expr {
+    /// Print the syntax tree back to pattern text in the given language.
+    ///
+    /// This keeps escapes and quantifiers as they were written, where the
+    /// language allows it. Whitespace stripped by the `x` flag is not
+    /// restored, so the result is meant to be used without that flag.
+    ///
+    /// A tree can be printed in another language than it was parsed in. For
+    /// instance a literal `^` from XSD is escaped as `\^` for XPath. If the
+    /// tree uses a feature the language doesn't have, such as a
+    /// back-reference in XSD, this returns [`Error::Unsupported`].
+    pub fn to_pattern(&self, language: Language) -> Result<String, Error> {
+        Printer::new(language, false).print(self)
+    }
+
+    /// Print the syntax tree as canonical pattern text in the given language.
+    ///
+    /// Like [`Expr::to_pattern`], but characters are only escaped where that
+    /// is required, and quantifiers are spelled in the shortest way, so `{0,}`
+    /// becomes `*` and `{1}` is left out. Patterns that only differ in these
+    /// ways print the same.
+    pub fn to_canonical_pattern(&self, language: Language) -> Result<String, Error> {
+        Printer::new(language, true).print(self)
+    }
+}
+
+/// A sequence of pieces that have to match one after the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    /// The pieces in this branch
+    pub pieces: Vec<Piece>,
+    /// The span of the branch
+    pub span: Span,
+}
+
+/// An atom with an optional quantifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    /// What is matched
+    pub atom: Atom,
+    /// How often it is matched; exactly once if there is no quantifier
+    pub quantifier: Option<Quantifier>,
+    /// The span of the atom and its quantifier
+    pub span: Span,
+}
+
+/// A quantifier such as `*` or `{2,3}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quantifier {
+    /// The kind of quantifier, as written
+    pub kind: QuantifierKind,
+    /// `false` if the quantifier is reluctant (followed by `?`)
+    pub greedy: bool,
+    /// The span of the quantifier, including a reluctant `?`
+    pub span: Span,
+}
+
+impl Quantifier {
+    /// The minimum number of repetitions.
+    pub fn min(&self) -> usize {
+        match self.kind {
+            QuantifierKind::ZeroOrOne | QuantifierKind::ZeroOrMore => 0,
+            QuantifierKind::OneOrMore => 1,
+            QuantifierKind::Exactly(n) | QuantifierKind::AtLeast(n) => n,
+            QuantifierKind::Between(min, _) => min,
+        }
+    }
+
+    /// The maximum number of repetitions, or `None` if unbounded.
+    pub fn max(&self) -> Option<usize> {
+        match self.kind {
+            QuantifierKind::ZeroOrOne => Some(1),
+            QuantifierKind::ZeroOrMore | QuantifierKind::OneOrMore => None,
+            QuantifierKind::AtLeast(_) => None,
+            QuantifierKind::Exactly(n) => Some(n),
+            QuantifierKind::Between(_, max) => Some(max),
+        }
+    }
+}
+
+/// The different ways to write a quantifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantifierKind {
+    /// `?`
+    ZeroOrOne,
+    /// `*`
+    ZeroOrMore,
+    /// `+`
+    OneOrMore,
+    /// `{n}`
+    Exactly(usize),
+    /// `{n,}`
+    AtLeast(usize),
+    /// `{n,m}`
+    Between(usize, usize),
+}
+
+/// The part of a piece that is matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Atom {
+    /// A single character, possibly escaped
+    Literal(Literal),
+    /// `.`
+    Any(Span),
+    /// `^` (XPath and ECMAScript only): the start of the input, or of a line
+    /// with the `m` flag
+    Start(Span),
+    /// `$` (XPath and ECMAScript only): the end of the input, or of a line
+    /// with the `m` flag
+    End(Span),
+    /// A character class escape such as `\d` or `\p{Lu}`
+    ClassEscape(ClassEscape),
+    /// A character class expression between square brackets
+    CharClass(CharClassExpr),
+    /// A parenthesized group
+    Group(Group),
+    /// A back-reference such as `\1` (XPath and ECMAScript only)
+    BackReference(BackReference),
+    /// `\b` (ECMAScript only): a position between a word character and
+    /// something else
+    WordBoundary(Span),
+    /// `\B` (ECMAScript only): a position that is not a word boundary
+    NotWordBoundary(Span),
+    /// A lookahead such as `(?=...)` (ECMAScript only)
+    Lookahead(Lookahead),
+}
+
+impl Atom {
+    /// The span of the atom.
+    pub fn span(&self) -> Span {
+        match self {
+            Atom::Literal(literal) => literal.span,
+            Atom::Any(span)
+            | Atom::Start(span)
+            | Atom::End(span)
+            | Atom::WordBoundary(span)
+            | Atom::NotWordBoundary(span) => *span,
+            Atom::ClassEscape(escape) => escape.span,
+            Atom::CharClass(class) => class.span,
+            Atom::Group(group) => group.span,
+            Atom::BackReference(back_reference) => back_reference.span,
+            Atom::Lookahead(lookahead) => lookahead.span,
+        }
+    }
+}
+
+/// A single character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Literal {
+    /// The character that is matched
+    pub c: char,
+    /// `true` if the character was written as an escape, such as `\n` or
+    /// `\*`
+    pub escaped: bool,
+    /// `true` for a character in a class that is followed by a hyphen that
+    /// doesn't start a range, as in `[a-]` or `[a-[b]]`. As in the Java
+    /// original, the `i` flag doesn't add its other cases.
+    pub case_sensitive: bool,
+    /// The span of the character or escape
+    pub span: Span,
+}
+
+/// A group between parentheses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    /// The number of the capturing group, or `None` for a non-capturing
+    /// group `(?:...)`
+    pub capture: Option<usize>,
+    /// The content of the group
+    pub expr: Expr,
+    /// The span of the group, including the parentheses
+    pub span: Span,
+}
+
+/// A lookahead, which matches the empty string if what follows matches its
+/// expression, without consuming it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookahead {
+    /// `true` for a negative lookahead `(?!...)`, which matches if what
+    /// follows doesn't match the expression
+    pub negated: bool,
+    /// The expression that is looked for
+    pub expr: Expr,
+    /// The span of the lookahead, including the parentheses
+    pub span: Span,
+}
+
+/// A back-reference to a capturing group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackReference {
+    /// The number of the group that is referred to
+    pub group: usize,
+    /// The span of the escape
+    pub span: Span,
+}
+
+/// An escape that matches a set of characters, such as `\s` or `\P{IsGreek}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassEscape {
+    /// The set of characters
+    pub kind: ClassEscapeKind,
+    /// `true` for the complemented form, such as `\S` or `\P{..}`
+    pub negated: bool,
+    /// The span of the escape
+    pub span: Span,
+}
+
+/// The sets of characters a class escape can refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassEscapeKind {
+    /// `\s`: whitespace
+    Space,
+    /// `\i`: characters that can start an XML name
+    NameStartChar,
+    /// `\c`: characters that can occur in an XML name
+    NameChar,
+    /// `\d`: decimal digits; only `0` to `9` in ECMAScript
+    Digit,
+    /// `\w`: word characters; only ASCII letters, digits and `_` in
+    /// ECMAScript
+    WordChar,
+    /// `\p{..}` with a Unicode general category such as `Lu`
+    Category(String),
+    /// `\p{Is..}` with a Unicode block name. This contains the name without
+    /// the `Is` prefix.
+    Block(String),
+}
+
+/// A character class expression such as `[a-z]` or `[^\d-[5]]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharClassExpr {
+    /// `true` if the class starts with `^`
+    pub negated: bool,
+    /// The characters, ranges and escapes in the class
+    pub items: Vec<ClassItem>,
+    /// A class that is subtracted from this one, as in `[a-z-[aeiou]]`
+    pub subtraction: Option<Box<CharClassExpr>>,
+    /// The span of the class, including the square brackets
+    pub span: Span,
+}
+
+/// An item in a character class expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassItem {
+    /// A single character
+    Char(Literal),
+    /// A range of characters such as `a-z`
+    Range(Literal, Literal),
+    /// A class escape such as `\d`
+    Escape(ClassEscape),
+}
+
+impl ClassItem {
+    /// The span of the item.
+    pub fn span(&self) -> Span {
+        match self {
+            ClassItem::Char(literal) => literal.span,
+            ClassItem::Range(start, end) => Span::new(start.span.start, end.span.end),
+            ClassItem::Escape(escape) => escape.span,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::OnceLock;
 
 use ahash::HashMap;
 use ahash::HashMapExt;
-use icu_collections::codepointinvlist::CodePointInversionListBuilder;
+use icu_collections::codepointinvlist::{CodePointInversionList, CodePointInversionListBuilder};
 use icu_properties::maps;
 use icu_properties::sets;
 use icu_properties::GeneralCategory;
@@ -68,11 +68,152 @@ fn get_category_group(property: &str) -> Result<GeneralCategoryGroup, Error> {
     })
 }
 
+// The long names and other aliases of the general categories, which
+// ECMAScript accepts besides the short names, from
+// PropertyValueAliases.txt.
+const CATEGORY_ALIASES: &[(&str, &str)] = &[
+    ("Letter", "L"),
+    ("Uppercase_Letter", "Lu"),
+    ("Lowercase_Letter", "Ll"),
+    ("Titlecase_Letter", "Lt"),
+    ("Modifier_Letter", "Lm"),
+    ("Other_Letter", "Lo"),
+    ("Mark", "M"),
+    ("Combining_Mark", "M"),
+    ("Nonspacing_Mark", "Mn"),
+    ("Spacing_Mark", "Mc"),
+    ("Enclosing_Mark", "Me"),
+    ("Number", "N"),
+    ("Decimal_Number", "Nd"),
+    ("digit", "Nd"),
+    ("Letter_Number", "Nl"),
+    ("Other_Number", "No"),
+    ("Punctuation", "P"),
+    ("punct", "P"),
+    ("Connector_Punctuation", "Pc"),
+    ("Dash_Punctuation", "Pd"),
+    ("Open_Punctuation", "Ps"),
+    ("Close_Punctuation", "Pe"),
+    ("Initial_Punctuation", "Pi"),
+    ("Final_Punctuation", "Pf"),
+    ("Other_Punctuation", "Po"),
+    ("Separator", "Z"),
+    ("Space_Separator", "Zs"),
+    ("Line_Separator", "Zl"),
+    ("Paragraph_Separator", "Zp"),
+    ("Symbol", "S"),
+    ("Math_Symbol", "Sm"),
+    ("Currency_Symbol", "Sc"),
+    ("Modifier_Symbol", "Sk"),
+    ("Other_Symbol", "So"),
+    ("Other", "C"),
+    ("Control", "Cc"),
+    ("cntrl", "Cc"),
+    ("Format", "Cf"),
+    ("Private_Use", "Co"),
+    ("Unassigned", "Cn"),
+];
+
+/// The short name of a general category given by its short name, long name
+/// or another alias, such as `Lu` for `Uppercase_Letter`.
+pub(crate) fn category_short_name(name: &str) -> Option<String> {
+    if get_category_group(name).is_ok() {
+        return Some(name.to_string());
+    }
+    CATEGORY_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, short)| short.to_string())
+}
+
+/// A description of a general category, as a noun that can be made plural
+/// by appending an "s".
+pub(crate) fn category_description(property: &str) -> Option<&'static str> {
+    Some(match property {
+        "L" => "letter",
+        "Lu" => "uppercase letter",
+        "Ll" => "lowercase letter",
+        "Lt" => "titlecase letter",
+        "Lm" => "modifier letter",
+        "Lo" => "other letter",
+        "M" => "mark",
+        "Mn" => "nonspacing mark",
+        "Mc" => "spacing mark",
+        "Me" => "enclosing mark",
+        "N" => "number",
+        "Nd" => "decimal digit",
+        "Nl" => "letter number",
+        "No" => "other number",
+        "P" => "punctuation character",
+        "Pc" => "connector punctuation character",
+        "Pd" => "dash",
+        "Ps" => "opening punctuation character",
+        "Pe" => "closing punctuation character",
+        "Pi" => "initial quote",
+        "Pf" => "final quote",
+        "Po" => "other punctuation character",
+        "Z" => "separator",
+        "Zs" => "space separator",
+        "Zl" => "line separator",
+        "Zp" => "paragraph separator",
+        "S" => "symbol",
+        "Sm" => "math symbol",
+        "Sc" => "currency symbol",
+        "Sk" => "modifier symbol",
+        "So" => "other symbol",
+        "C" => "other character",
+        "Cc" => "control character",
+        "Cf" => "format character",
+        "Co" => "private use character",
+        "Cn" => "unassigned code point",
+        _ => return None,
+    })
+}
+
+/// Character classes that have a name in a pattern, from the broadest to the
+/// narrowest, to describe a class in terms of them.
+pub(crate) fn named_classes() -> &'static [(String, CodePointInversionList<'static>)] {
+    static NAMED_CLASSES: OnceLock<Vec<(String, CodePointInversionList<'static>)>> =
+        OnceLock::new();
+    NAMED_CLASSES.get_or_init(|| {
+        let mut classes = vec![
+            ("\\w".to_string(), word_char().build()),
+            ("\\c".to_string(), name_char().build()),
+            ("\\i".to_string(), name_start_char().build()),
+        ];
+        let categories = [
+            "L", "M", "N", "P", "S", "Z", "C", "Lu", "Ll", "Lt", "Lm", "Lo", "Mn", "Mc", "Me",
+            "Nd", "Nl", "No", "Pc", "Pd", "Ps", "Pe", "Pi", "Pf", "Po", "Zs", "Zl", "Zp", "Sm",
+            "Sc", "Sk", "So", "Cc", "Cf", "Co", "Cn",
+        ];
+        for name in categories {
+            let builder = category_group(name).expect("known category");
+            // \d is the same as \p{Nd}, and shorter
+            let name = if name == "Nd" {
+                "\\d".to_string()
+            } else {
+                format!("\\p{{{}}}", name)
+            };
+            classes.push((name, builder.build()));
+        }
+        let mut space = CodePointInversionListBuilder::new();
+        for c in ['\t', '\n', '\r', ' '] {
+            space.add_char(c);
+        }
+        classes.push(("\\s".to_string(), space.build()));
+        classes
+    })
+}
+
 pub(crate) fn category_group(s: &str) -> Result<CodePointInversionListBuilder, Error> {
     let group = get_category_group(s)?;
     Ok(builder_for_group(group))
 }
 
+pub(crate) fn check_category(s: &str) -> Result<(), Error> {
+    get_category_group(s).map(|_| ())
+}
+
 fn builder_for_group(group: GeneralCategoryGroup) -> CodePointInversionListBuilder {
     let set = sets::for_general_category_group(group);
     let inv_list = set.to_code_point_inversion_list();
@@ -137,6 +278,33 @@ pub(crate) fn word_char() -> CodePointInversionListBuilder {
     builder
 }
 
+// `\d` in ECMAScript
+pub(crate) fn ascii_digit() -> CodePointInversionListBuilder {
+    let mut builder = CodePointInversionListBuilder::new();
+    builder.add_range(&('0'..='9'));
+    builder
+}
+
+// `\w` in ECMAScript
+pub(crate) fn ascii_word_char() -> CodePointInversionListBuilder {
+    let mut builder = ascii_digit();
+    builder.add_range(&('A'..='Z'));
+    builder.add_range(&('a'..='z'));
+    builder.add_char('_');
+    builder
+}
+
+// `\s` in ECMAScript: white space and line terminators
+pub(crate) fn ecmascript_space() -> CodePointInversionListBuilder {
+    let mut builder = builder_for_group(GeneralCategoryGroup::SpaceSeparator);
+    for c in [
+        '\t', '\n', '\u{B}', '\u{C}', '\r', '\u{2028}', '\u{2029}', '\u{FEFF}',
+    ] {
+        builder.add_char(c);
+    }
+    builder
+}
+
 #[derive(Debug)]
 pub(crate) struct BlockLookup {
     blocks: HashMap<String, &'static block::Block>,
@@ -178,6 +346,29 @@ fn block_lookup() -> &'static BlockLookup {
     BLOCK_LOOKUP.get_or_init(BlockLookup::new)
 }
 
+// Block names from CompatBlocks.txt, which only exist for backward
+// compatibility, with the name that replaces them.
+const DEPRECATED_BLOCKS: &[(&str, &str)] = &[
+    ("Greek", "GreekandCoptic"),
+    (
+        "CombiningMarksforSymbols",
+        "CombiningDiacriticalMarksforSymbols",
+    ),
+];
+
+/// If this block name is a deprecated alias, return the current name.
+pub(crate) fn deprecated_block(name: &str) -> Option<&'static str> {
+    DEPRECATED_BLOCKS
+        .iter()
+        .find(|(deprecated, _)| *deprecated == name)
+        .map(|(_, current)| *current)
+}
+
+/// The block with this name, if it is a single known block.
+pub(crate) fn block_info(name: &str) -> Option<&'static block::Block> {
+    block_lookup().lookup(name).ok()
+}
+
 pub(crate) fn block(name: &str) -> Result<CodePointInversionListBuilder, Error> {
     // backward compatibility, see XSD 1.1 part 2, section G 4.2.3
     // the other backwards compatibility blocks are handled in the block lookup,
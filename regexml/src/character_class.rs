@@ -1,6 +1,9 @@
 use icu_collections::codepointinvlist::{CodePointInversionList, CodePointInversionListBuilder};
 
+use crate::category;
+
 const IS_DISJOINT_CHECK_THRESHOLD: usize = 100;
+const ALL_CODE_POINTS: usize = 0x110000;
 
 #[derive(Debug, Clone)]
 pub(crate) struct CharacterClass(CodePointInversionList<'static>);
@@ -23,10 +26,61 @@ impl CharacterClass {
         Self(CodePointInversionList::all())
     }
 
+    /// A short description of the characters in this class, such as
+    /// `[\d_a-f]` or `[^\n\r]`, for tracing and dumps. Parts of the class
+    /// that are a class escape or category are shown by name. Long classes
+    /// are cut off.
+    pub(crate) fn summary(&self) -> String {
+        let size = self.0.size();
+        if let Some((name, _)) = category::named_classes()
+            .iter()
+            .find(|(_, named)| *named == self.0)
+        {
+            name.clone()
+        } else if size == 0 {
+            "[]".to_string()
+        } else if size == ALL_CODE_POINTS {
+            "any".to_string()
+        } else if size > ALL_CODE_POINTS / 2 {
+            let mut complement = CodePointInversionListBuilder::new();
+            complement.add_set(&self.0);
+            complement.complement();
+            format!("[^{}]", summary_items(complement.build()))
+        } else {
+            format!("[{}]", summary_items(self.0.clone()))
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.size() == 0
+    }
+
     pub(crate) fn contains(&self, c: char) -> bool {
         self.0.contains(c)
     }
 
+    /// Whether any character is in both classes. Unlike `is_disjoint` this
+    /// is exact.
+    pub(crate) fn intersects(&self, other: &Self) -> bool {
+        let other_ranges = other.0.iter_ranges().collect::<Vec<_>>();
+        self.0.iter_ranges().any(|range| {
+            // the last range in other that starts before this one ends
+            let i = other_ranges.partition_point(|other| other.start() <= range.end());
+            i > 0 && other_ranges[i - 1].end() >= range.start()
+        })
+    }
+
+    /// Whether every character of the other class is in this one.
+    pub(crate) fn contains_class(&self, other: &Self) -> bool {
+        let ranges = self.0.iter_ranges().collect::<Vec<_>>();
+        other.0.iter_ranges().all(|range| {
+            // the last range in this class that starts at or before the
+            // range; adjacent ranges are merged, so it has to hold all of it
+            let i = ranges.partition_point(|own| own.start() <= range.start());
+            i > 0 && ranges[i - 1].end() >= range.end()
+        })
+    }
+
     /// Gives a hint whether the character class is disjoint with
     /// another.
     ///
@@ -166,12 +220,72 @@ impl CharacterClassBuilder {
         }
     }
 
+    pub(crate) fn is_empty(&self) -> bool {
+        match self {
+            CharacterClassBuilder::Char(_) => false,
+            CharacterClassBuilder::CodePointInversionListBuilder(builder) => builder.is_empty(),
+        }
+    }
+
     pub(crate) fn build(self) -> CharacterClass {
         match self {
-            CharacterClassBuilder::Char(c) => CharacterClassBuilder::from_char(c).build(),
+            CharacterClassBuilder::Char(c) => {
+                let mut builder = CodePointInversionListBuilder::new();
+                builder.add_char(c);
+                CharacterClass(builder.build())
+            }
             CharacterClassBuilder::CodePointInversionListBuilder(builder) => {
                 CharacterClass(builder.build())
             }
         }
     }
 }
+
+// The named classes that make up the class, followed by what remains.
+fn summary_items(mut remaining: CodePointInversionList<'static>) -> String {
+    const MAX_RANGES: usize = 8;
+    let mut summary = String::new();
+    for (name, named) in category::named_classes() {
+        if named.size() > 1 && is_subset(named, &remaining) {
+            summary.push_str(name);
+            let mut builder = CodePointInversionListBuilder::new();
+            builder.add_set(&remaining);
+            builder.remove_set(named);
+            remaining = builder.build();
+        }
+    }
+    for (i, range) in remaining.iter_ranges().enumerate() {
+        if i == MAX_RANGES {
+            summary.push_str("...");
+            break;
+        }
+        let (start, end) = (*range.start(), *range.end());
+        summary.push_str(&summary_char(start));
+        if end > start {
+            summary.push('-');
+            summary.push_str(&summary_char(end));
+        }
+    }
+    summary
+}
+
+fn is_subset(a: &CodePointInversionList, b: &CodePointInversionList) -> bool {
+    let b_ranges = b.iter_ranges().collect::<Vec<_>>();
+    a.iter_ranges().all(|range| {
+        // the last range in b that starts at or before this one
+        let i = b_ranges.partition_point(|b_range| b_range.start() <= range.start());
+        i > 0 && b_ranges[i - 1].end() >= range.end()
+    })
+}
+
+// a code point in a class summary; only printable ASCII is shown as is
+fn summary_char(code_point: u32) -> String {
+    match char::from_u32(code_point) {
+        Some('\n') => "\\n".to_string(),
+        Some('\r') => "\\r".to_string(),
+        Some('\t') => "\\t".to_string(),
+        Some(c @ ('\\' | '-' | '[' | ']' | '^')) => format!("\\{}", c),
+        Some(c) if c.is_ascii_graphic() || c == ' ' => c.to_string(),
+        _ => format!("\\u{{{:04X}}}", code_point),
+    }
+}
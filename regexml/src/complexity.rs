@@ -0,0 +1,426 @@
+use std::fmt;
+
+use icu_collections::codepointinvlist::CodePointInversionListBuilder;
+
+use crate::{
+    ast::{Atom, Branch, Expr, Piece},
+    character_class::CharacterClass,
+    diagnostics::Span,
+    operation::OperationControl,
+    re_compiler::ReCompiler,
+    re_flags::ReFlags,
+};
+
+/// How much time matching a regular expression can take, in the worst case,
+/// as a function of the length of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Risk {
+    /// No structures were found that make the matcher backtrack excessively.
+    Low,
+    /// The matcher can take time polynomial in the length of the input, such
+    /// as quadratic.
+    Polynomial,
+    /// The matcher can take time exponential in the length of the input.
+    Exponential,
+}
+
+/// The result of [`Regex::analyze_complexity`](crate::Regex::analyze_complexity).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Complexity {
+    /// The highest risk of all issues, or [`Risk::Low`] if there are none
+    pub risk: Risk,
+    /// The structures in the pattern that can cause excessive backtracking
+    pub issues: Vec<ComplexityIssue>,
+}
+
+/// A structure in a pattern that can cause excessive backtracking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComplexityIssue {
+    /// What the structure is
+    pub kind: ComplexityKind,
+    /// How bad it is
+    pub risk: Risk,
+    /// The part of the pattern that has the structure
+    pub span: Span,
+    /// The parts within it that can match the same text
+    pub related: Vec<Span>,
+}
+
+/// The different structures that can cause excessive backtracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplexityKind {
+    /// A repeated group consists of another repeat, so text can be divided
+    /// over the repetitions in many ways, as in `(a+)+`. This includes a
+    /// repeat that can also match the rest of the group, as in `(.*a){20}`.
+    NestedQuantifier,
+    /// A repeated group has alternatives that can match the same text, as in
+    /// `(a|\w)*`.
+    OverlappingAlternation,
+    /// Two repeats follow each other and can match the same characters, as
+    /// in `\d+\d*`.
+    AdjacentQuantifiers,
+}
+
+impl fmt::Display for ComplexityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComplexityKind::NestedQuantifier => {
+                write!(f, "Repeat consists of another repeat")
+            }
+            ComplexityKind::OverlappingAlternation => {
+                write!(f, "Repeat of alternatives that can match the same text")
+            }
+            ComplexityKind::AdjacentQuantifiers => {
+                write!(f, "Adjacent repeats that can match the same characters")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ComplexityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+// Looks for backtracking hazards in the syntax tree. The tree has the spans
+// to report; the character classes and lengths are taken from the
+// operations the compiler makes of it.
+pub(crate) struct ComplexityAnalyzer {
+    compiler: ReCompiler,
+    case_blind: bool,
+    issues: Vec<ComplexityIssue>,
+}
+
+impl ComplexityAnalyzer {
+    pub(crate) fn new(re_flags: &ReFlags) -> Self {
+        Self {
+            compiler: ReCompiler::new(Vec::new(), re_flags.clone()),
+            case_blind: re_flags.is_case_independent(),
+            issues: Vec::new(),
+        }
+    }
+
+    pub(crate) fn analyze(mut self, expr: &Expr) -> Complexity {
+        self.expr(expr);
+        let risk = self
+            .issues
+            .iter()
+            .map(|issue| issue.risk)
+            .max()
+            .unwrap_or(Risk::Low);
+        Complexity {
+            risk,
+            issues: self.issues,
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        for branch in &expr.branches {
+            self.adjacent_repeats(branch);
+            for piece in &branch.pieces {
+                self.piece(piece);
+            }
+        }
+    }
+
+    fn piece(&mut self, piece: &Piece) {
+        if let (Atom::Group(group), Some(quantifier)) = (&piece.atom, &piece.quantifier) {
+            if repeats(piece) {
+                // bounded repeats limit the number of ways to divide the text
+                let risk = if quantifier.max().is_none() {
+                    Risk::Exponential
+                } else {
+                    Risk::Polynomial
+                };
+                let adjacent = group
+                    .expr
+                    .branches
+                    .iter()
+                    .find_map(|branch| self.overlapping_repeats(branch).first().copied());
+                if let Some(inner) = self.sole_repeat(&group.expr) {
+                    self.issue(
+                        ComplexityKind::NestedQuantifier,
+                        risk,
+                        piece.span,
+                        vec![inner],
+                    );
+                } else if let Some((first, second)) = adjacent {
+                    // each repetition can divide its text between the two
+                    self.issue(
+                        ComplexityKind::NestedQuantifier,
+                        risk,
+                        piece.span,
+                        vec![first, second],
+                    );
+                } else if let Some((inner, rest)) = self.absorbing_repeat(&group.expr) {
+                    // where one repetition ends and the next starts is up to
+                    // the inner repeat
+                    self.issue(
+                        ComplexityKind::NestedQuantifier,
+                        risk,
+                        piece.span,
+                        vec![inner, rest],
+                    );
+                } else if let Some((first, second, same_text)) =
+                    self.overlapping_branches(&group.expr)
+                {
+                    let risk = if same_text { risk } else { Risk::Polynomial };
+                    self.issue(
+                        ComplexityKind::OverlappingAlternation,
+                        risk,
+                        piece.span,
+                        vec![first, second],
+                    );
+                }
+            }
+        }
+        match &piece.atom {
+            Atom::Group(group) => self.expr(&group.expr),
+            Atom::Lookahead(lookahead) => self.expr(&lookahead.expr),
+            _ => {}
+        }
+    }
+
+    fn issue(&mut self, kind: ComplexityKind, risk: Risk, span: Span, related: Vec<Span>) {
+        self.issues.push(ComplexityIssue {
+            kind,
+            risk,
+            span,
+            related,
+        });
+    }
+
+    fn adjacent_repeats(&mut self, branch: &Branch) {
+        for (first, second) in self.overlapping_repeats(branch) {
+            self.issue(
+                ComplexityKind::AdjacentQuantifiers,
+                Risk::Polynomial,
+                Span::new(first.start, second.end),
+                vec![first, second],
+            );
+        }
+    }
+
+    // Two repeats in a row, with only optional pieces between them, that
+    // can match the same characters: the text can be divided between them
+    // in many ways.
+    fn overlapping_repeats(&mut self, branch: &Branch) -> Vec<(Span, Span)> {
+        let mut found = Vec::new();
+        let mut previous: Option<(Span, CharacterClass)> = None;
+        for piece in &branch.pieces {
+            let unbounded = piece
+                .quantifier
+                .as_ref()
+                .is_some_and(|quantifier| quantifier.max().is_none());
+            if unbounded && self.atom_min_length(&piece.atom) > 0 {
+                let chars = self.all_chars(&piece.atom);
+                if let Some((previous_span, previous_chars)) = &previous {
+                    if previous_chars.intersects(&chars) {
+                        found.push((*previous_span, piece.span));
+                    }
+                }
+                previous = Some((piece.span, chars));
+            } else if self.min_length(piece) > 0 {
+                previous = None;
+            }
+        }
+        found
+    }
+
+    // A repeat that can make up a whole match of the expression by itself,
+    // everything else being optional.
+    fn sole_repeat(&mut self, expr: &Expr) -> Option<Span> {
+        for branch in &expr.branches {
+            for (i, piece) in branch.pieces.iter().enumerate() {
+                let others_optional = branch
+                    .pieces
+                    .iter()
+                    .enumerate()
+                    .all(|(j, other)| j == i || self.min_length(other) == 0);
+                if !others_optional {
+                    continue;
+                }
+                if repeats(piece) && self.atom_min_length(&piece.atom) > 0 {
+                    return Some(piece.span);
+                }
+                if let (Atom::Group(group), None) = (&piece.atom, &piece.quantifier) {
+                    if let Some(span) = self.sole_repeat(&group.expr) {
+                        return Some(span);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // A repeat that can match everything else the expression needs, as `.*`
+    // can in `.*a`: a text that the expression matches a number of times in
+    // a row can then be divided over those matches in many ways. Returns
+    // the repeat and the first other piece that needs text.
+    fn absorbing_repeat(&mut self, expr: &Expr) -> Option<(Span, Span)> {
+        for branch in &expr.branches {
+            for (i, piece) in branch.pieces.iter().enumerate() {
+                let unbounded = piece
+                    .quantifier
+                    .as_ref()
+                    .is_some_and(|quantifier| quantifier.max().is_none());
+                if !unbounded || self.atom_min_length(&piece.atom) == 0 {
+                    continue;
+                }
+                let chars = self.all_chars(&piece.atom);
+                let mut rest = None;
+                let mut absorbed = true;
+                for (j, other) in branch.pieces.iter().enumerate() {
+                    if j == i || self.min_length(other) == 0 {
+                        continue;
+                    }
+                    rest = rest.or(Some(other.span));
+                    let other_chars = self.all_chars(&other.atom);
+                    absorbed &= chars.contains_class(&other_chars);
+                }
+                if let (Some(rest), true) = (rest, absorbed) {
+                    return Some((piece.span, rest));
+                }
+            }
+        }
+        None
+    }
+
+    // Two alternatives that can start with the same character. Also returns
+    // whether they can match exactly the same text, which is when they
+    // consist of single characters that overlap one by one.
+    fn overlapping_branches(&mut self, expr: &Expr) -> Option<(Span, Span, bool)> {
+        let mut found = None;
+        for (i, first) in expr.branches.iter().enumerate() {
+            for second in &expr.branches[i + 1..] {
+                let first_chars = self.first_chars(first);
+                let second_chars = self.first_chars(second);
+                if !first_chars.intersects(&second_chars) {
+                    continue;
+                }
+                let same_text = self.same_text(first, second);
+                if same_text {
+                    return Some((first.span, second.span, true));
+                }
+                found = found.or(Some((first.span, second.span, false)));
+            }
+        }
+        found
+    }
+
+    fn same_text(&mut self, first: &Branch, second: &Branch) -> bool {
+        match (self.single_chars(first), self.single_chars(second)) {
+            (Some(first), Some(second)) => {
+                first.len() == second.len()
+                    && first.iter().zip(&second).all(|(a, b)| a.intersects(b))
+            }
+            _ => false,
+        }
+    }
+
+    // the classes of a branch of unquantified single characters
+    fn single_chars(&mut self, branch: &Branch) -> Option<Vec<CharacterClass>> {
+        branch
+            .pieces
+            .iter()
+            .map(|piece| match (&piece.atom, &piece.quantifier) {
+                (
+                    Atom::Literal(_) | Atom::Any(_) | Atom::ClassEscape(_) | Atom::CharClass(_),
+                    None,
+                ) => Some(self.atom_chars(&piece.atom)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // the characters a match of the branch can start with
+    fn first_chars(&mut self, branch: &Branch) -> CharacterClass {
+        let mut builder = CodePointInversionListBuilder::new();
+        for piece in &branch.pieces {
+            let chars = match &piece.atom {
+                Atom::Start(_)
+                | Atom::End(_)
+                | Atom::WordBoundary(_)
+                | Atom::NotWordBoundary(_)
+                | Atom::Lookahead(_) => continue,
+                Atom::Group(group) => {
+                    let mut group_builder = CodePointInversionListBuilder::new();
+                    for branch in &group.expr.branches {
+                        group_builder
+                            .add_set(self.first_chars(branch).as_code_point_inversion_list());
+                    }
+                    CharacterClass::new(group_builder.build())
+                }
+                atom => self.atom_chars(atom),
+            };
+            builder.add_set(chars.as_code_point_inversion_list());
+            if self.min_length(piece) > 0 {
+                break;
+            }
+        }
+        CharacterClass::new(builder.build())
+    }
+
+    // all characters that a match of the atom can contain
+    fn all_chars(&mut self, atom: &Atom) -> CharacterClass {
+        match atom {
+            Atom::Start(_)
+            | Atom::End(_)
+            | Atom::WordBoundary(_)
+            | Atom::NotWordBoundary(_)
+            | Atom::Lookahead(_) => CharacterClass::empty(),
+            Atom::Group(group) => {
+                let mut builder = CodePointInversionListBuilder::new();
+                for branch in &group.expr.branches {
+                    for piece in &branch.pieces {
+                        builder.add_set(self.all_chars(&piece.atom).as_code_point_inversion_list());
+                    }
+                }
+                CharacterClass::new(builder.build())
+            }
+            atom => self.atom_chars(atom),
+        }
+    }
+
+    // the characters of a single character atom; a back-reference can be
+    // anything
+    fn atom_chars(&mut self, atom: &Atom) -> CharacterClass {
+        match atom {
+            Atom::BackReference(_) => CharacterClass::all(),
+            atom => {
+                let operation = self.compiler.atom(atom);
+                let chars = operation.get_initial_character_class(false);
+                if !self.case_blind {
+                    return chars;
+                }
+                // the case-blind class of a character only has its case
+                // variants
+                let mut builder = CodePointInversionListBuilder::new();
+                builder.add_set(chars.as_code_point_inversion_list());
+                builder.add_set(
+                    operation
+                        .get_initial_character_class(true)
+                        .as_code_point_inversion_list(),
+                );
+                CharacterClass::new(builder.build())
+            }
+        }
+    }
+
+    fn min_length(&mut self, piece: &Piece) -> usize {
+        self.compiler.piece(piece).get_minimum_match_length()
+    }
+
+    fn atom_min_length(&mut self, atom: &Atom) -> usize {
+        self.compiler.atom(atom).get_minimum_match_length()
+    }
+}
+
+// whether the piece can match its atom more than once
+fn repeats(piece: &Piece) -> bool {
+    piece
+        .quantifier
+        .as_ref()
+        .is_some_and(|quantifier| quantifier.max().is_none_or(|max| max > 1))
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+
+use icu_collections::codepointinvlist::CodePointInversionListBuilder;
+
+use crate::character_class::{CharacterClass, CharacterClassBuilder};
+
+/// Why a string doesn't match a regular expression, as returned by
+/// [`Regex::diagnose`](crate::Regex::diagnose).
+///
+/// This describes the furthest position in the string that any attempt to
+/// match reached, and what would have been accepted there. Its
+/// [`Display`](fmt::Display) gives a message such as
+/// `at position 5 expected \d but found 'x'`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// The furthest position reached, in characters (Unicode code points)
+    /// from the start of the string
+    pub position: usize,
+    /// The character at that position, or `None` at the end of the string
+    pub found: Option<char>,
+    /// A summary of the characters that would have been accepted at that
+    /// position, such as `\d` or `[a-f]`, or `None` if no character would
+    pub expected: Option<String>,
+    /// Whether the end of the string would have been accepted at that
+    /// position
+    pub end_expected: bool,
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at position {} expected ", self.position)?;
+        match (&self.expected, self.end_expected) {
+            (Some(expected), true) => write!(f, "{} or the end of the string", expected)?,
+            (Some(expected), false) => write!(f, "{}", expected)?,
+            (None, true) => write!(f, "the end of the string")?,
+            (None, false) => write!(f, "nothing")?,
+        }
+        match self.found {
+            Some(c) => write!(f, " but found {:?}", c),
+            None => write!(f, " but found the end of the string"),
+        }
+    }
+}
+
+// What the operations that failed furthest into the string would have
+// accepted, collected by the matcher while diagnosing.
+#[derive(Debug)]
+pub(crate) struct Expectations {
+    position: usize,
+    classes: Vec<CharacterClass>,
+    end: bool,
+}
+
+impl Expectations {
+    pub(crate) fn new() -> Self {
+        Self {
+            position: 0,
+            classes: Vec::new(),
+            end: false,
+        }
+    }
+
+    // forgets what was expected before a position further into the string;
+    // returns false if the position is before the furthest one
+    fn reach(&mut self, position: usize) -> bool {
+        if position > self.position {
+            self.position = position;
+            self.classes.clear();
+            self.end = false;
+        }
+        position == self.position
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    pub(crate) fn char(&mut self, position: usize, c: char) {
+        if self.reach(position) {
+            self.classes
+                .push(CharacterClassBuilder::from_char(c).build());
+        }
+    }
+
+    pub(crate) fn class(&mut self, position: usize, class: &CharacterClass) {
+        if self.reach(position) {
+            self.classes.push(class.clone());
+        }
+    }
+
+    pub(crate) fn end(&mut self, position: usize) {
+        if self.reach(position) {
+            self.end = true;
+        }
+    }
+
+    pub(crate) fn diagnosis(&self, found: Option<char>) -> Diagnosis {
+        let mut builder = CodePointInversionListBuilder::new();
+        for class in &self.classes {
+            builder.add_set(class.as_code_point_inversion_list());
+        }
+        let expected = CharacterClass::new(builder.build());
+        Diagnosis {
+            position: self.position,
+            found,
+            expected: (!expected.is_empty()).then(|| expected.summary()),
+            end_expected: self.end,
+        }
+    }
+}
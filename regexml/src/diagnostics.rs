@@ -0,0 +1,94 @@
+use std::fmt;
+
+/// A span of characters in a regular expression pattern.
+///
+/// Offsets are counted in characters (Unicode code points) from the start of
+/// the pattern as it was passed in, so they are not affected by whitespace
+/// stripped because of the `x` flag. `end` is exclusive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Span {
+    /// The offset of the first character in the span
+    pub start: usize,
+    /// The offset just past the last character in the span
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a new span.
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+/// A non-fatal problem found while compiling a regular expression.
+///
+/// Warnings are returned by [`Regex::compile_with_diagnostics`](crate::Regex::compile_with_diagnostics).
+/// The regular expression compiles regardless.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Warning {
+    /// What the warning is about
+    pub kind: WarningKind,
+    /// The part of the pattern the warning applies to
+    pub span: Span,
+}
+
+/// The different kinds of compiler warnings.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum WarningKind {
+    /// A block escape such as `\p{IsFoo}` names a block that doesn't exist.
+    /// This is only a warning with the `;k` flag; the escape then matches
+    /// any character.
+    UnknownBlock(String),
+    /// A block escape uses a name that only exists for backwards
+    /// compatibility with older versions of Unicode (`CompatBlocks.txt`).
+    /// This contains the name that was used, and the current name.
+    DeprecatedBlock(String, String),
+    /// With the `x` flag, whitespace was stripped between a backslash and
+    /// the escaped character.
+    WhitespaceInEscape,
+    /// A quantifier such as `{0,0}` only allows zero occurrences, so the
+    /// expression it applies to never matches anything.
+    ZeroRepetitions,
+    /// A character class subtraction leaves no characters at all, so the
+    /// character class can never match.
+    EmptySubtraction,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::UnknownBlock(name) => {
+                write!(
+                    f,
+                    "Unknown Unicode block: {}; it matches any character",
+                    name
+                )
+            }
+            WarningKind::DeprecatedBlock(name, current) => {
+                write!(
+                    f,
+                    "Unicode block name {} is deprecated; use {} instead",
+                    name, current
+                )
+            }
+            WarningKind::WhitespaceInEscape => {
+                write!(f, "Whitespace inside an escape is ignored")
+            }
+            WarningKind::ZeroRepetitions => {
+                write!(
+                    f,
+                    "Quantifier allows no repetitions; expression is never matched"
+                )
+            }
+            WarningKind::EmptySubtraction => {
+                write!(f, "Character class subtraction leaves an empty class")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
@@ -0,0 +1,475 @@
+use crate::{
+    ast::{Atom, Branch, CharClassExpr, ClassEscape, ClassEscapeKind, ClassItem, Expr, Piece},
+    ast::{Literal, Quantifier, QuantifierKind},
+    category,
+    diagnostics::Span,
+    re_flags::{Language, ReFlags},
+};
+
+/// A natural-language description of part of a regular expression.
+///
+/// Explanations form a tree that follows the structure of the pattern. The
+/// text of a node describes everything below it, so the text of the root
+/// describes the whole pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// The description
+    pub text: String,
+    /// The part of the pattern that is described
+    pub span: Span,
+    /// Descriptions of the parts this is made of
+    pub children: Vec<Explanation>,
+}
+
+impl Explanation {
+    fn new(text: String, span: Span) -> Self {
+        Self {
+            text,
+            span,
+            children: Vec::new(),
+        }
+    }
+
+    fn with_children(text: String, span: Span, children: Vec<Explanation>) -> Self {
+        Self {
+            text,
+            span,
+            children,
+        }
+    }
+}
+
+// How to describe something that can be repeated: one of it, or a number of
+// them.
+struct Noun {
+    // as in "a decimal digit"
+    singular: String,
+    // as in "one decimal digit", for counts
+    one: String,
+    // as in "decimal digits"
+    plural: String,
+    children: Vec<Explanation>,
+}
+
+impl Noun {
+    fn new(singular: impl Into<String>, plural: impl Into<String>) -> Self {
+        let singular = singular.into();
+        let one = match singular
+            .strip_prefix("a ")
+            .or_else(|| singular.strip_prefix("an "))
+        {
+            Some(rest) => format!("one {}", rest),
+            None => format!("one repetition of {}", singular),
+        };
+        Self {
+            singular,
+            one,
+            plural: plural.into(),
+            children: Vec::new(),
+        }
+    }
+
+    // a noun for a kind of character, such as "decimal digit"
+    fn character(description: &str) -> Self {
+        Self::new(
+            format!("{} {}", article(description), description),
+            format!("{}s", description),
+        )
+    }
+}
+
+pub(crate) struct Explainer<'a> {
+    re_flags: &'a ReFlags,
+}
+
+impl<'a> Explainer<'a> {
+    pub(crate) fn new(re_flags: &'a ReFlags) -> Self {
+        Self { re_flags }
+    }
+
+    pub(crate) fn explain(&self, expr: &Expr) -> Explanation {
+        let mut explanation = self.expr(expr);
+        if self.re_flags.is_case_independent() {
+            explanation.text.push_str(", ignoring case");
+        }
+        explanation
+    }
+
+    fn expr(&self, expr: &Expr) -> Explanation {
+        if expr.branches.len() == 1 {
+            return self.branch(&expr.branches[0]);
+        }
+        let children = expr
+            .branches
+            .iter()
+            .map(|branch| self.branch(branch))
+            .collect::<Vec<_>>();
+        let text = format!(
+            "either {}",
+            children
+                .iter()
+                .map(|child| child.text.as_str())
+                .collect::<Vec<_>>()
+                .join(", or ")
+        );
+        Explanation::with_children(text, expr.span, children)
+    }
+
+    fn branch(&self, branch: &Branch) -> Explanation {
+        let mut children = Vec::new();
+        let mut pieces = branch.pieces.iter().peekable();
+        while let Some(piece) = pieces.next() {
+            // describe unquantified characters in a row as a single text
+            if let (Atom::Literal(first), None) = (&piece.atom, &piece.quantifier) {
+                let mut text = vec![first.c];
+                let mut span = first.span;
+                while let Some(Piece {
+                    atom: Atom::Literal(literal),
+                    quantifier: None,
+                    ..
+                }) = pieces.peek()
+                {
+                    text.push(literal.c);
+                    span.end = literal.span.end;
+                    pieces.next();
+                }
+                let text = if text.len() == 1 {
+                    character(text[0])
+                } else {
+                    format!("the text \"{}\"", text.iter().collect::<String>())
+                };
+                children.push(Explanation::new(text, span));
+            } else {
+                children.push(self.piece(piece));
+            }
+        }
+        match children.len() {
+            0 => Explanation::new("the empty string".to_string(), branch.span),
+            1 => children.remove(0),
+            _ => {
+                let text = children
+                    .iter()
+                    .map(|child| child.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", then ");
+                Explanation::with_children(text, branch.span, children)
+            }
+        }
+    }
+
+    fn piece(&self, piece: &Piece) -> Explanation {
+        let noun = self.atom(&piece.atom);
+        let text = match &piece.quantifier {
+            None => noun.singular,
+            Some(quantifier) => {
+                let mut text = quantified(quantifier, &noun);
+                if !quantifier.greedy {
+                    text.push_str(" (as few as possible)");
+                }
+                text
+            }
+        };
+        Explanation::with_children(text, piece.span, noun.children)
+    }
+
+    fn atom(&self, atom: &Atom) -> Noun {
+        match atom {
+            Atom::Literal(literal) => {
+                let mut noun = Noun::new(
+                    character(literal.c),
+                    format!("{} characters", quoted(literal.c)),
+                );
+                noun.one = format!("one {} character", quoted(literal.c));
+                noun
+            }
+            Atom::Any(_) => {
+                let mut noun = if self.re_flags.is_single_line()
+                    || self.re_flags.language() == Language::FullTextWildcards
+                {
+                    Noun::new("any character", "characters of any kind")
+                } else if self.ecmascript() {
+                    Noun::new(
+                        "any character except a line terminator",
+                        "characters other than line terminators",
+                    )
+                } else {
+                    Noun::new(
+                        "any character except a newline or carriage return",
+                        "characters other than newline and carriage return",
+                    )
+                };
+                noun.one = format!("one {}", noun.singular.trim_start_matches("any "));
+                noun
+            }
+            Atom::Start(_) => {
+                let start = if self.re_flags.is_multi_line() {
+                    "the start of a line"
+                } else {
+                    "the start of the input"
+                };
+                Noun::new(start, format!("repetitions of {}", start))
+            }
+            Atom::End(_) => {
+                let end = if self.re_flags.is_multi_line() {
+                    "the end of a line"
+                } else {
+                    "the end of the input"
+                };
+                Noun::new(end, format!("repetitions of {}", end))
+            }
+            Atom::ClassEscape(escape) => self.class_escape(escape),
+            Atom::CharClass(class) => {
+                let (description, children) = self.char_class(class);
+                let mut noun = Noun::new(
+                    format!("a character {}", description),
+                    format!("characters {}", description),
+                );
+                noun.children = children;
+                noun
+            }
+            Atom::Group(group) => {
+                let inner = self.expr(&group.expr);
+                let name = match group.capture {
+                    Some(number) => format!("group {}", number),
+                    None => "a group".to_string(),
+                };
+                let mut noun = Noun::new(
+                    format!("{} ({})", name, inner.text),
+                    format!("repetitions of {} ({})", name, inner.text),
+                );
+                noun.children = vec![inner];
+                noun
+            }
+            Atom::BackReference(back_reference) => Noun::new(
+                format!("the same text as matched by group {}", back_reference.group),
+                format!(
+                    "repetitions of the text matched by group {}",
+                    back_reference.group
+                ),
+            ),
+            Atom::WordBoundary(_) => Noun::new("a word boundary", "word boundaries"),
+            Atom::NotWordBoundary(_) => Noun::new(
+                "a position that is not a word boundary",
+                "positions that are not word boundaries",
+            ),
+            Atom::Lookahead(lookahead) => {
+                let inner = self.expr(&lookahead.expr);
+                let followed = if lookahead.negated {
+                    "not followed by"
+                } else {
+                    "followed by"
+                };
+                let mut noun = Noun::new(
+                    format!("a position {} ({})", followed, inner.text),
+                    format!("positions {} ({})", followed, inner.text),
+                );
+                noun.children = vec![inner];
+                noun
+            }
+        }
+    }
+
+    fn ecmascript(&self) -> bool {
+        self.re_flags.language() == Language::EcmaScript
+    }
+
+    // ECMAScript has other classes for some escapes
+    fn class_escape(&self, escape: &ClassEscape) -> Noun {
+        let (singular, plural) = match &escape.kind {
+            ClassEscapeKind::Space if self.ecmascript() => (
+                "a whitespace character or line terminator",
+                "whitespace characters and line terminators",
+            ),
+            ClassEscapeKind::Digit if self.ecmascript() => {
+                ("an ASCII digit (0\u{2013}9)", "ASCII digits (0\u{2013}9)")
+            }
+            ClassEscapeKind::WordChar if self.ecmascript() => (
+                "a word character (ASCII letter, digit or '_')",
+                "word characters (ASCII letters, digits and '_')",
+            ),
+            _ => return class_escape(escape),
+        };
+        if escape.negated {
+            Noun::new(
+                format!("a character that is not {}", singular),
+                format!("characters that are not {}", plural),
+            )
+        } else {
+            Noun::new(singular, plural)
+        }
+    }
+
+    // Describe which characters are in a character class, as in "from A–Z".
+    fn char_class(&self, class: &CharClassExpr) -> (String, Vec<Explanation>) {
+        let mut children = class
+            .items
+            .iter()
+            .map(|item| {
+                let text = match item {
+                    ClassItem::Char(literal) => display(literal),
+                    ClassItem::Range(start, end) => {
+                        format!("{}\u{2013}{}", display(start), display(end))
+                    }
+                    ClassItem::Escape(escape) => self.class_escape(escape).plural,
+                };
+                Explanation::new(text, item.span())
+            })
+            .collect::<Vec<_>>();
+        let items = list(children.iter().map(|child| child.text.as_str()), "or");
+        let mut description = if class.negated {
+            format!("not from {}", items)
+        } else {
+            format!("from {}", items)
+        };
+        if let Some(subtraction) = &class.subtraction {
+            let (subtracted, subtraction_children) = self.char_class(subtraction);
+            let text = format!("except those {}", subtracted);
+            description.push_str(", ");
+            description.push_str(&text);
+            children.push(Explanation::with_children(
+                text,
+                subtraction.span,
+                subtraction_children,
+            ));
+        }
+        (description, children)
+    }
+}
+
+fn class_escape(escape: &ClassEscape) -> Noun {
+    let (singular, plural) = match &escape.kind {
+        ClassEscapeKind::Space => (
+            "a whitespace character (space, tab, newline or carriage return)".to_string(),
+            "whitespace characters (space, tab, newline or carriage return)".to_string(),
+        ),
+        ClassEscapeKind::NameStartChar => (
+            "a character that can start an XML name".to_string(),
+            "characters that can start an XML name".to_string(),
+        ),
+        ClassEscapeKind::NameChar => (
+            "a character that can occur in an XML name".to_string(),
+            "characters that can occur in an XML name".to_string(),
+        ),
+        ClassEscapeKind::Digit => (
+            "a decimal digit (\\p{Nd})".to_string(),
+            "decimal digits (\\p{Nd})".to_string(),
+        ),
+        ClassEscapeKind::WordChar => (
+            "a word character (anything but punctuation, separators and other characters)"
+                .to_string(),
+            "word characters (anything but punctuation, separators and other characters)"
+                .to_string(),
+        ),
+        ClassEscapeKind::Category(name) => {
+            let description = category::category_description(name).unwrap_or("character");
+            let noun = Noun::character(description);
+            (
+                format!("{} (\\p{{{}}})", noun.singular, name),
+                format!("{} (\\p{{{}}})", noun.plural, name),
+            )
+        }
+        ClassEscapeKind::Block(name) => match block_description(name) {
+            Some(description) => (
+                format!("a character in the {}", description),
+                format!("characters in the {}", description),
+            ),
+            None => {
+                // only allowed with the ';k' flag; this matches any
+                // character, whether negated or not
+                return Noun::new(
+                    format!("any character (unknown block {})", name),
+                    format!("characters of any kind (unknown block {})", name),
+                );
+            }
+        },
+    };
+    if escape.negated {
+        Noun::new(
+            format!("a character that is not {}", singular),
+            format!("characters that are not {}", plural),
+        )
+    } else {
+        Noun::new(singular, plural)
+    }
+}
+
+fn block_description(name: &str) -> Option<String> {
+    if name == "PrivateUse" {
+        return Some("Private Use blocks".to_string());
+    }
+    category::block_info(name).map(|block| {
+        format!(
+            "{} block (U+{:04X}\u{2013}U+{:04X})",
+            block.name, block.start, block.end
+        )
+    })
+}
+
+fn quantified(quantifier: &Quantifier, noun: &Noun) -> String {
+    let count = |n: usize| {
+        if n == 1 {
+            noun.one.clone()
+        } else {
+            format!("{} {}", n, noun.plural)
+        }
+    };
+    match quantifier.kind {
+        QuantifierKind::ZeroOrOne => format!("optionally {}", noun.singular),
+        QuantifierKind::ZeroOrMore => format!("any number of {}", noun.plural),
+        QuantifierKind::OneOrMore => format!("one or more {}", noun.plural),
+        QuantifierKind::Exactly(n) => format!("exactly {}", count(n)),
+        QuantifierKind::AtLeast(n) => format!("at least {}", count(n)),
+        QuantifierKind::Between(min, max) => {
+            format!("between {} and {} {}", min, max, noun.plural)
+        }
+    }
+}
+
+fn article(noun: &str) -> &'static str {
+    if noun.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        "an"
+    } else {
+        "a"
+    }
+}
+
+fn list<'a>(items: impl Iterator<Item = &'a str>, conjunction: &str) -> String {
+    let items = items.collect::<Vec<_>>();
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} {} {}", rest.join(", "), conjunction, last),
+    }
+}
+
+fn character(c: char) -> String {
+    match name(c) {
+        Some(name) => format!("a {}", name),
+        None => format!("the character {}", quoted(c)),
+    }
+}
+
+fn quoted(c: char) -> String {
+    match name(c) {
+        Some(name) => name.to_string(),
+        None => format!("'{}'", c),
+    }
+}
+
+// a character in a list, such as in a character class
+fn display(literal: &Literal) -> String {
+    match name(literal.c) {
+        Some(name) => name.to_string(),
+        None => literal.c.to_string(),
+    }
+}
+
+fn name(c: char) -> Option<&'static str> {
+    Some(match c {
+        ' ' => "space",
+        '\t' => "tab",
+        '\n' => "newline",
+        '\r' => "carriage return",
+        _ => return None,
+    })
+}
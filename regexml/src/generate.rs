@@ -0,0 +1,287 @@
+use crate::character_class::CharacterClass;
+use crate::operation::{Operation, OperationControl, RepeatOperation};
+use crate::re_program::ReProgram;
+
+// The characters that representative characters are taken from first.
+const PRINTABLE_ASCII: std::ops::RangeInclusive<u32> = 0x20..=0x7E;
+// With representative characters, only this many characters at the start of
+// a range of a class are picked from.
+const REPRESENTATIVE_RANGE: u32 = 16;
+// Characters that are inserted to make a string that doesn't match.
+const MUTATIONS: &[char] = &['a', 'Z', '0', '9', ' ', '-', '.', '_', '\n', 'é', 'Ж', '中'];
+
+/// A source of random numbers for
+/// [`Regex::generate`](crate::Regex::generate).
+///
+/// Implement this to use a random number generator from another crate, or
+/// use the [`SeededRng`] that comes with this crate.
+pub trait RngLike {
+    /// The next random number. All values should be equally likely.
+    fn next_u64(&mut self) -> u64;
+}
+
+impl<R: RngLike + ?Sized> RngLike for &mut R {
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+}
+
+/// A small random number generator (SplitMix64) that gives the same numbers
+/// for the same seed. It is fast and good enough for test data, but not for
+/// anything that needs to be unpredictable.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl RngLike for SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// How many times a quantified expression is repeated in generated strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepeatBias {
+    /// As few times as the quantifier allows.
+    Min,
+    /// As many times as the quantifier allows, up to
+    /// [`GenerateOptions::max_unbounded_repeats`] more than the minimum for
+    /// quantifiers without an upper bound.
+    Max,
+    /// Any number of times in between, with equal chance.
+    #[default]
+    Uniform,
+}
+
+/// Options for [`Regex::generate`](crate::Regex::generate) and
+/// [`Regex::generate_non_matching`](crate::Regex::generate_non_matching).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateOptions {
+    /// How many times quantified expressions are repeated
+    pub repeat_bias: RepeatBias,
+    /// How many more repetitions than the minimum a quantifier without an
+    /// upper bound, such as `*` or `{2,}`, may have
+    pub max_unbounded_repeats: usize,
+    /// Pick characters that are easy to read from large classes such as
+    /// `\p{L}` or `.`: printable ASCII if the class has any, and otherwise
+    /// characters near the start of the ranges of the class, which are
+    /// usually assigned. If `false`, any character in the class is picked
+    /// with equal chance.
+    pub representative_chars: bool,
+    /// How many strings to try before giving up
+    pub max_attempts: usize,
+    /// The longest string to generate, in characters. Repetitions are cut
+    /// short to stay within it, and a pattern that needs a longer string,
+    /// such as `a{100000}`, generates none.
+    pub max_length: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            repeat_bias: RepeatBias::Uniform,
+            max_unbounded_repeats: 3,
+            representative_chars: true,
+            max_attempts: 100,
+            max_length: 10_000,
+        }
+    }
+}
+
+// Generates strings by walking the operations of a program, choosing
+// branches, repetitions and characters at random. The strings are only
+// candidates: anchors and back-references can make them fail to match.
+pub(crate) struct Generator<'a, R: RngLike> {
+    program: &'a ReProgram,
+    rng: R,
+    options: &'a GenerateOptions,
+    groups: Vec<Option<String>>,
+    // the number of characters generated so far
+    length: usize,
+}
+
+impl<'a, R: RngLike> Generator<'a, R> {
+    pub(crate) fn new(program: &'a ReProgram, rng: R, options: &'a GenerateOptions) -> Self {
+        Self {
+            program,
+            rng,
+            options,
+            groups: Vec::new(),
+            length: 0,
+        }
+    }
+
+    // A string that the program may match, or None if the walk got stuck,
+    // for instance on an empty character class.
+    pub(crate) fn candidate(&mut self) -> Option<String> {
+        let program = self.program;
+        self.groups = vec![None; program.max_parens];
+        self.length = 0;
+        let mut out = String::new();
+        self.operation(&program.operation, &mut out)?;
+        Some(out)
+    }
+
+    // A string close to one that the program may match, changed by
+    // replacing, removing or inserting a character.
+    pub(crate) fn mutated(&mut self) -> String {
+        let mut chars = self
+            .candidate()
+            .unwrap_or_default()
+            .chars()
+            .collect::<Vec<_>>();
+        let replacement = MUTATIONS[self.below(MUTATIONS.len())];
+        let position = self.below(chars.len() + 1);
+        match self.below(3) {
+            0 if position < chars.len() => chars[position] = replacement,
+            1 if position < chars.len() => {
+                chars.remove(position);
+            }
+            _ => chars.insert(position, replacement),
+        }
+        chars.into_iter().collect()
+    }
+
+    fn operation(&mut self, operation: &Operation, out: &mut String) -> Option<()> {
+        match operation {
+            Operation::Atom(atom) => {
+                self.grow(atom.atom.len())?;
+                out.extend(atom.atom.iter());
+            }
+            Operation::CharClass(char_class) => {
+                self.grow(1)?;
+                let c = self.pick(&char_class.character_class)?;
+                out.push(c);
+            }
+            Operation::BackReference(back_reference) => {
+                let group = self.groups.get(back_reference.group_nr)?.as_ref()?;
+                let length = group.chars().count();
+                out.push_str(group);
+                self.grow(length)?;
+            }
+            Operation::Bol(_) => {
+                let at_line_start =
+                    out.is_empty() || (self.program.flags.is_multi_line() && out.ends_with('\n'));
+                if !at_line_start {
+                    return None;
+                }
+            }
+            // checked when the candidate is matched
+            Operation::Eol(_)
+            | Operation::Nothing(_)
+            | Operation::EndProgram(_)
+            | Operation::WordBoundary(_)
+            | Operation::Lookahead(_) => {}
+            Operation::Capture(capture) => {
+                let start = out.len();
+                self.operation(&capture.child_op, out)?;
+                if let Some(group) = self.groups.get_mut(capture.group_nr) {
+                    *group = Some(out[start..].to_string());
+                }
+            }
+            Operation::Choice(choice) => {
+                if choice.branches.is_empty() {
+                    return None;
+                }
+                let branch = &choice.branches[self.below(choice.branches.len())];
+                self.operation(branch, out)?;
+            }
+            Operation::Sequence(sequence) => {
+                for operation in &sequence.operations {
+                    self.operation(operation, out)?;
+                }
+            }
+            Operation::Repeat(_)
+            | Operation::GreedyFixed(_)
+            | Operation::ReluctantFixed(_)
+            | Operation::UnambiguousRepeat(_) => {
+                let repeat = operation.repeat_operation()?;
+                self.repeat(repeat, out)?;
+            }
+        }
+        Some(())
+    }
+
+    // add characters to the length, or None if that makes it too long
+    fn grow(&mut self, length: usize) -> Option<()> {
+        self.length = self.length.saturating_add(length);
+        (self.length <= self.options.max_length).then_some(())
+    }
+
+    fn repeat(&mut self, repeat: &dyn RepeatOperation, out: &mut String) -> Option<()> {
+        let child = repeat.child();
+        // how many repetitions fit in the characters that are left; stop
+        // before starting on more than that
+        let left = self.options.max_length.saturating_sub(self.length);
+        let fit = match child.get_minimum_match_length() {
+            0 => left,
+            length => left / length,
+        };
+        let min = repeat.min();
+        if min > fit {
+            return None;
+        }
+        let max = match repeat.max() {
+            usize::MAX => min.saturating_add(self.options.max_unbounded_repeats),
+            max => max,
+        }
+        .min(fit);
+        let count = match self.options.repeat_bias {
+            RepeatBias::Min => min,
+            RepeatBias::Max => max,
+            RepeatBias::Uniform => min + self.below(max - min + 1),
+        };
+        for _ in 0..count {
+            self.operation(&child, out)?;
+        }
+        Some(())
+    }
+
+    fn pick(&mut self, class: &CharacterClass) -> Option<char> {
+        let list = class.as_code_point_inversion_list();
+        let ranges = list.iter_ranges().collect::<Vec<_>>();
+        if ranges.is_empty() {
+            return None;
+        }
+        if self.options.representative_chars {
+            let printable = list
+                .iter_chars()
+                .skip_while(|c| !PRINTABLE_ASCII.contains(&(*c as u32)))
+                .take_while(|c| PRINTABLE_ASCII.contains(&(*c as u32)))
+                .collect::<Vec<_>>();
+            if !printable.is_empty() {
+                return Some(printable[self.below(printable.len())]);
+            }
+            let range = &ranges[self.below(ranges.len())];
+            let size = (range.end() - range.start() + 1).min(REPRESENTATIVE_RANGE);
+            return char::from_u32(range.start() + self.below(size as usize) as u32);
+        }
+        let mut index = self.below(list.size()) as u32;
+        for range in ranges {
+            let size = range.end() - range.start() + 1;
+            if index < size {
+                // surrogates are in some classes, but aren't characters
+                return char::from_u32(range.start() + index);
+            }
+            index -= size;
+        }
+        None
+    }
+
+    // a random number below n, which must not be 0
+    fn below(&mut self, n: usize) -> usize {
+        (self.rng.next_u64() % n.max(1) as u64) as usize
+    }
+}
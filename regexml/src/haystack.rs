@@ -0,0 +1,416 @@
+use std::cell::Cell;
+use std::fmt;
+use std::ops::Range;
+
+/// Text that a regular expression can be matched against without copying
+/// it into one contiguous string.
+///
+/// The matcher reads the text by code point: positions are counted in
+/// Unicode code points from the start of the text. It steps back and forth
+/// through the text while backtracking, so [`Haystack::char_at`] should be
+/// fast for positions near the previous one.
+///
+/// Implementations are provided for `&str`, `&[char]` and [`Chunked`]. Use
+/// [`Regex::is_match_in`](crate::Regex::is_match_in) and
+/// [`Regex::find_in`](crate::Regex::find_in) to match against them.
+pub trait Haystack {
+    /// The number of code points in the text.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the text is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The code point at a position, or `None` if the position is at or
+    /// past the end.
+    fn char_at(&self, position: usize) -> Option<char>;
+
+    /// The byte offset of a position in the UTF-8 encoding of the text. A
+    /// position at or past the end gives the length of the text in bytes.
+    fn byte_offset(&self, position: usize) -> usize {
+        (0..position)
+            .map_while(|i| self.char_at(i))
+            .map(char::len_utf8)
+            .sum()
+    }
+
+    /// The text as one string, if it is stored that way. The matcher then
+    /// steps through the string itself, rather than reading each position
+    /// with [`Haystack::char_at`].
+    fn as_str(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<T: Haystack + ?Sized> Haystack for &T {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn char_at(&self, position: usize) -> Option<char> {
+        (**self).char_at(position)
+    }
+
+    fn byte_offset(&self, position: usize) -> usize {
+        (**self).byte_offset(position)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        (**self).as_str()
+    }
+}
+
+impl Haystack for [char] {
+    fn len(&self) -> usize {
+        <[char]>::len(self)
+    }
+
+    fn char_at(&self, position: usize) -> Option<char> {
+        self.get(position).copied()
+    }
+}
+
+/// A string has no random access by code point, so calling
+/// [`Haystack::char_at`] directly scans the string up to the position. The
+/// matcher doesn't do this: it keeps track of where it is in the string.
+impl Haystack for str {
+    fn len(&self) -> usize {
+        self.chars().count()
+    }
+
+    fn char_at(&self, position: usize) -> Option<char> {
+        self.chars().nth(position)
+    }
+
+    fn byte_offset(&self, position: usize) -> usize {
+        self.char_indices()
+            .nth(position)
+            .map(|(offset, _)| offset)
+            .unwrap_or(str::len(self))
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+// The last position read in a string, and its byte offset. Reading a
+// position near it steps there from the cursor, so reading the positions
+// of a string one after the other takes constant time for each.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StrCursor(Cell<(usize, usize)>);
+
+impl StrCursor {
+    // move to a position and return its byte offset; a position past the end
+    // gives the length of the text
+    fn seek(&self, text: &str, position: usize) -> usize {
+        let (mut current, mut byte) = self.0.get();
+        // stepping back from the cursor could take longer than starting over
+        if position < current && position < current - position {
+            (current, byte) = (0, 0);
+        }
+        while current < position {
+            match text[byte..].chars().next() {
+                Some(c) => byte += c.len_utf8(),
+                None => break,
+            }
+            current += 1;
+        }
+        while current > position {
+            match text[..byte].chars().next_back() {
+                Some(c) => byte -= c.len_utf8(),
+                None => break,
+            }
+            current -= 1;
+        }
+        self.0.set((current, byte));
+        byte
+    }
+
+    fn char_at(&self, text: &str, position: usize) -> Option<char> {
+        text[self.seek(text, position)..].chars().next()
+    }
+
+    fn reset(&self) {
+        self.0.set((0, 0));
+    }
+}
+
+/// Text stored as a sequence of string segments, such as the chunks of a
+/// rope or the text nodes of a document, matched as if the segments were
+/// concatenated.
+///
+/// Reading a position near the one read before takes constant time; a
+/// position further away takes a binary search over the segments.
+#[derive(Clone)]
+pub struct Chunked<'a> {
+    chunks: Vec<&'a str>,
+    // the position of the first code point of each chunk, plus the total
+    starts: Vec<usize>,
+    // the same, in bytes
+    byte_starts: Vec<usize>,
+    // the chunk read last, and the position read last within it
+    chunk: Cell<usize>,
+    cursor: StrCursor,
+}
+
+impl<'a> Chunked<'a> {
+    /// Create a haystack from segments of text.
+    pub fn new(chunks: impl IntoIterator<Item = &'a str>) -> Self {
+        let chunks = chunks.into_iter().collect::<Vec<_>>();
+        let mut starts = Vec::with_capacity(chunks.len() + 1);
+        let mut byte_starts = Vec::with_capacity(chunks.len() + 1);
+        let (mut start, mut byte_start) = (0, 0);
+        for chunk in chunks.iter().copied() {
+            starts.push(start);
+            byte_starts.push(byte_start);
+            start += chunk.chars().count();
+            byte_start += str::len(chunk);
+        }
+        starts.push(start);
+        byte_starts.push(byte_start);
+        Self {
+            chunks,
+            starts,
+            byte_starts,
+            chunk: Cell::new(0),
+            cursor: StrCursor::default(),
+        }
+    }
+
+    // the chunk that contains a position, and the position within it
+    fn locate(&self, position: usize) -> Option<(usize, usize)> {
+        if position >= self.len() {
+            return None;
+        }
+        let mut chunk = self.chunk.get();
+        if !(self.starts[chunk]..self.starts[chunk + 1]).contains(&position) {
+            // the last chunk that starts at or before the position; empty
+            // chunks start at the same position as the next one
+            chunk = self.starts.partition_point(|start| *start <= position) - 1;
+            self.chunk.set(chunk);
+            self.cursor.reset();
+        }
+        Some((chunk, position - self.starts[chunk]))
+    }
+}
+
+impl Haystack for Chunked<'_> {
+    fn len(&self) -> usize {
+        self.starts.last().copied().unwrap_or(0)
+    }
+
+    fn char_at(&self, position: usize) -> Option<char> {
+        let (chunk, offset) = self.locate(position)?;
+        self.cursor.char_at(self.chunks[chunk], offset)
+    }
+
+    fn byte_offset(&self, position: usize) -> usize {
+        match self.locate(position) {
+            Some((chunk, offset)) => {
+                self.byte_starts[chunk] + self.cursor.seek(self.chunks[chunk], offset)
+            }
+            None => self.byte_starts.last().copied().unwrap_or(0),
+        }
+    }
+}
+
+impl fmt::Debug for Chunked<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Chunked").field(&self.chunks).finish()
+    }
+}
+
+/// UTF-16 text, such as strings from Java or JavaScript, matched without
+/// converting it to UTF-8.
+///
+/// A surrogate pair is one code point. A lone surrogate, which UTF-16 text
+/// from these languages can contain, is read as U+FFFD REPLACEMENT
+/// CHARACTER, because a surrogate is not a character that a regular
+/// expression can match. So a lone surrogate matches `.`, `\p{So}`,
+/// `\p{IsSpecials}` and `\w` like U+FFFD does, while `\p{IsHighSurrogates}`
+/// and the other surrogate blocks never match anything. `\p{Cs}` is not
+/// allowed in a pattern at all.
+///
+/// Use [`Regex::find_utf16`](crate::Regex::find_utf16) for offsets in
+/// UTF-16 code units.
+#[derive(Debug, Clone)]
+pub struct Utf16<'a> {
+    units: &'a [u16],
+    // the positions of the code points that are surrogate pairs
+    pairs: Vec<usize>,
+    // the same, as offsets in code units
+    pair_units: Vec<usize>,
+    len: usize,
+}
+
+impl<'a> Utf16<'a> {
+    /// Create a haystack from UTF-16 code units.
+    pub fn new(units: &'a [u16]) -> Self {
+        let mut pairs = Vec::new();
+        let mut pair_units = Vec::new();
+        let mut len = 0;
+        let mut i = 0;
+        while i < units.len() {
+            if is_high_surrogate(units[i]) && units.get(i + 1).is_some_and(|u| is_low_surrogate(*u))
+            {
+                pairs.push(len);
+                pair_units.push(i);
+                i += 2;
+            } else {
+                i += 1;
+            }
+            len += 1;
+        }
+        Self {
+            units,
+            pairs,
+            pair_units,
+            len,
+        }
+    }
+
+    /// The offset in code units of a code point position. A position at or
+    /// past the end gives the number of code units.
+    pub fn unit_offset(&self, position: usize) -> usize {
+        if position >= self.len {
+            return self.units.len();
+        }
+        position + self.pairs.partition_point(|pair| *pair < position)
+    }
+
+    /// The code point position of an offset in code units, or `None` if the
+    /// offset is in the middle of a surrogate pair or past the end.
+    pub fn position(&self, unit_offset: usize) -> Option<usize> {
+        if unit_offset > self.units.len() {
+            return None;
+        }
+        let pairs_before = self.pair_units.partition_point(|pair| *pair < unit_offset);
+        if pairs_before > 0 && self.pair_units[pairs_before - 1] + 1 == unit_offset {
+            return None;
+        }
+        Some(unit_offset - pairs_before)
+    }
+}
+
+impl Haystack for Utf16<'_> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn char_at(&self, position: usize) -> Option<char> {
+        if position >= self.len {
+            return None;
+        }
+        let unit_offset = self.unit_offset(position);
+        let unit = self.units[unit_offset];
+        let units = match self.units.get(unit_offset + 1) {
+            Some(next) if is_high_surrogate(unit) && is_low_surrogate(*next) => {
+                &self.units[unit_offset..unit_offset + 2]
+            }
+            _ => &self.units[unit_offset..unit_offset + 1],
+        };
+        char::decode_utf16(units.iter().copied())
+            .next()
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+fn is_high_surrogate(unit: u16) -> bool {
+    (0xD800..0xDC00).contains(&unit)
+}
+
+fn is_low_surrogate(unit: u16) -> bool {
+    (0xDC00..0xE000).contains(&unit)
+}
+
+// The text a matcher works on: a copy of a string, or a haystack passed in
+// by the user. The length of a haystack is only counted once.
+pub(crate) enum Search<'a> {
+    Chars(Vec<char>),
+    Str {
+        text: &'a str,
+        len: usize,
+        cursor: StrCursor,
+    },
+    Haystack {
+        haystack: &'a dyn Haystack,
+        len: usize,
+    },
+}
+
+impl<'a> Search<'a> {
+    pub(crate) fn new(haystack: &'a dyn Haystack) -> Self {
+        match haystack.as_str() {
+            Some(text) => Search::Str {
+                text,
+                len: text.chars().count(),
+                cursor: StrCursor::default(),
+            },
+            None => Search::Haystack {
+                haystack,
+                len: haystack.len(),
+            },
+        }
+    }
+}
+
+impl Search<'_> {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Search::Chars(chars) => chars.len(),
+            Search::Str { len, .. } | Search::Haystack { len, .. } => *len,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn get(&self, position: usize) -> Option<char> {
+        match self {
+            Search::Chars(chars) => chars.get(position).copied(),
+            Search::Str { text, len, cursor } => {
+                if position < *len {
+                    cursor.char_at(text, position)
+                } else {
+                    None
+                }
+            }
+            Search::Haystack { haystack, len } => {
+                if position < *len {
+                    haystack.char_at(position)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    // the code points in a range, clamped to the text
+    pub(crate) fn slice(&self, range: Range<usize>) -> Vec<char> {
+        match self {
+            Search::Chars(chars) => {
+                let end = range.end.min(chars.len());
+                chars.get(range.start.min(end)..end).unwrap_or(&[]).to_vec()
+            }
+            Search::Str { text, cursor, .. } => {
+                let start = cursor.seek(text, range.start);
+                let end = cursor.seek(text, range.end.max(range.start));
+                text[start..end].chars().collect()
+            }
+            Search::Haystack { haystack, .. } => range.map_while(|i| haystack.char_at(i)).collect(),
+        }
+    }
+}
+
+impl fmt::Debug for Search<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Search::Chars(chars) => f.debug_tuple("Chars").field(chars).finish(),
+            Search::Str { text, .. } => f.debug_tuple("Str").field(text).finish(),
+            Search::Haystack { len, .. } => f.debug_struct("Haystack").field("len", len).finish(),
+        }
+    }
+}
@@ -1,11 +1,22 @@
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+pub mod analysis;
+mod analyze_output;
 mod analyze_string;
+pub mod ast;
 mod block;
 mod category;
 mod character_class;
+mod complexity;
+mod diagnosis;
+mod diagnostics;
+mod explain;
+mod generate;
+mod haystack;
 mod history;
+mod limits;
+mod nfa;
 mod op_atom;
 mod op_back_reference;
 mod op_bol;
@@ -15,18 +26,43 @@ mod op_choice;
 mod op_end_program;
 mod op_eol;
 mod op_greedy_fixed;
+mod op_lookahead;
 mod op_nothing;
 mod op_reluctant_fixed;
 mod op_repeat;
 mod op_sequence;
 mod op_unambiguous_repeat;
+mod op_word_boundary;
 mod operation;
+mod printer;
+mod program_dump;
 mod re_compiler;
 mod re_flags;
 mod re_matcher;
+mod re_parser;
 mod re_program;
 mod regex;
+mod regex_match;
+mod trace;
+mod translate;
 
-pub use crate::analyze_string::{AnalyzeEntry, MatchEntry};
+pub use crate::analyze_output::{
+    write_analyze_json, write_analyze_json_io, write_analyze_xml, write_analyze_xml_io,
+    FN_NAMESPACE,
+};
+pub use crate::analyze_string::{
+    AnalyzeEntry, MatchEntry, Position, PositionedAnalyzeEntry, PositionedMatchEntry,
+};
+pub use crate::complexity::{Complexity, ComplexityIssue, ComplexityKind, Risk};
+pub use crate::diagnosis::Diagnosis;
+pub use crate::diagnostics::{Span, Warning, WarningKind};
+pub use crate::explain::Explanation;
+pub use crate::generate::{GenerateOptions, RepeatBias, RngLike, SeededRng};
+pub use crate::haystack::{Chunked, Haystack, Utf16};
+pub use crate::limits::{CompileLimits, MAX_NESTING_DEPTH};
 pub use crate::re_compiler::Error;
+pub use crate::re_flags::Language;
 pub use crate::regex::Regex;
+pub use crate::regex_match::{Match, PartialMatch};
+pub use crate::trace::{OperationKind, PrintTracer, TracedOperation, Tracer};
+pub use crate::translate::{Dialect, Translation};
@@ -0,0 +1,57 @@
+/// The deepest that groups and character class subtractions can be nested,
+/// whatever the [`CompileLimits`].
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+/// Limits on the resources a regular expression may use when it's compiled.
+///
+/// Patterns from untrusted sources can be crafted to take a lot of memory or
+/// stack space, for instance with thousands of nested groups. Compile such
+/// patterns with [`Regex::with_limits`](crate::Regex::with_limits); a
+/// pattern that exceeds a limit is rejected with
+/// [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+///
+/// The default limits leave room for any reasonable pattern. Whatever the
+/// limits, groups and character class subtractions can't be nested more than
+/// [`MAX_NESTING_DEPTH`] deep, as that would overflow the stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileLimits {
+    /// The maximum length of the pattern, in characters
+    pub max_pattern_length: usize,
+    /// The maximum number of groups nested inside each other
+    pub max_nesting_depth: usize,
+    /// The maximum number in a quantifier such as `{m,n}`, and of the
+    /// product of the numbers in nested quantifiers, where an unbounded
+    /// quantifier such as `+` or `{m,}` counts as one more than its minimum
+    pub max_repetition: usize,
+    /// The maximum number of capturing groups
+    pub max_capture_groups: usize,
+    /// The maximum number of operations in the compiled program
+    pub max_program_size: usize,
+}
+
+impl CompileLimits {
+    /// No limits other than [`MAX_NESTING_DEPTH`]. This is what
+    /// [`Regex::xpath`](crate::Regex::xpath) and
+    /// [`Regex::xsd`](crate::Regex::xsd) use.
+    pub fn unlimited() -> Self {
+        Self {
+            max_pattern_length: usize::MAX,
+            max_nesting_depth: MAX_NESTING_DEPTH,
+            max_repetition: usize::MAX,
+            max_capture_groups: usize::MAX,
+            max_program_size: usize::MAX,
+        }
+    }
+}
+
+impl Default for CompileLimits {
+    fn default() -> Self {
+        Self {
+            max_pattern_length: 10_000,
+            max_nesting_depth: 64,
+            max_repetition: 10_000,
+            max_capture_groups: 1_000,
+            max_program_size: 10_000,
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt::Write;
+
+use crate::{
+    character_class::{CharacterClass, CharacterClassBuilder},
+    operation::{Operation, RepeatOperation},
+};
+
+// Repeats with more copies than this are drawn as a loop with a counter
+// rather than unrolled.
+const MAX_UNROLL: usize = 8;
+// So are repeats whose copies together would be drawn with more operations
+// than this, so that unrolling nested repeats can't multiply the drawing.
+const MAX_UNROLLED_SIZE: usize = 64;
+
+// A nondeterministic automaton derived from a compiled program, for drawing.
+// It has the structure of the program: every operation gets its own states,
+// and the operations it consists of are connected by epsilon edges.
+pub(crate) struct Nfa {
+    states: Vec<State>,
+    start: usize,
+    accept: usize,
+}
+
+struct State {
+    kind: StateKind,
+    edges: Vec<Edge>,
+}
+
+enum StateKind {
+    Plain,
+    // the start and end of a capturing group
+    CaptureStart(usize),
+    CaptureEnd(usize),
+    // anchors, which only let matching through at the start or end of the
+    // input or a line
+    Bol,
+    Eol,
+    // `\b` or, if negated, `\B`
+    WordBoundary(bool),
+    // a lookahead, drawn without the expression it looks for; negated for
+    // `(?!...)`
+    Lookahead(bool),
+    // a repeat that is not unrolled, with its bounds
+    Counter(String),
+}
+
+struct Edge {
+    label: Label,
+    target: usize,
+}
+
+enum Label {
+    Epsilon,
+    Class(CharacterClass),
+    BackReference(usize),
+}
+
+impl Nfa {
+    pub(crate) fn new(operation: &Operation) -> Self {
+        let mut nfa = Self {
+            states: Vec::new(),
+            start: 0,
+            accept: 0,
+        };
+        let start = nfa.state(StateKind::Plain);
+        let accept = nfa.state(StateKind::Plain);
+        nfa.operation(operation, start, accept);
+        nfa.start = start;
+        nfa.accept = accept;
+        nfa
+    }
+
+    fn state(&mut self, kind: StateKind) -> usize {
+        self.states.push(State {
+            kind,
+            edges: Vec::new(),
+        });
+        self.states.len() - 1
+    }
+
+    fn edge(&mut self, from: usize, label: Label, to: usize) {
+        self.states[from].edges.push(Edge { label, target: to });
+    }
+
+    // Add states and edges so that `operation` leads from `from` to `to`.
+    fn operation(&mut self, operation: &Operation, from: usize, to: usize) {
+        match operation {
+            Operation::Atom(atom) => {
+                let mut current = from;
+                for (i, c) in atom.atom.iter().enumerate() {
+                    let next = if i == atom.atom.len() - 1 {
+                        to
+                    } else {
+                        self.state(StateKind::Plain)
+                    };
+                    self.edge(
+                        current,
+                        Label::Class(CharacterClassBuilder::from_char(*c).build()),
+                        next,
+                    );
+                    current = next;
+                }
+                if atom.atom.is_empty() {
+                    self.edge(from, Label::Epsilon, to);
+                }
+            }
+            Operation::CharClass(char_class) => {
+                self.edge(from, Label::Class(char_class.character_class.clone()), to)
+            }
+            Operation::BackReference(back_reference) => {
+                self.edge(from, Label::BackReference(back_reference.group_nr), to)
+            }
+            Operation::Bol(_) => self.anchor(StateKind::Bol, from, to),
+            Operation::Eol(_) => self.anchor(StateKind::Eol, from, to),
+            Operation::WordBoundary(word_boundary) => {
+                self.anchor(StateKind::WordBoundary(word_boundary.negated), from, to)
+            }
+            Operation::Lookahead(lookahead) => {
+                self.anchor(StateKind::Lookahead(lookahead.negated), from, to)
+            }
+            Operation::Nothing(_) | Operation::EndProgram(_) => self.edge(from, Label::Epsilon, to),
+            Operation::Capture(capture) => {
+                let start = self.state(StateKind::CaptureStart(capture.group_nr));
+                let end = self.state(StateKind::CaptureEnd(capture.group_nr));
+                self.edge(from, Label::Epsilon, start);
+                self.operation(&capture.child_op, start, end);
+                self.edge(end, Label::Epsilon, to);
+            }
+            Operation::Choice(choice) => {
+                for branch in &choice.branches {
+                    self.operation(branch, from, to);
+                }
+            }
+            Operation::Sequence(sequence) => {
+                // the program ends with EndProgram, which needs no states
+                let operations = sequence
+                    .operations
+                    .iter()
+                    .filter(|operation| !matches!(operation, Operation::EndProgram(_)))
+                    .collect::<Vec<_>>();
+                if operations.is_empty() {
+                    self.edge(from, Label::Epsilon, to);
+                }
+                let mut current = from;
+                for (i, operation) in operations.iter().enumerate() {
+                    let next = if i == operations.len() - 1 {
+                        to
+                    } else {
+                        self.state(StateKind::Plain)
+                    };
+                    self.operation(operation, current, next);
+                    current = next;
+                }
+            }
+            Operation::Repeat(_)
+            | Operation::GreedyFixed(_)
+            | Operation::ReluctantFixed(_)
+            | Operation::UnambiguousRepeat(_) => {
+                let repeat = operation.repeat_operation().unwrap();
+                self.repeat(repeat, from, to)
+            }
+        }
+    }
+
+    fn anchor(&mut self, kind: StateKind, from: usize, to: usize) {
+        let anchor = self.state(kind);
+        self.edge(from, Label::Epsilon, anchor);
+        self.edge(anchor, Label::Epsilon, to);
+    }
+
+    fn repeat(&mut self, repeat: &dyn RepeatOperation, from: usize, to: usize) {
+        let child = repeat.child();
+        let (min, max) = (repeat.min(), repeat.max());
+        let unbounded = max == usize::MAX;
+        let copies = if unbounded { min + 1 } else { max };
+        if !unrolled(copies, drawn_size(&child)) {
+            // a single copy in a loop, with the bounds on the state that
+            // counts the repetitions
+            let counter = self.state(StateKind::Counter(repeat.bounds()));
+            let body = self.state(StateKind::Plain);
+            self.edge(from, Label::Epsilon, counter);
+            self.edge(counter, Label::Epsilon, body);
+            self.operation(&child, body, counter);
+            self.edge(counter, Label::Epsilon, to);
+            return;
+        }
+        // a state of its own, so that the loop below can't lead back into
+        // other paths through `from`
+        let mut current = self.state(StateKind::Plain);
+        self.edge(from, Label::Epsilon, current);
+        // the required copies one after the other
+        for _ in 0..min {
+            let next = self.state(StateKind::Plain);
+            self.operation(&child, current, next);
+            current = next;
+        }
+        if unbounded {
+            // then a loop for any further ones
+            let body = self.state(StateKind::Plain);
+            self.edge(current, Label::Epsilon, body);
+            self.operation(&child, body, current);
+            self.edge(current, Label::Epsilon, to);
+        } else {
+            // then optional copies, each of which can be skipped
+            for _ in min..max {
+                let next = self.state(StateKind::Plain);
+                self.edge(current, Label::Epsilon, to);
+                self.operation(&child, current, next);
+                current = next;
+            }
+            self.edge(current, Label::Epsilon, to);
+        }
+    }
+
+    pub(crate) fn to_dot(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph regex {{");
+        let _ = writeln!(out, "  rankdir=LR;");
+        let _ = writeln!(out, "  node [shape=circle, label=\"\"];");
+        let _ = writeln!(out, "  start [shape=point];");
+        let _ = writeln!(out, "  start -> {};", self.start);
+        for (i, state) in self.states.iter().enumerate() {
+            let attributes = match &state.kind {
+                _ if i == self.accept => "shape=doublecircle".to_string(),
+                StateKind::Plain => continue,
+                StateKind::CaptureStart(group) => format!("shape=box, label=\"({}\"", group),
+                StateKind::CaptureEnd(group) => format!("shape=box, label=\"{})\"", group),
+                StateKind::Bol => "shape=diamond, label=\"^\"".to_string(),
+                StateKind::Eol => "shape=diamond, label=\"$\"".to_string(),
+                StateKind::WordBoundary(false) => "shape=diamond, label=\"\\\\b\"".to_string(),
+                StateKind::WordBoundary(true) => "shape=diamond, label=\"\\\\B\"".to_string(),
+                StateKind::Lookahead(false) => "shape=diamond, label=\"?=\"".to_string(),
+                StateKind::Lookahead(true) => "shape=diamond, label=\"?!\"".to_string(),
+                StateKind::Counter(bounds) => {
+                    format!("shape=octagon, label=\"{}\"", escape(bounds))
+                }
+            };
+            let _ = writeln!(out, "  {} [{}];", i, attributes);
+        }
+        for (i, state) in self.states.iter().enumerate() {
+            for edge in &state.edges {
+                let attributes = match &edge.label {
+                    Label::Epsilon => "style=dashed".to_string(),
+                    Label::Class(class) => format!("label=\"{}\"", escape(&class_label(class))),
+                    Label::BackReference(group) => format!("label=\"\\\\{}\"", group),
+                };
+                let _ = writeln!(out, "  {} -> {} [{}];", i, edge.target, attributes);
+            }
+        }
+        let _ = writeln!(out, "}}");
+        out
+    }
+}
+
+// Whether a repeat with this many copies of a child of the given drawn
+// size is drawn unrolled.
+fn unrolled(copies: usize, child_size: usize) -> bool {
+    copies <= MAX_UNROLL && copies * child_size <= MAX_UNROLLED_SIZE
+}
+
+// The number of operations drawn for an operation, counting the copies of
+// unrolled repeats.
+fn drawn_size(operation: &Operation) -> usize {
+    let operands = operation.operands();
+    match operation.repeat_operation() {
+        Some(repeat) => {
+            let child_size = drawn_size(operands[0]);
+            let copies = if repeat.max() == usize::MAX {
+                repeat.min() + 1
+            } else {
+                repeat.max()
+            };
+            if unrolled(copies, child_size) {
+                1 + copies * child_size
+            } else {
+                1 + child_size
+            }
+        }
+        None => 1 + operands.into_iter().map(drawn_size).sum::<usize>(),
+    }
+}
+
+// A single character is shown as is, other classes as a summary.
+fn class_label(class: &CharacterClass) -> String {
+    let mut chars = class.as_code_point_inversion_list().iter_chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_control() => c.to_string(),
+        _ => class.summary(),
+    }
+}
+
+// escape text for a quoted DOT string
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -62,25 +62,19 @@ impl OperationControl for Atom {
         matcher: &'a ReMatcher,
         position: usize,
     ) -> Box<dyn Iterator<Item = usize> + 'a> {
-        let in_ = &matcher.search;
-        if (position + self.len) > in_.len() {
-            return Box::new(std::iter::empty());
-        }
-        let mut in_chars = in_.iter().skip(position);
-        let atom_chars = &self.atom;
-        if matcher.program.flags.is_case_independent() {
-            for atom_char in atom_chars {
-                let in_char = in_chars.next().unwrap();
-                if !matcher.equal_case_blind(*in_char, *atom_char) {
-                    return Box::new(std::iter::empty());
-                }
-            }
-        } else {
-            for atom_char in atom_chars {
-                let in_char = in_chars.next().unwrap();
-                if *in_char != *atom_char {
-                    return Box::new(std::iter::empty());
+        let case_independent = matcher.program.flags.is_case_independent();
+        for (i, atom_char) in self.atom.iter().enumerate() {
+            let same = match matcher.search.get(position + i) {
+                Some(in_char) if case_independent => matcher.equal_case_blind(in_char, *atom_char),
+                Some(in_char) => in_char == *atom_char,
+                None => {
+                    matcher.set_hit_end();
+                    false
                 }
+            };
+            if !same {
+                matcher.expect_char(position + i, *atom_char);
+                return Box::new(std::iter::empty());
             }
         }
         Box::new(std::iter::once(position + self.len))
@@ -1,13 +1,13 @@
 use crate::{
     operation::{Operation, OperationControl},
-    re_flags::ReFlags,
+    re_flags::{Language, ReFlags},
     re_matcher::ReMatcher,
 };
 
 // A back-reference in a regular expression.
 #[derive(Debug, Clone)]
 pub(crate) struct BackReference {
-    group_nr: usize,
+    pub(crate) group_nr: usize,
 }
 
 impl BackReference {
@@ -44,29 +44,32 @@ impl OperationControl for BackReference {
             // Get the length of the backref
             let l = e - s;
 
-            // if there's not enough input left, give up
             let search = &matcher.search;
-            if (position + l - 1) >= search.len() {
-                return Box::new(std::iter::empty());
-            }
 
-            // Case fold the backref?
-            if matcher.program.flags.is_case_independent() {
-                // Compare backref to input
-                for i in 0..l {
-                    if !matcher.equal_case_blind(search[position + i], search[s + i]) {
-                        return Box::new(std::iter::empty());
+            // Compare backref to input, case folded if needed
+            let case_independent = matcher.program.flags.is_case_independent();
+            for i in 0..l {
+                let same = match (search.get(position + i), search.get(s + i)) {
+                    (Some(a), Some(b)) if case_independent => matcher.equal_case_blind(a, b),
+                    (Some(a), Some(b)) => a == b,
+                    (None, Some(_)) => {
+                        matcher.set_hit_end();
+                        false
                     }
-                }
-            } else {
-                // Compare backref to input
-                for i in 0..l {
-                    if search[position + i] != search[s + i] {
-                        return Box::new(std::iter::empty());
+                    _ => false,
+                };
+                if !same {
+                    if let Some(expected) = search.get(s + i) {
+                        matcher.expect_char(position + i, expected);
                     }
+                    return Box::new(std::iter::empty());
                 }
             }
             Box::new(std::iter::once(position + l))
+        } else if matcher.program.flags.language() == Language::EcmaScript {
+            // in ECMAScript a group that didn't take part in the match
+            // matches the empty string
+            Box::new(std::iter::once(position))
         } else {
             // We don't know the backref yet
             Box::new(std::iter::empty())
@@ -29,8 +29,12 @@ impl OperationControl for Bol {
             // If we're multiline matching, we could still be at the start of a line
             if matcher.program.flags.is_multi_line() {
                 // Continue if at the start of a line
-                if matcher.is_new_line(position - 1) && position < matcher.search.len() {
-                    return Box::new(std::iter::once(position));
+                if matcher.is_new_line(position - 1) {
+                    if position < matcher.search.len() || matcher.bol_at_end() {
+                        return Box::new(std::iter::once(position));
+                    }
+                    // more input would start a line here
+                    matcher.set_hit_end();
                 }
             }
             return Box::new(std::iter::empty());
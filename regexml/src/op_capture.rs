@@ -8,7 +8,7 @@ use crate::{
 // Open paren (captured group) within a regular expression
 #[derive(Debug, Clone)]
 pub(crate) struct Capture {
-    group_nr: usize,
+    pub(crate) group_nr: usize,
     pub(crate) child_op: Box<Operation>,
 }
 
@@ -49,7 +49,7 @@ impl OperationControl for Capture {
         if (matcher.program.optimization_flags & OPT_HASBACKREFS) != 0 {
             matcher.set_start_backref(self.group_nr, Some(position));
         }
-        let basis = self.child_op.matches_iter(matcher, position);
+        let basis = self.child_op.traced_matches_iter(matcher, position);
 
         Box::new(CaptureGroupIterator::new(
             matcher,
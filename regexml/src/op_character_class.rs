@@ -40,11 +40,17 @@ impl OperationControl for CharClass {
         matcher: &'b crate::re_matcher::ReMatcher,
         position: usize,
     ) -> Box<dyn Iterator<Item = usize> + 'b> {
-        let search = &matcher.search;
-        if position < search.len() && self.character_class.contains(search[position]) {
-            Box::new(std::iter::once(position + 1))
-        } else {
-            Box::new(std::iter::empty())
+        match matcher.search.get(position) {
+            Some(c) if self.character_class.contains(c) => Box::new(std::iter::once(position + 1)),
+            Some(_) => {
+                matcher.expect_class(position, &self.character_class);
+                Box::new(std::iter::empty())
+            }
+            None => {
+                matcher.set_hit_end();
+                matcher.expect_class(position, &self.character_class);
+                Box::new(std::iter::empty())
+            }
         }
     }
 }
@@ -10,7 +10,7 @@ use crate::{
 // A choice of several branches within a regular expression.
 #[derive(Debug, Clone)]
 pub(crate) struct Choice {
-    branches: Vec<Operation>,
+    pub(crate) branches: Vec<Operation>,
 }
 
 impl Choice {
@@ -22,7 +22,7 @@ impl Choice {
 impl OperationControl for Choice {
     fn get_match_length(&self) -> Option<usize> {
         let mut iter = self.branches.iter();
-        let fixed = iter.next().unwrap().get_match_length();
+        let fixed = iter.next()?.get_match_length();
         for branch in iter {
             if branch.get_match_length() != fixed {
                 return None;
@@ -33,7 +33,10 @@ impl OperationControl for Choice {
 
     fn get_minimum_match_length(&self) -> usize {
         let mut iter = self.branches.iter();
-        let mut min = iter.next().unwrap().get_minimum_match_length();
+        let Some(first) = iter.next() else {
+            return 0;
+        };
+        let mut min = first.get_minimum_match_length();
         for branch in iter {
             let m = branch.get_minimum_match_length();
             if m < min {
@@ -119,7 +122,7 @@ impl<'a> ChoiceIterator<'a> {
         if let Some(next_op) = next_op {
             // if there is one, set the current iter to that one
             self.matcher.clear_captured_groups_beyond(self.position);
-            self.current_iter = Some(next_op.matches_iter(self.matcher, self.position));
+            self.current_iter = Some(next_op.traced_matches_iter(self.matcher, self.position));
             true
         } else {
             false
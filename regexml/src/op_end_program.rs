@@ -32,6 +32,7 @@ impl OperationControl for EndProgram {
             if position >= matcher.search.len() {
                 Box::new(std::iter::once(position))
             } else {
+                matcher.expect_end(position);
                 Box::new(std::iter::empty())
             }
         } else {
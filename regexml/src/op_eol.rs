@@ -27,16 +27,23 @@ impl OperationControl for Eol {
         position: usize,
     ) -> Box<dyn Iterator<Item = usize>> {
         let search = &matcher.search;
+        if position >= search.len() {
+            // more input could be something other than the end of a line
+            matcher.set_hit_end();
+        }
 
         if matcher.program.flags.is_multi_line() {
             if search.is_empty() || position >= search.len() || matcher.is_new_line(position) {
                 Box::new(std::iter::once(position))
             } else {
+                matcher.expect_char(position, '\n');
+                matcher.expect_end(position);
                 Box::new(std::iter::empty())
             }
         } else if search.is_empty() || position >= search.len() {
             Box::new(std::iter::once(position))
         } else {
+            matcher.expect_end(position);
             Box::new(std::iter::empty())
         }
     }
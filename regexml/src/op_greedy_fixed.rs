@@ -9,7 +9,7 @@ use crate::{
 // repeated unit is fixed.
 #[derive(Debug, Clone)]
 pub(crate) struct GreedyFixed {
-    operation: Box<Operation>,
+    pub(crate) operation: Box<Operation>,
     pub(crate) min: usize,
     max: usize,
     len: usize,
@@ -29,14 +29,15 @@ impl GreedyFixed {
 impl OperationControl for GreedyFixed {
     fn get_match_length(&self) -> Option<usize> {
         if self.min == self.max {
-            Some(self.min * self.len)
+            self.min.checked_mul(self.len)
         } else {
             None
         }
     }
 
     fn get_minimum_match_length(&self) -> usize {
-        self.min * self.operation.get_minimum_match_length()
+        self.min
+            .saturating_mul(self.operation.get_minimum_match_length())
     }
 
     fn matches_empty_string(&self) -> u32 {
@@ -75,7 +76,7 @@ impl OperationControl for GreedyFixed {
     ) -> Box<dyn Iterator<Item = usize> + 'a> {
         let mut guard = matcher.search.len();
         if self.max < usize::MAX {
-            guard = guard.min(position + self.len * self.max)
+            guard = guard.min(position.saturating_add(self.len.saturating_mul(self.max)))
         }
         if position >= guard && self.min > 0 {
             return Box::new(std::iter::empty());
@@ -84,7 +85,7 @@ impl OperationControl for GreedyFixed {
         let mut p = position;
         let mut matches = 0;
         while p <= guard {
-            let mut it = self.operation.matches_iter(matcher, p);
+            let mut it = self.operation.traced_matches_iter(matcher, p);
             let matched = it.next().is_some();
             if matched {
                 matches += 1;
@@ -102,7 +103,7 @@ impl OperationControl for GreedyFixed {
         Box::new(IntStepIterator::new(
             p,
             -(self.len as i64),
-            position + self.len * self.min,
+            position.saturating_add(self.len.saturating_mul(self.min)),
         ))
     }
 
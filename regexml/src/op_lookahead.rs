@@ -0,0 +1,72 @@
+use crate::{
+    operation::{Operation, OperationControl},
+    re_flags::ReFlags,
+    re_matcher::ReMatcher,
+};
+
+// Lookahead (?=...), or negative lookahead (?!...), in an ECMAScript regular
+// expression. As in ECMAScript, only the first way the expression matches
+// counts; matching never backtracks into a lookahead.
+#[derive(Debug, Clone)]
+pub(crate) struct Lookahead {
+    pub(crate) child_op: Box<Operation>,
+    pub(crate) negated: bool,
+}
+
+impl Lookahead {
+    pub(crate) fn new(child_op: Operation, negated: bool) -> Self {
+        Self {
+            child_op: Box::new(child_op),
+            negated,
+        }
+    }
+}
+
+impl OperationControl for Lookahead {
+    fn get_match_length(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn matches_empty_string(&self) -> u32 {
+        // depends on the text that follows
+        0
+    }
+
+    fn optimize(self, flags: &ReFlags) -> Operation {
+        Operation::from(Lookahead {
+            child_op: Box::new(self.child_op.optimize(flags)),
+            negated: self.negated,
+        })
+    }
+
+    fn contains_capturing_expressions(&self) -> bool {
+        matches!(self.child_op.as_ref(), Operation::Capture(_))
+            || self.child_op.contains_capturing_expressions()
+    }
+
+    fn matches_iter<'a>(
+        &'a self,
+        matcher: &'a ReMatcher<'a>,
+        position: usize,
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        // groups in a negative lookahead never capture anything
+        let saved_state = self.negated.then(|| matcher.capture_state());
+        let found = self
+            .child_op
+            .traced_matches_iter(matcher, position)
+            .next()
+            .is_some();
+        if let Some(saved_state) = saved_state {
+            matcher.reset_state(saved_state);
+        }
+        if found != self.negated {
+            Box::new(std::iter::once(position))
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    fn children(&self) -> Vec<Operation> {
+        vec![self.child_op.as_ref().clone()]
+    }
+}
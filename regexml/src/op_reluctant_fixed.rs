@@ -8,7 +8,7 @@ use crate::{
 // the repeated unit is fixed.
 #[derive(Debug, Clone)]
 pub(crate) struct ReluctantFixed {
-    operation: Box<Operation>,
+    pub(crate) operation: Box<Operation>,
     pub(crate) min: usize,
     max: usize,
     len: usize,
@@ -28,14 +28,15 @@ impl ReluctantFixed {
 impl OperationControl for ReluctantFixed {
     fn get_match_length(&self) -> Option<usize> {
         if self.min == self.max {
-            Some(self.min * self.len)
+            self.min.checked_mul(self.len)
         } else {
             None
         }
     }
 
     fn get_minimum_match_length(&self) -> usize {
-        self.min * self.operation.get_minimum_match_length()
+        self.min
+            .saturating_mul(self.operation.get_minimum_match_length())
     }
 
     fn matches_empty_string(&self) -> u32 {
@@ -138,7 +139,7 @@ impl Iterator for ReluctantFixedIterator<'_> {
             self.started = true;
 
             while self.count < self.min {
-                let mut it = self.op.matches_iter(self.matcher, self.pos);
+                let mut it = self.op.traced_matches_iter(self.matcher, self.pos);
                 if let Some(next) = it.next() {
                     self.count += 1;
                     self.pos = next;
@@ -151,7 +152,7 @@ impl Iterator for ReluctantFixedIterator<'_> {
 
         if self.count < self.max {
             self.matcher.clear_captured_groups_beyond(self.position);
-            let mut it = self.op.matches_iter(self.matcher, self.pos);
+            let mut it = self.op.traced_matches_iter(self.matcher, self.pos);
             if let Some(next) = it.next() {
                 self.pos = next;
                 self.count += 1;
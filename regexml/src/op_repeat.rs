@@ -32,7 +32,7 @@ impl OperationControl for Repeat {
     fn get_match_length(&self) -> Option<usize> {
         self.operation.get_match_length().and_then(|match_length| {
             if self.min == self.max {
-                Some(self.min * match_length)
+                self.min.checked_mul(match_length)
             } else {
                 None
             }
@@ -40,7 +40,8 @@ impl OperationControl for Repeat {
     }
 
     fn get_minimum_match_length(&self) -> usize {
-        self.min * self.operation.get_minimum_match_length()
+        self.min
+            .saturating_mul(self.operation.get_minimum_match_length())
     }
 
     fn get_initial_character_class(&self, case_blind: bool) -> CharacterClass {
@@ -83,7 +84,9 @@ impl OperationControl for Repeat {
     ) -> Box<dyn Iterator<Item = usize> + 'a> {
         let mut iterators: Vec<Box<dyn Iterator<Item = usize>>> = Vec::new();
         let mut positions = Vec::new();
-        let bound = self.max.min(matcher.search.len() - position + 1);
+        let bound = self
+            .max
+            .min(matcher.search.len().saturating_sub(position) + 1);
         let mut p = position;
         if self.greedy {
             // Prime the arrays first with iterators up to the maximum length,
@@ -100,7 +103,7 @@ impl OperationControl for Repeat {
                 positions.push(p);
             }
             for _i in 0..bound {
-                let mut it = self.operation.matches_iter(matcher, p);
+                let mut it = self.operation.traced_matches_iter(matcher, p);
                 if let Some(next) = it.next() {
                     p = next;
                     iterators.push(it);
@@ -206,7 +209,7 @@ impl Iterator for GreedyRepeatIterator<'_> {
                     self.positions.pop();
                     self.positions.push(p);
                     while self.iterators.len() < self.bound {
-                        let mut it = self.operation.matches_iter(self.matcher, p);
+                        let mut it = self.operation.traced_matches_iter(self.matcher, p);
                         if let Some(next) = it.next() {
                             p = next;
                             self.iterators.push(it);
@@ -268,7 +271,7 @@ impl Iterator for ReluctantRepeatIterator<'_> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             if let Some(position) = self.position {
-                let mut it = self.operation.matches_iter(self.matcher, position);
+                let mut it = self.operation.traced_matches_iter(self.matcher, position);
                 if let Some(position) = it.next() {
                     self.counter += 1;
                     if self.counter > self.max {
@@ -29,13 +29,13 @@ impl OperationControl for Sequence {
     fn get_match_length(&self) -> Option<usize> {
         self.operations
             .iter()
-            .try_fold(0, |acc, op| op.get_match_length().map(|len| acc + len))
+            .try_fold(0usize, |acc, op| acc.checked_add(op.get_match_length()?))
     }
 
     fn get_minimum_match_length(&self) -> usize {
-        self.operations
-            .iter()
-            .fold(0, |acc, op| acc + op.get_minimum_match_length())
+        self.operations.iter().fold(0usize, |acc, op| {
+            acc.saturating_add(op.get_minimum_match_length())
+        })
     }
 
     fn get_initial_character_class(&self, case_blind: bool) -> CharacterClass {
@@ -188,7 +188,6 @@ impl OperationControl for Sequence {
 struct SequenceIterator<'a> {
     iterators: Vec<Box<dyn Iterator<Item = usize> + 'a>>,
     operations: &'a [Operation],
-    backtracking_limit: Option<usize>,
     matcher: &'a ReMatcher<'a>,
     saved_state: Option<CaptureState>,
 }
@@ -205,10 +204,14 @@ impl<'a> SequenceIterator<'a> {
         } else {
             None
         };
+        let first = match operations.first() {
+            Some(operation) => operation.traced_matches_iter(matcher, position),
+            // an empty sequence matches the empty string
+            None => Box::new(std::iter::once(position)),
+        };
         Self {
-            iterators: vec![operations.first().unwrap().matches_iter(matcher, position)],
+            iterators: vec![first],
             operations,
-            backtracking_limit: matcher.program.backtracking_limit,
             matcher,
             saved_state,
         }
@@ -225,40 +228,24 @@ impl Iterator for SequenceIterator<'_> {
     // backwards getting the next match for each term in the sequence until we
     // find a route through.
     fn next(&mut self) -> Option<Self::Item> {
-        let mut counter = 0;
         // as long as there are iterators on the stack
         while !self.iterators.is_empty() {
-            loop {
-                // take the top of the stack
-                let top = self.iterators.last_mut().unwrap();
-                // take the next item from the top iterator
-                if let Some(next) = top.next() {
-                    self.matcher.clear_captured_groups_beyond(next);
-                    // if the amount of iterators to process is equal or
-                    // greater than the amount of operations in this sequence,
-                    // then we return next
-                    let i = self.iterators.len();
-                    if i >= self.operations.len() {
-                        return Some(next);
-                    }
-                    // otherwise we push a new iterator to the stack
-                    let new_top = self.operations[i].matches_iter(self.matcher, next);
-                    self.iterators.push(new_top);
-                } else {
-                    // continue until we have no more next in the top
-                    break;
+            // take the top of the stack, and the next item from it
+            while let Some(next) = self.iterators.last_mut().and_then(|top| top.next()) {
+                self.matcher.clear_captured_groups_beyond(next);
+                // if the amount of iterators to process is equal or
+                // greater than the amount of operations in this sequence,
+                // then we return next
+                let i = self.iterators.len();
+                if i >= self.operations.len() {
+                    return Some(next);
                 }
+                // otherwise we push a new iterator to the stack
+                let new_top = self.operations[i].traced_matches_iter(self.matcher, next);
+                self.iterators.push(new_top);
             }
             // we are backtracking. pop the iterator from the stack
             self.iterators.pop();
-            if let Some(backtracking_limit) = self.backtracking_limit {
-                if counter > backtracking_limit {
-                    // TODO: error
-                    // Regex backtracking exceeded processing
-                    todo!()
-                }
-            }
-            counter += 1;
         }
         // restore saved state
         if let Some(saved_state) = &self.saved_state {
@@ -16,7 +16,7 @@ use crate::{
 // in the regex "A*B".
 #[derive(Debug, Clone)]
 pub(crate) struct UnambiguousRepeat {
-    operation: Box<Operation>,
+    pub(crate) operation: Box<Operation>,
     pub(crate) min: usize,
     max: usize,
 }
@@ -35,7 +35,7 @@ impl OperationControl for UnambiguousRepeat {
     fn get_match_length(&self) -> Option<usize> {
         self.operation.get_match_length().and_then(|match_length| {
             if self.min == self.max {
-                Some(self.min * match_length)
+                self.min.checked_mul(match_length)
             } else {
                 None
             }
@@ -43,7 +43,8 @@ impl OperationControl for UnambiguousRepeat {
     }
 
     fn get_minimum_match_length(&self) -> usize {
-        self.min * self.operation.get_minimum_match_length()
+        self.min
+            .saturating_mul(self.operation.get_minimum_match_length())
     }
 
     fn matches_empty_string(&self) -> u32 {
@@ -77,7 +78,7 @@ impl OperationControl for UnambiguousRepeat {
         let mut p = position;
         let mut matches = 0;
         while matches < self.max && p <= guard {
-            let mut iter = self.operation.matches_iter(matcher, p);
+            let mut iter = self.operation.traced_matches_iter(matcher, p);
             if let Some(n) = iter.next() {
                 p = n;
                 matches += 1;
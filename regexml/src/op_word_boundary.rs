@@ -0,0 +1,60 @@
+use crate::{
+    character_class::CharacterClass,
+    operation::{Operation, OperationControl},
+    re_flags::ReFlags,
+    re_matcher::ReMatcher,
+};
+
+// Word boundary (\b), or a position that is not one (\B), in an ECMAScript
+// regular expression
+#[derive(Debug, Clone)]
+pub(crate) struct WordBoundary {
+    pub(crate) word_chars: CharacterClass,
+    pub(crate) negated: bool,
+}
+
+impl WordBoundary {
+    pub(crate) fn new(word_chars: CharacterClass, negated: bool) -> Self {
+        Self {
+            word_chars,
+            negated,
+        }
+    }
+}
+
+impl OperationControl for WordBoundary {
+    fn get_match_length(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn matches_empty_string(&self) -> u32 {
+        // depends on the characters around it
+        0
+    }
+
+    fn optimize(self, _flags: &ReFlags) -> Operation {
+        Operation::from(self)
+    }
+
+    fn matches_iter<'a>(
+        &self,
+        matcher: &'a ReMatcher,
+        position: usize,
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let is_word = |c: Option<char>| c.is_some_and(|c| self.word_chars.contains(c));
+        let before = position
+            .checked_sub(1)
+            .and_then(|before| matcher.search.get(before));
+        let after = matcher.search.get(position);
+        if after.is_none() {
+            // more input could be a word character
+            matcher.set_hit_end();
+        }
+        let boundary = is_word(before) != is_word(after);
+        if boundary != self.negated {
+            Box::new(std::iter::once(position))
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+}
@@ -10,14 +10,17 @@ use crate::op_choice::Choice;
 use crate::op_end_program::EndProgram;
 use crate::op_eol::Eol;
 use crate::op_greedy_fixed::GreedyFixed;
+use crate::op_lookahead::Lookahead;
 use crate::op_nothing::Nothing;
 use crate::op_reluctant_fixed::ReluctantFixed;
 use crate::op_repeat::Repeat;
 use crate::op_sequence::Sequence;
 use crate::op_unambiguous_repeat::UnambiguousRepeat;
+use crate::op_word_boundary::WordBoundary;
 
 use crate::re_flags::ReFlags;
 use crate::re_matcher::ReMatcher;
+use crate::trace::TraceIter;
 
 pub(crate) const MATCHES_ZLS_AT_START: u32 = 1;
 pub(crate) const MATCHES_ZLS_AT_END: u32 = 2;
@@ -96,9 +99,7 @@ pub(crate) trait OperationControl {
     }
 
     /// Access child information so we can structurally dive into
-    /// a regex, mostly for testing purposes.
-    /// allow unused code to exist as we do use it in the tests
-    #[allow(dead_code)]
+    /// a regex, for the program dump and for testing purposes.
     fn children(&self) -> Vec<Operation> {
         Vec::new()
     }
@@ -109,6 +110,20 @@ pub(crate) trait RepeatOperation {
     fn min(&self) -> usize;
     fn max(&self) -> usize;
     fn greedy(&self) -> bool;
+
+    /// The bounds of the repetition as in a pattern, such as `{1,}` or
+    /// `{0,3}?`, for tracing and dumps.
+    fn bounds(&self) -> String {
+        let mut bounds = if self.max() == usize::MAX {
+            format!("{{{},}}", self.min())
+        } else {
+            format!("{{{},{}}}", self.min(), self.max())
+        };
+        if !self.greedy() {
+            bounds.push('?');
+        }
+        bounds
+    }
 }
 
 #[enum_dispatch(OperationControl)]
@@ -128,9 +143,50 @@ pub(crate) enum Operation {
     GreedyFixed,
     ReluctantFixed,
     UnambiguousRepeat,
+    WordBoundary,
+    Lookahead,
 }
 
 impl Operation {
+    /// Get an iterator returning all the matches for this operation, like
+    /// `matches_iter`, reporting them to the tracer of the matcher if it has
+    /// one. Operations use this to match the operations they are made of.
+    pub(crate) fn traced_matches_iter<'a>(
+        &'a self,
+        matcher: &'a ReMatcher<'a>,
+        position: usize,
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        match matcher.trace_sink() {
+            Some(sink) => Box::new(TraceIter::new(self, matcher, sink, position)),
+            None => self.matches_iter(matcher, position),
+        }
+    }
+
+    /// The operations this operation consists of. Unlike `children` this
+    /// borrows them.
+    pub(crate) fn operands(&self) -> Vec<&Operation> {
+        match self {
+            Operation::Capture(capture) => vec![&capture.child_op],
+            Operation::Choice(choice) => choice.branches.iter().collect(),
+            Operation::Sequence(sequence) => sequence.operations.iter().collect(),
+            Operation::Repeat(repeat) => vec![&repeat.operation],
+            Operation::GreedyFixed(repeat) => vec![&repeat.operation],
+            Operation::ReluctantFixed(repeat) => vec![&repeat.operation],
+            Operation::UnambiguousRepeat(repeat) => vec![&repeat.operation],
+            Operation::Lookahead(lookahead) => vec![&lookahead.child_op],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The number of operations in this operation, including itself.
+    pub(crate) fn size(&self) -> usize {
+        1 + self
+            .operands()
+            .into_iter()
+            .map(Operation::size)
+            .sum::<usize>()
+    }
+
     pub(crate) fn repeat_operation(&self) -> Option<&dyn RepeatOperation> {
         match self {
             Operation::Repeat(repeat) => Some(repeat),
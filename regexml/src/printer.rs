@@ -0,0 +1,318 @@
+use crate::{
+    ast::{Atom, Branch, CharClassExpr, ClassEscape, ClassEscapeKind, ClassItem, Expr, Literal},
+    ast::{Piece, Quantifier, QuantifierKind},
+    re_compiler::Error,
+    re_flags::Language,
+};
+
+/// Prints a syntax tree back to pattern text.
+pub(crate) struct Printer {
+    language: Language,
+    // normalize escapes and quantifiers rather than preserving how they were
+    // written
+    canonical: bool,
+    out: String,
+}
+
+impl Printer {
+    pub(crate) fn new(language: Language, canonical: bool) -> Self {
+        Self {
+            language,
+            canonical,
+            out: String::new(),
+        }
+    }
+
+    pub(crate) fn print(mut self, expr: &Expr) -> Result<String, Error> {
+        if self.language == Language::FullTextWildcards {
+            self.wildcards(expr)?;
+        } else {
+            self.expr(expr)?;
+        }
+        Ok(self.out)
+    }
+
+    // A full-text wildcard pattern only has characters and wildcards, one
+    // after the other.
+    fn wildcards(&mut self, expr: &Expr) -> Result<(), Error> {
+        let unsupported = |what: &str| {
+            Error::Unsupported(format!("{} can't be written as full-text wildcards", what))
+        };
+        let [branch] = &expr.branches[..] else {
+            return Err(unsupported("Alternatives"));
+        };
+        for piece in &branch.pieces {
+            match (&piece.atom, &piece.quantifier) {
+                (Atom::Literal(literal), None) => {
+                    let needs_escape = matches!(literal.c, '.' | '\\');
+                    if needs_escape || (literal.escaped && !self.canonical) {
+                        self.out.push('\\');
+                    }
+                    self.out.push(literal.c);
+                }
+                (Atom::Any(_), quantifier) => {
+                    self.out.push('.');
+                    let Some(quantifier) = quantifier else {
+                        continue;
+                    };
+                    if !quantifier.greedy {
+                        return Err(unsupported("Reluctant quantifiers"));
+                    }
+                    match (quantifier.min(), quantifier.max()) {
+                        (0, Some(1)) => self.out.push('?'),
+                        (0, None) => self.out.push('*'),
+                        (1, None) => self.out.push('+'),
+                        (min, Some(max)) => self.out.push_str(&format!("{{{},{}}}", min, max)),
+                        (_, None) => return Err(unsupported("Open-ended repeats")),
+                    }
+                }
+                (Atom::Literal(_), Some(_)) => {
+                    return Err(unsupported("Repeated characters"));
+                }
+                _ => return Err(unsupported("Groups, classes and anchors")),
+            }
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        for (i, branch) in expr.branches.iter().enumerate() {
+            if i > 0 {
+                self.out.push('|');
+            }
+            self.branch(branch)?;
+        }
+        Ok(())
+    }
+
+    fn branch(&mut self, branch: &Branch) -> Result<(), Error> {
+        for (i, piece) in branch.pieces.iter().enumerate() {
+            // a back-reference takes as many digits as there are groups, so
+            // shield it from a digit that follows
+            let followed_by_digit = matches!(
+                branch.pieces.get(i + 1).map(|next| &next.atom),
+                Some(Atom::Literal(Literal { c: '0'..='9', .. }))
+            );
+            if followed_by_digit && matches!(piece.atom, Atom::BackReference(_)) {
+                self.out.push_str("(?:");
+                self.atom(&piece.atom)?;
+                self.out.push(')');
+                self.quantifier(piece)?;
+            } else {
+                self.piece(piece)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn piece(&mut self, piece: &Piece) -> Result<(), Error> {
+        self.atom(&piece.atom)?;
+        self.quantifier(piece)
+    }
+
+    fn quantifier(&mut self, piece: &Piece) -> Result<(), Error> {
+        let Some(quantifier) = &piece.quantifier else {
+            return Ok(());
+        };
+        if !quantifier.greedy && self.language == Language::XSD {
+            return Err(Error::Unsupported(
+                "Reluctant quantifiers are not allowed in XSD".to_string(),
+            ));
+        }
+        let kind = if self.canonical {
+            match canonical_quantifier(quantifier) {
+                Some(kind) => kind,
+                // exactly once, so the quantifier is redundant
+                None => return Ok(()),
+            }
+        } else {
+            quantifier.kind
+        };
+        match kind {
+            QuantifierKind::ZeroOrOne => self.out.push('?'),
+            QuantifierKind::ZeroOrMore => self.out.push('*'),
+            QuantifierKind::OneOrMore => self.out.push('+'),
+            QuantifierKind::Exactly(n) => self.out.push_str(&format!("{{{}}}", n)),
+            QuantifierKind::AtLeast(n) => self.out.push_str(&format!("{{{},}}", n)),
+            QuantifierKind::Between(min, max) => self.out.push_str(&format!("{{{},{}}}", min, max)),
+        }
+        if !quantifier.greedy {
+            self.out.push('?');
+        }
+        Ok(())
+    }
+
+    fn atom(&mut self, atom: &Atom) -> Result<(), Error> {
+        match atom {
+            Atom::Literal(literal) => self.literal(literal),
+            Atom::Any(_) => self.out.push('.'),
+            Atom::Start(_) | Atom::End(_) if self.language == Language::XSD => {
+                return Err(Error::Unsupported(
+                    "Anchors '^' and '$' are not allowed in XSD".to_string(),
+                ));
+            }
+            Atom::Start(_) => self.out.push('^'),
+            Atom::End(_) => self.out.push('$'),
+            Atom::ClassEscape(escape) => self.class_escape(escape)?,
+            Atom::CharClass(class) => self.char_class(class)?,
+            Atom::Group(group) => {
+                // XSD has no non-capturing groups, but as it has no
+                // back-references either, a capturing group does the same
+                if group.capture.is_none() && self.language != Language::XSD {
+                    self.out.push_str("(?:");
+                } else {
+                    self.out.push('(');
+                }
+                self.expr(&group.expr)?;
+                self.out.push(')');
+            }
+            Atom::BackReference(back_reference) => {
+                if self.language == Language::XSD {
+                    return Err(Error::Unsupported(
+                        "Back-references are not allowed in XSD".to_string(),
+                    ));
+                }
+                self.out.push_str(&format!("\\{}", back_reference.group));
+            }
+            Atom::WordBoundary(_) | Atom::NotWordBoundary(_) | Atom::Lookahead(_)
+                if self.language != Language::EcmaScript =>
+            {
+                return Err(Error::Unsupported(
+                    "Word boundaries and lookaheads are only allowed in ECMAScript".to_string(),
+                ));
+            }
+            Atom::WordBoundary(_) => self.out.push_str("\\b"),
+            Atom::NotWordBoundary(_) => self.out.push_str("\\B"),
+            Atom::Lookahead(lookahead) => {
+                self.out
+                    .push_str(if lookahead.negated { "(?!" } else { "(?=" });
+                self.expr(&lookahead.expr)?;
+                self.out.push(')');
+            }
+        }
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        let c = literal.c;
+        let needs_escape = match c {
+            '\\' | '|' | '.' | '?' | '*' | '+' | '{' | '}' | '(' | ')' | '[' | ']' => true,
+            '^' | '$' => self.language != Language::XSD,
+            _ => false,
+        };
+        // '$' can't be escaped in XSD, where it doesn't need to be
+        let may_escape = !(c == '$' && self.language == Language::XSD);
+        let escape = if self.canonical {
+            needs_escape
+        } else {
+            needs_escape || (literal.escaped && may_escape)
+        };
+        self.char(c, escape);
+    }
+
+    fn char(&mut self, c: char, escape: bool) {
+        match c {
+            '\n' if escape || self.canonical => self.out.push_str("\\n"),
+            '\r' if escape || self.canonical => self.out.push_str("\\r"),
+            '\t' if escape || self.canonical => self.out.push_str("\\t"),
+            c if escape => {
+                self.out.push('\\');
+                self.out.push(c);
+            }
+            c => self.out.push(c),
+        }
+    }
+
+    fn class_escape(&mut self, escape: &ClassEscape) -> Result<(), Error> {
+        let (lower, upper) = match &escape.kind {
+            ClassEscapeKind::NameStartChar
+            | ClassEscapeKind::NameChar
+            | ClassEscapeKind::Block(_)
+                if self.language == Language::EcmaScript =>
+            {
+                return Err(Error::Unsupported(
+                    "ECMAScript has no escapes for XML names and Unicode blocks".to_string(),
+                ));
+            }
+            ClassEscapeKind::Space => ('s', 'S'),
+            ClassEscapeKind::NameStartChar => ('i', 'I'),
+            ClassEscapeKind::NameChar => ('c', 'C'),
+            ClassEscapeKind::Digit => ('d', 'D'),
+            ClassEscapeKind::WordChar => ('w', 'W'),
+            ClassEscapeKind::Category(name) => {
+                let p = if escape.negated { 'P' } else { 'p' };
+                self.out.push_str(&format!("\\{}{{{}}}", p, name));
+                return Ok(());
+            }
+            ClassEscapeKind::Block(name) => {
+                let p = if escape.negated { 'P' } else { 'p' };
+                self.out.push_str(&format!("\\{}{{Is{}}}", p, name));
+                return Ok(());
+            }
+        };
+        self.out.push('\\');
+        self.out.push(if escape.negated { upper } else { lower });
+        Ok(())
+    }
+
+    fn char_class(&mut self, class: &CharClassExpr) -> Result<(), Error> {
+        self.out.push('[');
+        if class.negated {
+            self.out.push('^');
+        }
+        for (i, item) in class.items.iter().enumerate() {
+            let first = i == 0 && !class.negated;
+            match item {
+                ClassItem::Char(literal) => self.class_char(literal, first),
+                ClassItem::Range(start, end) => {
+                    self.class_char(start, first);
+                    self.out.push('-');
+                    self.class_char(end, false);
+                }
+                ClassItem::Escape(escape) => self.class_escape(escape)?,
+            }
+        }
+        if let Some(subtraction) = &class.subtraction {
+            if self.language == Language::EcmaScript {
+                return Err(Error::Unsupported(
+                    "ECMAScript has no character class subtraction".to_string(),
+                ));
+            }
+            self.out.push('-');
+            self.char_class(subtraction)?;
+        }
+        self.out.push(']');
+        Ok(())
+    }
+
+    fn class_char(&mut self, literal: &Literal, first: bool) {
+        let c = literal.c;
+        let needs_escape = match c {
+            '\\' | '[' | ']' => true,
+            '^' => first,
+            // a hyphen is only literal in some positions, so always escape
+            // it when normalizing
+            '-' => self.canonical,
+            _ => false,
+        };
+        let escape = if self.canonical {
+            needs_escape
+        } else {
+            needs_escape || literal.escaped
+        };
+        self.char(c, escape);
+    }
+}
+
+// The simplest way to write a quantifier, or `None` if it can be left out.
+fn canonical_quantifier(quantifier: &Quantifier) -> Option<QuantifierKind> {
+    Some(match (quantifier.min(), quantifier.max()) {
+        (1, Some(1)) => return None,
+        (0, Some(1)) => QuantifierKind::ZeroOrOne,
+        (0, None) => QuantifierKind::ZeroOrMore,
+        (1, None) => QuantifierKind::OneOrMore,
+        (min, None) => QuantifierKind::AtLeast(min),
+        (min, Some(max)) if min == max => QuantifierKind::Exactly(min),
+        (min, Some(max)) => QuantifierKind::Between(min, max),
+    })
+}
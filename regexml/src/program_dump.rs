@@ -0,0 +1,115 @@
+use std::fmt::Write;
+
+use crate::{
+    character_class::CharacterClass,
+    operation::{Operation, OperationControl, RepeatOperation},
+    re_program::{ReProgram, OPT_HASBACKREFS, OPT_HASBOL},
+};
+
+// Writes a compiled program as text, one operation per line, with children
+// indented below their parent. The format only depends on the program, so
+// dumps can be compared between patterns and versions.
+pub(crate) fn dump(program: &ReProgram) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "pattern: {:?}",
+        program.pattern.iter().collect::<String>()
+    );
+    let _ = writeln!(out, "minimum length: {}", program.minimum_length);
+    let _ = writeln!(
+        out,
+        "prefix: {}",
+        program
+            .prefix
+            .as_ref()
+            .map(|prefix| format!("{:?}", prefix.iter().collect::<String>()))
+            .unwrap_or_else(|| "none".to_string())
+    );
+    let _ = writeln!(
+        out,
+        "initial character class: {}",
+        program
+            .initial_char_class
+            .as_ref()
+            .map(CharacterClass::summary)
+            .unwrap_or_else(|| "none".to_string())
+    );
+    let mut optimizations = Vec::new();
+    if program.optimization_flags & OPT_HASBOL != 0 {
+        optimizations.push("starts with ^");
+    }
+    if program.optimization_flags & OPT_HASBACKREFS != 0 {
+        optimizations.push("has back-references");
+    }
+    let _ = writeln!(
+        out,
+        "optimizations: {}",
+        if optimizations.is_empty() {
+            "none".to_string()
+        } else {
+            optimizations.join(", ")
+        }
+    );
+    let _ = writeln!(out, "preconditions:");
+    for precondition in &program.preconditions {
+        let position = match precondition.fixed_position {
+            Some(position) => format!("at {}", position),
+            None => format!("from {}", precondition.min_position),
+        };
+        let _ = writeln!(out, "  {}: {}", position, label(&precondition.operation));
+        for child in precondition.operation.children() {
+            operation(&mut out, &child, 2);
+        }
+    }
+    let _ = writeln!(out, "operations:");
+    operation(&mut out, &program.operation, 1);
+    out
+}
+
+fn operation(out: &mut String, op: &Operation, depth: usize) {
+    let _ = writeln!(out, "{:indent$}{}", "", label(op), indent = depth * 2);
+    for child in op.children() {
+        operation(out, &child, depth + 1);
+    }
+}
+
+// The name of the operation with what it matches, without its children.
+fn label(op: &Operation) -> String {
+    match op {
+        Operation::Atom(atom) => format!("Atom {:?}", atom.atom.iter().collect::<String>()),
+        Operation::BackReference(back_reference) => {
+            format!("BackReference \\{}", back_reference.group_nr)
+        }
+        Operation::Bol(_) => "Bol".to_string(),
+        Operation::Capture(capture) => format!("Capture {}", capture.group_nr),
+        Operation::CharClass(char_class) => {
+            format!("CharClass {}", char_class.character_class.summary())
+        }
+        Operation::Choice(_) => "Choice".to_string(),
+        Operation::EndProgram(_) => "EndProgram".to_string(),
+        Operation::Eol(_) => "Eol".to_string(),
+        Operation::Nothing(_) => "Nothing".to_string(),
+        Operation::Sequence(_) => "Sequence".to_string(),
+        Operation::Repeat(repeat) => format!("Repeat {}", repeat.bounds()),
+        Operation::GreedyFixed(repeat) => format!(
+            "GreedyFixed {} length {}",
+            repeat.bounds(),
+            repeat.child().get_match_length().unwrap_or(0)
+        ),
+        Operation::ReluctantFixed(repeat) => format!(
+            "ReluctantFixed {} length {}",
+            repeat.bounds(),
+            repeat.child().get_match_length().unwrap_or(0)
+        ),
+        Operation::UnambiguousRepeat(repeat) => {
+            format!("UnambiguousRepeat {}", repeat.bounds())
+        }
+        Operation::WordBoundary(word_boundary) if word_boundary.negated => {
+            "WordBoundary \\B".to_string()
+        }
+        Operation::WordBoundary(_) => "WordBoundary \\b".to_string(),
+        Operation::Lookahead(lookahead) if lookahead.negated => "Lookahead ?!".to_string(),
+        Operation::Lookahead(_) => "Lookahead ?=".to_string(),
+    }
+}
@@ -1,10 +1,15 @@
-use ahash::{HashSet, HashSetExt};
 use icu_casemap::CaseMapCloser;
 use icu_collections::codepointinvlist::CodePointInversionListBuilder;
+use icu_properties::sets;
 
 use crate::{
+    ast::{
+        self, CharClassExpr, ClassEscape, ClassEscapeKind, ClassItem, Expr, Piece, QuantifierKind,
+    },
     category,
     character_class::{CharacterClass, CharacterClassBuilder},
+    diagnostics::{Warning, WarningKind},
+    limits::CompileLimits,
     op_atom::Atom,
     op_back_reference::BackReference,
     op_bol::Bol,
@@ -14,49 +19,36 @@ use crate::{
     op_end_program::EndProgram,
     op_eol::Eol,
     op_greedy_fixed::GreedyFixed,
+    op_lookahead::Lookahead,
     op_nothing::Nothing,
     op_reluctant_fixed::ReluctantFixed,
     op_repeat::Repeat,
     op_sequence::Sequence,
+    op_word_boundary::WordBoundary,
     operation::{Operation, OperationControl, MATCHES_ZLS_ANYWHERE},
     re_flags::{Language, ReFlags},
+    re_parser::ReParser,
     re_program::{ReProgram, OPT_HASBACKREFS},
 };
 
-// No flags (nothing special)
-pub(crate) const NODE_NORMAL: u32 = 0;
-// True if top level expr
-pub(crate) const NODE_TOPLEVEL: u32 = 2;
-
+/// Compiles the syntax tree produced by [`ReParser`] into operations.
 pub(crate) struct ReCompiler {
-    // Input state for compiling regular expression
-
     // input string
     pattern: Vec<char>,
-    // length of the pattern string
-    len: usize,
-    // current input index into ac
-    idx: usize,
-    // total number of paren pairs
-    capturing_open_paren_count: usize,
-
-    // {m, n} stacks
-    // minimum number of matches
-    bracket_min: usize,
-    // maximum number of matches
-    bracket_max: usize,
-
-    captures: HashSet<usize>,
-    has_back_references: bool,
 
     re_flags: ReFlags,
+    limits: CompileLimits,
+
+    // non-fatal problems found while compiling
+    warnings: Vec<Warning>,
 }
 
 /// Regular expression error
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
-    /// Internal error
-    Internal,
+    /// Internal error: an invariant of the engine doesn't hold. This is a
+    /// bug; the message describes what went wrong.
+    Internal(String),
     /// Invalid flags (FORX0001)
     InvalidFlags(String),
     /// Syntax error (FORX0002)
@@ -65,858 +57,336 @@ pub enum Error {
     MatchesEmptyString,
     /// Replacement string is invalid (FORX0004)
     InvalidReplacementString(String),
+    /// The regular expression can't be expressed in the requested language,
+    /// or uses a feature that the engine or the requested analysis doesn't
+    /// support
+    Unsupported(String),
+    /// The regular expression exceeds the [`CompileLimits`] it was compiled
+    /// with, or an analysis of it needs more states than it allows
+    LimitExceeded(String),
 }
 
 impl Error {
     pub(crate) fn syntax(s: impl Into<String>) -> Error {
         Error::Syntax(s.into())
     }
-}
 
-enum CharacterClassOrBackReference {
-    CharacterClass(CharacterClassBuilder),
-    BackReference(usize),
-}
-
-impl From<CharacterClassBuilder> for CharacterClassOrBackReference {
-    fn from(cc: CharacterClassBuilder) -> Self {
-        Self::CharacterClass(cc)
+    pub(crate) fn internal(s: impl Into<String>) -> Error {
+        Error::Internal(s.into())
     }
 }
 
 impl ReCompiler {
     pub(crate) fn new(pattern: Vec<char>, re_flags: ReFlags) -> Self {
         Self {
-            len: pattern.len(),
             pattern,
-            idx: 0,
-            // the implicit outer parens
-            capturing_open_paren_count: 1,
-            bracket_min: 0,
-            bracket_max: 0,
-            captures: HashSet::new(),
-            has_back_references: false,
             re_flags,
+            limits: CompileLimits::unlimited(),
+            warnings: Vec::new(),
         }
     }
 
-    fn bracket(&mut self) -> Result<(), Error> {
-        // current character must be a '{'
-        if self.idx >= self.len {
-            return Err(Error::Internal);
-        }
-        if self.pattern[self.idx] != '{' {
-            return Err(Error::Internal);
-        }
-        self.idx += 1;
-
-        // next char must be a digit
-        if self.idx >= self.len || !self.pattern[self.idx].is_ascii_digit() {
-            return Err(Error::syntax("Expected digit"));
-        }
-
-        // get min ('m' of {m,n}) number
-        let mut number = String::new();
-        while self.idx < self.len && self.pattern[self.idx].is_ascii_digit() {
-            number.push(self.pattern[self.idx]);
-            self.idx += 1;
-        }
-
-        self.bracket_min = number
-            .parse::<usize>()
-            .map_err(|_| Error::syntax("Expected valid number"))?;
-
-        // if out of input, fail
-        if self.idx >= self.len {
-            return Err(Error::syntax("Expected comma or right bracket"));
-        }
-
-        // if end of expr, optional limit is 0
-        if self.pattern[self.idx] == '}' {
-            self.idx += 1;
-            self.bracket_max = self.bracket_min;
-            return Ok(());
-        }
-
-        // must have at least {m,} and maybe {m,n}
-        if self.idx >= self.len || self.pattern[self.idx] != ',' {
-            return Err(Error::syntax("Expected comma"));
-        }
-        self.idx += 1;
-
-        // if out of input, fail
-        if self.idx >= self.len {
-            return Err(Error::syntax("Expected comma or right bracket"));
-        }
-
-        // if {m,} max is unlimited
-        if self.pattern[self.idx] == '}' {
-            self.idx += 1;
-            self.bracket_max = usize::MAX;
-            return Ok(());
-        }
-
-        // next char must be a digit
-        if self.idx >= self.len || !self.pattern[self.idx].is_ascii_digit() {
-            return Err(Error::syntax("Unexpected digit"));
-        }
-
-        // get max number
-        let mut number = String::new();
-        while self.idx < self.len && self.pattern[self.idx].is_ascii_digit() {
-            number.push(self.pattern[self.idx]);
-            self.idx += 1;
-        }
-
-        self.bracket_max = number
-            .parse::<usize>()
-            .map_err(|_| Error::syntax("Expected valid number"))?;
-
-        // optional repetitions must be >= 0
-        if self.bracket_max < self.bracket_min {
-            return Err(Error::syntax("Bad range"));
-        }
-
-        // must have close brace
-        if self.idx >= self.len || self.pattern[self.idx] != '}' {
-            return Err(Error::syntax("Missing closing brace"));
-        }
-        self.idx += 1;
-        Ok(())
+    pub(crate) fn with_limits(mut self, limits: &CompileLimits) -> Self {
+        self.limits = limits.clone();
+        self
     }
 
-    fn escape(&mut self, in_square_brackets: bool) -> Result<CharacterClassOrBackReference, Error> {
-        // "Shouldn't" happen
-        if self.pattern[self.idx] != '\\' {
-            return Err(Error::Internal);
-        }
-
-        // escape shouldn't occur as last character in string!
-        if self.idx + 1 >= self.len {
-            return Err(Error::syntax("Escape terminates string"));
-        }
-
-        // switch on character after backslash
-        self.idx += 2;
-        let escape_char = self.pattern[self.idx - 1];
-
-        let escape_s = CharacterClassBuilder::from_str("\t\n\r ");
-
-        match escape_char {
-            'n' => Ok(CharacterClassBuilder::from_char('\n').into()),
-            'r' => Ok(CharacterClassBuilder::from_char('\r').into()),
-            't' => Ok(CharacterClassBuilder::from_char('\t').into()),
-            '\\' | '|' | '.' | '-' | '^' | '?' | '*' | '+' | '{' | '}' | '(' | ')' | '[' | ']' => {
-                Ok(CharacterClassBuilder::from_char(escape_char).into())
-            }
-            '$' => match self.re_flags.language() {
-                Language::XPath => Ok(CharacterClassBuilder::from_char('$').into()),
-                Language::XSD => Err(Error::syntax("In XSD, '$' must not be escaped")),
-            },
-            's' => Ok(escape_s.into()),
-            'S' => Ok(escape_s.complement().into()),
-            'i' => Ok(CharacterClassBuilder::CodePointInversionListBuilder(
-                category::name_start_char(),
-            )
-            .into()),
-            'I' => Ok(CharacterClassBuilder::CodePointInversionListBuilder(
-                category::name_start_char(),
-            )
-            .complement()
-            .into()),
-            'c' => Ok(
-                CharacterClassBuilder::CodePointInversionListBuilder(category::name_char()).into(),
-            ),
-            'C' => Ok(
-                CharacterClassBuilder::CodePointInversionListBuilder(category::name_char())
-                    .complement()
-                    .into(),
-            ),
-            'd' => Ok(CharacterClassBuilder::CodePointInversionListBuilder(
-                category::decimal_number(),
-            )
-            .into()),
-            'D' => Ok(CharacterClassBuilder::CodePointInversionListBuilder(
-                category::decimal_number(),
-            )
-            .complement()
-            .into()),
-            'p' | 'P' => {
-                if self.idx == self.len {
-                    return Err(Error::syntax(format!(
-                        "Expected '{{' after \\{}",
-                        escape_char
-                    )));
-                }
-                if self.pattern[self.idx] != '{' {
-                    return Err(Error::syntax(format!(
-                        "Expected '{{' after \\{}",
-                        escape_char
-                    )));
-                }
-                self.idx += 1;
-                let from = self.idx;
-                let close = self
-                    .pattern
-                    .iter()
-                    .skip(from)
-                    .position(|c| *c == '}')
-                    .ok_or(Error::syntax(format!(
-                        "No closing '}}' after \\{}",
-                        escape_char
-                    )))?;
-                let close = from + close;
-                let block = &self.pattern[self.idx..close];
-
-                if block.len() == 1 || block.len() == 2 {
-                    self.idx = close + 1;
-                    let name = block.iter().collect::<String>();
-
-                    let cc = CharacterClassBuilder::CodePointInversionListBuilder(
-                        category::category_group(&name)?,
-                    );
-
-                    if escape_char == 'p' {
-                        Ok(cc.into())
-                    } else {
-                        Ok(cc.complement().into())
-                    }
-                } else if block.starts_with(&['I', 's']) {
-                    let name = block[2..].iter().collect::<String>();
-                    // TODO: obey isAllowUnknownBlockNames to follow XSD spec
-                    // (which doesn't make this an error; original source code
-                    // issues a warning)
-                    let cc = CharacterClassBuilder::CodePointInversionListBuilder(category::block(
-                        &name,
-                    )?);
-                    self.idx = close + 1;
-                    if escape_char == 'p' {
-                        Ok(cc.into())
-                    } else {
-                        Ok(cc.complement().into())
-                    }
-                } else {
-                    Err(Error::syntax(format!(
-                        "Unknown character category: {}",
-                        block.iter().collect::<String>()
-                    )))
+    pub(crate) fn class_escape(&self, escape: &ClassEscape) -> CharacterClassBuilder {
+        let ecmascript = self.re_flags.language() == Language::EcmaScript;
+        let builder = match &escape.kind {
+            ClassEscapeKind::Space if ecmascript => category::ecmascript_space().into(),
+            ClassEscapeKind::Digit if ecmascript => category::ascii_digit().into(),
+            ClassEscapeKind::WordChar if ecmascript => {
+                let mut builder = category::ascii_word_char();
+                if self.re_flags.is_case_independent() {
+                    // the long s and the Kelvin sign fold to word characters
+                    builder.add_char('\u{17F}');
+                    builder.add_char('\u{212A}');
                 }
-            }
-            'w' => Ok(
-                CharacterClassBuilder::CodePointInversionListBuilder(category::word_char()).into(),
-            ),
-            'W' => Ok(
-                CharacterClassBuilder::CodePointInversionListBuilder(category::word_char())
-                    .complement()
-                    .into(),
-            ),
-            '0' => Err(Error::syntax("Octal escapes are not allowed")),
-            '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                if in_square_brackets {
-                    return Err(Error::syntax(
-                        "Backreferences not allowed within character classes",
-                    ));
-                }
-                if self.re_flags.language() != Language::XPath {
-                    return Err(Error::syntax("digit not allowed after \\"));
-                }
-                let mut back_ref = (escape_char as usize) - ('0' as usize);
-                while self.idx < self.len {
-                    let c1 = self.pattern[self.idx].to_digit(10);
-                    if let Some(c1) = c1 {
-                        let back_ref2 = back_ref * 10 + (c1 as usize);
-                        if back_ref2 > (self.capturing_open_paren_count - 1) {
-                            break;
-                        }
-                        back_ref = back_ref2;
-                        self.idx += 1;
-                    } else {
-                        break;
-                    }
-                }
-                if !self.captures.contains(&back_ref) {
-                    let explanation = if back_ref > (self.capturing_open_paren_count - 1) {
-                        "(no such group)"
-                    } else {
-                        "(group not yet closed)"
-                    };
-                    return Err(Error::syntax(format!(
-                        "invalid backreference \\{} {}",
-                        back_ref, explanation
-                    )));
+                builder.into()
+            }
+            ClassEscapeKind::Space => CharacterClassBuilder::from_str("\t\n\r "),
+            ClassEscapeKind::NameStartChar => category::name_start_char().into(),
+            ClassEscapeKind::NameChar => category::name_char().into(),
+            ClassEscapeKind::Digit => category::decimal_number().into(),
+            ClassEscapeKind::WordChar => category::word_char().into(),
+            ClassEscapeKind::Category(name) => match category::category_group(name) {
+                Ok(builder) => builder.into(),
+                // the parser only accepts known categories
+                Err(_) => CharacterClassBuilder::from_str(""),
+            },
+            ClassEscapeKind::Block(name) => match category::block(name) {
+                Ok(builder) => builder.into(),
+                Err(_) => {
+                    // the parser only accepts unknown block names with the
+                    // ';k' flag; the escape then matches any character, both
+                    // for \p and \P
+                    let mut all = CodePointInversionListBuilder::new();
+                    all.add_range32(&(0..=0x10FFFF));
+                    return all.into();
                 }
-                self.has_back_references = true;
-                Ok(CharacterClassOrBackReference::BackReference(back_ref))
-            }
-            escape_char => Err(Error::syntax(format!(
-                "Escape character '{}' not allowed",
-                escape_char
-            ))),
+            },
+        };
+        let builder = if escape.negated {
+            builder.complement()
+        } else {
+            builder
+        };
+        if ecmascript && self.re_flags.is_case_independent() {
+            Self::case_closure(builder)
+        } else {
+            builder
         }
     }
 
-    fn parse_character_class(&mut self) -> Result<CharacterClassBuilder, Error> {
-        // check for bac calling or empty class
-        if self.pattern[self.idx] != '[' {
-            return Err(Error::Internal);
-        }
-
-        // check for unterminated or empty class
-        self.idx += 1;
-        let index = self.idx;
-        if self.idx + 1 >= self.len || self.pattern[index] == ']' {
-            return Err(Error::syntax("Missing ']"));
-        }
-
-        // parse class declaration
-        let mut simple_char;
-        let mut positive = true;
-        let mut defining_range = false;
-        let mut range_start: Option<char> = None;
-        let mut range_end;
-
+    pub(crate) fn character_class(&mut self, class: &CharClassExpr) -> CharacterClassBuilder {
         let mut builder = CodePointInversionListBuilder::new();
         let mut addend: Option<CharacterClassBuilder> = None;
-        let mut subtrahend = None;
-
-        if self.there_follows("^") {
-            if self.there_follows("^-[") {
-                return Err(Error::syntax("Nothing before subtraction operator"));
-            } else if self.there_follows("^]") {
-                return Err(Error::syntax("Empty negative character group"));
-            } else {
-                positive = false;
-                self.idx += 1;
-            }
-        } else if self.there_follows("-[") {
-            return Err(Error::syntax("Nothing before subtraction operator"));
-        }
-
-        while self.idx < self.len && self.pattern[self.idx] != ']' {
-            let ch = self.pattern[self.idx];
-            simple_char = None;
-            match ch {
-                '[' => {
-                    return Err(Error::syntax("Unescaped '[' within square brackets"));
-                }
-                '\\' => {
-                    // escape always advances the stream
-                    let builder = self.escape(true)?;
-
-                    if let CharacterClassOrBackReference::CharacterClass(builder) = builder {
-                        match builder {
-                            CharacterClassBuilder::Char(c) => {
-                                simple_char = Some(c);
-                            }
-                            builder @ CharacterClassBuilder::CodePointInversionListBuilder(_) => {
-                                if defining_range {
-                                    return Err(Error::syntax(
-                                        "Multi-character escape cannot follow '-'",
-                                    ));
-                                } else if let Some(a) = addend {
-                                    addend = Some(a.union(builder));
-                                } else {
-                                    addend = Some(builder);
-                                }
-                                continue;
-                            }
+        let case_map_closer = self.re_flags.is_case_independent().then(CaseMapCloser::new);
+
+        for item in &class.items {
+            match item {
+                ClassItem::Char(literal) => {
+                    builder.add_char(literal.c);
+                    if let Some(cm) = &case_map_closer {
+                        if !literal.case_sensitive {
+                            cm.add_case_closure_to(literal.c, &mut builder);
                         }
-                    } else {
-                        unreachable!();
                     }
                 }
-                '-' => {
-                    if self.there_follows("-[") {
-                        self.idx += 1;
-                        subtrahend = Some(self.parse_character_class()?);
-                        if !self.there_follows("]") {
-                            return Err(Error::syntax("Expected closing ']' after subtraction"));
-                        }
-                    } else if self.there_follows("-]") {
-                        simple_char = Some('-');
-                        self.idx += 1;
-                    } else if range_start.is_some() {
-                        defining_range = true;
-                        self.idx += 1;
-                        continue;
-                    } else if defining_range {
-                        return Err(Error::syntax("Bad range"));
-                    } else if self.there_follows("--") && !self.there_follows("--[") {
-                        return Err(Error::syntax("Unescaped hyphen at start of range"));
-                    } else {
-                        simple_char = Some('-');
-                        self.idx += 1;
-                    }
-                }
-                _ => {
-                    simple_char = Some(ch);
-                    self.idx += 1;
-                }
-            }
-
-            // handle simple character simpleChar
-            if defining_range {
-                // if we are defining a range make it now
-                range_end = simple_char;
-
-                // actually create a range if the range is ok
-                if let (Some(start), Some(end)) = (range_start, range_end) {
-                    if start > end {
-                        // format start as hex
-                        return Err(Error::syntax(format!(
-                            "Bad character range: start ({:?}) > end ({:?})",
-                            start, end
-                        )));
-                        // Technically this is not an error in XSD, merely a
-                        // no-op; but it is so utterly pointless that it is
-                        // almost certainly a mistake; and we have no way of
-                        // indicating warnings.
-                    }
-                    builder.add_range(&(start..=end));
-                    if self.re_flags.is_case_independent() {
-                        let cm = CaseMapCloser::new();
-                        for c in start..=end {
+                ClassItem::Range(start, end) => {
+                    builder.add_range(&(start.c..=end.c));
+                    if let Some(cm) = &case_map_closer {
+                        for c in start.c..=end.c {
                             cm.add_case_closure_to(c, &mut builder);
                         }
                     }
-                    // we are done defining the range
-                    defining_range = false;
-                    range_start = None;
                 }
-            } else {
-                // if simple character and not start of range, include it (see
-                // XSD 1.1 rules)
-                if self.there_follows("-") {
-                    if self.there_follows("-[")
-                        || self.there_follows("-]")
-                        || self.there_follows("--[")
-                    {
-                        if let Some(simple_char) = simple_char {
-                            builder.add_char(simple_char);
-                        }
-                    } else if self.there_follows("--") {
-                        return Err(Error::syntax("Unescaped hyphen cannot act as end of range"));
-                    } else {
-                        range_start = simple_char;
-                    }
-                } else {
-                    if let Some(simple_char) = simple_char {
-                        builder.add_char(simple_char);
-                    }
-                    if self.re_flags.is_case_independent() {
-                        if let Some(simple_char) = simple_char {
-                            let cm = CaseMapCloser::new();
-                            cm.add_case_closure_to(simple_char, &mut builder);
-                        }
-                    }
+                ClassItem::Escape(escape) => {
+                    let escape = self.class_escape(escape);
+                    addend = Some(match addend {
+                        Some(addend) => addend.union(escape),
+                        None => escape,
+                    });
                 }
             }
         }
 
-        // shouldn't be out of input
-        if self.idx == self.len {
-            return Err(Error::syntax("Unterminated character class"));
-        }
-
-        // absorb the ']' end of class marker
-        self.idx += 1;
         let mut result: CharacterClassBuilder = builder.into();
         if let Some(addend) = addend {
             result = result.union(addend);
         }
-        if !positive {
+        if class.negated {
             result = result.complement();
         }
-        if let Some(subtrahend) = subtrahend {
+        if let Some(subtraction) = &class.subtraction {
+            let subtrahend = self.character_class(subtraction);
             result = result.difference(subtrahend);
-        }
-        Ok(result)
-    }
-
-    fn parse_atom(&mut self) -> Result<Operation, Error> {
-        // length of atom
-        let mut len_atom = 0;
-
-        // loop while we've got input
-        let mut ub = Vec::new();
-
-        while self.idx < self.len {
-            // is there a next char?
-            if (self.idx + 1) < self.len {
-                let mut c = self.pattern[self.idx + 1];
-
-                // if the next 'char' is an escape, look past the whole escape
-                if self.pattern[self.idx] == '\\' {
-                    let idx_escape = self.idx;
-
-                    self.escape(false)?;
-                    if self.idx < self.len {
-                        c = self.pattern[self.idx];
-                    }
-                    self.idx = idx_escape;
-                }
-
-                // switch on next har
-                if matches!(c, '{' | '?' | '*' | '+') {
-                    // If the next character is a quantifier operator and
-                    // our atom is non-empty, the current character should
-                    // bind to the quantifier operator rather than the atom
-                    if len_atom != 0 {
-                        break;
-                    }
-                }
-            }
-
-            // switch on currenct char
-            match self.pattern[self.idx] {
-                ']' | '.' | '[' | '(' | ')' | '|' => {
-                    break;
-                }
-                '{' | '?' | '*' | '+' => {
-                    // we should have an atom by now
-                    if len_atom == 0 {
-                        return Err(Error::syntax("Missing expression before quantifier"));
-                    }
-                    break;
-                }
-                '}' => {
-                    return Err(Error::syntax("Unescaped right curly brace"));
-                }
-                '\\' => {
-                    // get the escaped character (advanced input automatically)
-                    let idx_before_escape = self.idx;
-                    let character_class = self.escape(false)?;
-
-                    // check if it's a simple escape (as opposed to, say, a backreference)
-                    if let CharacterClassOrBackReference::CharacterClass(
-                        CharacterClassBuilder::Char(ch),
-                    ) = character_class
-                    {
-                        ub.push(ch);
-                        len_atom += 1;
-                    } else {
-                        // not a simple escape, so backup to where we were before the escape
-                        self.idx = idx_before_escape;
-                        break;
-                    }
-                }
-                c => {
-                    if (c == '^' || c == '$') && self.re_flags.language() == Language::XPath {
-                        break;
-                    }
-                    ub.push(self.pattern[self.idx]);
-                    self.idx += 1;
-                    len_atom += 1;
-                }
+            if result.is_empty() {
+                self.warnings.push(Warning {
+                    kind: WarningKind::EmptySubtraction,
+                    span: class.span,
+                });
             }
         }
-        // this shouldn't happen
-        if ub.is_empty() {
-            return Err(Error::Internal);
-        }
+        result
+    }
 
-        // return the instruction
-        Ok(Operation::from(Atom::new(ub)))
+    // The characters '.' matches.
+    pub(crate) fn any_char(&self) -> CharacterClass {
+        if self.re_flags.is_single_line() || self.re_flags.language() == Language::FullTextWildcards
+        {
+            // in XPath with the 's' flag, '.' matches everything, as a
+            // full-text wildcard does
+            return CharacterClass::all();
+        }
+        let mut inv_list = CodePointInversionListBuilder::new();
+        inv_list.add_char('\n');
+        inv_list.add_char('\r');
+        if self.re_flags.language() == Language::EcmaScript {
+            inv_list.add_char('\u{2028}');
+            inv_list.add_char('\u{2029}');
+        }
+        inv_list.complement();
+        CharacterClass::new(inv_list.build())
     }
 
-    fn parse_terminal(&mut self, flags: &[u32]) -> Result<Operation, Error> {
-        match self.pattern[self.idx] {
-            '$' => {
-                if self.re_flags.language() == Language::XPath {
-                    self.idx += 1;
-                    return Ok(Operation::from(Eol));
+    pub(crate) fn atom(&mut self, atom: &ast::Atom) -> Operation {
+        match atom {
+            ast::Atom::Literal(literal) => Operation::from(Atom::new(vec![literal.c])),
+            ast::Atom::Any(_) => Operation::from(CharClass::new(self.any_char())),
+            ast::Atom::Start(_) => Operation::from(Bol),
+            ast::Atom::End(_) => Operation::from(Eol),
+            ast::Atom::ClassEscape(escape) => {
+                Operation::from(CharClass::new(self.class_escape(escape).build()))
+            }
+            ast::Atom::CharClass(class) => {
+                Operation::from(CharClass::new(self.character_class(class).build()))
+            }
+            ast::Atom::Group(group) => {
+                let op = self.expr(&group.expr);
+                match group.capture {
+                    Some(number) => Operation::from(Capture::new(number, op)),
+                    None => op,
                 }
             }
-            '^' => {
-                if self.re_flags.language() == Language::XPath {
-                    self.idx += 1;
-                    return Ok(Operation::from(Bol));
-                }
-            }
-            '.' => {
-                self.idx += 1;
-                return Ok(Operation::from(if self.re_flags.is_single_line() {
-                    // in XPath with the 's' flag, '.' matches everything
-                    CharClass::new(CharacterClass::all())
-                } else {
-                    let mut inv_list = CodePointInversionListBuilder::new();
-                    inv_list.add_char('\n');
-                    inv_list.add_char('\r');
-                    inv_list.complement();
-                    let inv_list = inv_list.build();
-                    CharClass::new(CharacterClass::new(inv_list))
-                }));
+            ast::Atom::BackReference(back_reference) => {
+                Operation::from(BackReference::new(back_reference.group))
             }
-            '[' => {
-                let builder = self.parse_character_class()?;
-                return Ok(Operation::from(CharClass::new(builder.build())));
+            ast::Atom::WordBoundary(span) | ast::Atom::NotWordBoundary(span) => {
+                let word_chars = self.class_escape(&ClassEscape {
+                    kind: ClassEscapeKind::WordChar,
+                    negated: false,
+                    span: *span,
+                });
+                let negated = matches!(atom, ast::Atom::NotWordBoundary(_));
+                Operation::from(WordBoundary::new(word_chars.build(), negated))
             }
-            '(' => return self.parse_expr(flags),
-            ')' => return Err(Error::syntax("Unescaped closing ')'")),
-            '|' => return Err(Error::Internal),
-            ']' => return Err(Error::syntax("Unexpected closing ']'")),
-            '?' | '+' | '{' | '*' => {
-                return Err(Error::syntax("No expression before quantifier"));
+            ast::Atom::Lookahead(lookahead) => {
+                let op = self.expr(&lookahead.expr);
+                Operation::from(Lookahead::new(op, lookahead.negated))
             }
-            '\\' => {
-                // don't forget, escape() advances the input stream!
-                let idx_before_escape = self.idx;
-                let esc = self.escape(false)?;
-
-                match esc {
-                    CharacterClassOrBackReference::BackReference(back_ref) => {
-                        if self.capturing_open_paren_count <= back_ref {
-                            return Err(Error::syntax("Bad backreference"));
-                        }
-                        return Ok(Operation::from(BackReference::new(back_ref)));
-                    }
-                    CharacterClassOrBackReference::CharacterClass(CharacterClassBuilder::Char(
-                        _c,
-                    )) => {
-                        // we had a simple escape and we want to have it end up in an atom,
-                        // so we back up and fall through to the default handling
-                        self.idx = idx_before_escape;
-                    }
-                    CharacterClassOrBackReference::CharacterClass(builder) => {
-                        return Ok(Operation::from(CharClass::new(builder.build())))
-                    }
-                }
-            }
-            _ => {}
         }
-        // if it wasn't one of the above, it must be the start of an atom.
-        self.parse_atom()
     }
 
-    fn piece(&mut self, flags: &[u32]) -> Result<Operation, Error> {
-        // values to pass by refrence to terminal()
-        let terminal_flags = vec![NODE_NORMAL];
-
-        // get terminal symbol
-        let ret = self.parse_terminal(&terminal_flags)?;
-
-        // or in flags from terminal symnbol
-        let mut modified_flags = flags.to_vec();
-        modified_flags[0] = flags[0] | terminal_flags[0];
-
-        // advance input, set NODE_NULLABLE flag and do santify checks
-        if self.idx >= self.len {
-            return Ok(ret);
-        }
-
-        let quantifier_type = self.pattern[self.idx];
-
-        let has_quantifier = match quantifier_type {
-            '?' | '*' | '+' => {
-                // eat quantifier character
-                self.idx += 1;
-                true
-            }
-            '{' => {
-                self.bracket()?;
-                true
-            }
-            _ => false,
+    pub(crate) fn piece(&mut self, piece: &Piece) -> Operation {
+        let ret = self.atom(&piece.atom);
+        let Some(quantifier) = &piece.quantifier else {
+            return ret;
         };
 
-        let mut quantifier_type = Some(quantifier_type);
-        if has_quantifier {
-            // TODO: reluctant quantifiers are not allowed in XSD logic
+        let mut quantifier_type = Some(quantifier.kind);
+        if matches!(ret, Operation::Bol(_) | Operation::Eol(_)) {
+            // pretty meaningless but legal. If the quantifier allows zero
+            // occurrences, ignore the instruction. Otherwise, ignore the
+            // quantifier.
+            if quantifier.min() == 0 {
+                return Operation::from(Nothing);
+            } else {
+                quantifier_type = None
+            }
+        }
 
-            if matches!(ret, Operation::Bol(_) | Operation::Eol(_)) {
-                // pretty meaningless but legal. If the quantifier allows zero
-                // occurrences, ignore the instruction. Otherwise, ignore the
-                // quantifier.
-                if quantifier_type == Some('?')
-                    || quantifier_type == Some('*')
-                    || (quantifier_type == Some('{') && self.bracket_min == 0)
-                {
-                    return Ok(Operation::from(Nothing));
-                } else {
+        if ret.matches_empty_string() == MATCHES_ZLS_ANYWHERE {
+            match quantifier_type {
+                Some(QuantifierKind::ZeroOrOne) => {
+                    // can ignore the quantifier
                     quantifier_type = None
                 }
-            }
-
-            if ret.matches_empty_string() == MATCHES_ZLS_ANYWHERE {
-                match quantifier_type {
-                    Some('?') => {
-                        // can ignore the quantifier
-                        quantifier_type = None
-                    }
-                    Some('+') => {
-                        // '*' and '+' are equivalent
-                        quantifier_type = Some('*');
-                    }
-                    Some('{') => {
-                        // bounds are meaningless
-                        quantifier_type = Some('*')
-                    }
-                    _ => {}
+                Some(QuantifierKind::OneOrMore) => {
+                    // '*' and '+' are equivalent
+                    quantifier_type = Some(QuantifierKind::ZeroOrMore);
                 }
+                Some(
+                    QuantifierKind::Exactly(_)
+                    | QuantifierKind::AtLeast(_)
+                    | QuantifierKind::Between(_, _),
+                ) => {
+                    // bounds are meaningless
+                    quantifier_type = Some(QuantifierKind::ZeroOrMore)
+                }
+                _ => {}
             }
         }
 
-        let mut greedy = true;
-
-        // if the next character is a '?', make the quantifier non-greedy (reluctant)
-        if self.idx < self.len && self.pattern[self.idx] == '?' {
-            if self.re_flags.language() == Language::XSD {
-                return Err(Error::syntax("Reluctant quantifier not allowed in XSD"));
-            }
-            self.idx += 1;
-            greedy = false;
-        }
-        let mut min = 1;
-        let mut max = 1;
-
-        match quantifier_type {
-            Some('{') => {
-                min = self.bracket_min;
-                max = self.bracket_max;
-            }
-            Some('?') => {
-                min = 0;
-                max = 1;
-            }
-            Some('+') => {
-                min = 1;
-                max = usize::MAX;
-            }
-            Some('*') => {
-                min = 0;
-                max = usize::MAX;
-            }
-            _ => {}
-        }
+        let greedy = quantifier.greedy;
+        let (min, max) = match quantifier_type {
+            Some(QuantifierKind::ZeroOrOne) => (0, 1),
+            Some(QuantifierKind::ZeroOrMore) => (0, usize::MAX),
+            Some(QuantifierKind::OneOrMore) => (1, usize::MAX),
+            Some(QuantifierKind::Exactly(n)) => (n, n),
+            Some(QuantifierKind::AtLeast(n)) => (n, usize::MAX),
+            Some(QuantifierKind::Between(min, max)) => (min, max),
+            None => (1, 1),
+        };
 
         if max == 0 {
-            Ok(Operation::from(Nothing))
+            Operation::from(Nothing)
         } else if min == 1 && max == 1 {
-            Ok(ret)
+            ret
         } else if greedy {
             // actually do the quantifier now
             if let Some(match_length) = ret.get_match_length() {
                 if match_length > 0 {
-                    Ok(Operation::from(GreedyFixed::new(
-                        ret,
-                        min,
-                        max,
-                        match_length,
-                    )))
+                    Operation::from(GreedyFixed::new(ret, min, max, match_length))
                 } else {
                     // otherwise need to match with nothing
-                    Ok(Operation::from(Nothing))
+                    Operation::from(Nothing)
                 }
             } else {
-                Ok(Operation::from(Repeat::new(ret, min, max, true)))
+                Operation::from(Repeat::new(ret, min, max, true))
             }
         } else if let Some(match_length) = ret.get_match_length() {
-            Ok(Operation::from(ReluctantFixed::new(
-                ret,
-                min,
-                max,
-                match_length,
-            )))
+            Operation::from(ReluctantFixed::new(ret, min, max, match_length))
         } else {
-            Ok(Operation::from(Repeat::new(ret, min, max, false)))
+            Operation::from(Repeat::new(ret, min, max, false))
         }
     }
 
-    fn parse_branch(&mut self) -> Result<Operation, Error> {
-        // get each possibly quantified piece and concat
+    fn branch(&mut self, branch: &ast::Branch) -> Operation {
+        // concatenate the pieces; unquantified characters that follow each
+        // other are combined into a single atom
         let mut current = None;
-        let mut quantifier_flags = vec![1];
-        while self.idx < self.len && self.pattern[self.idx] != '|' && self.pattern[self.idx] != ')'
-        {
-            // get new node
-            quantifier_flags[0] = NODE_NORMAL;
-            let op = self.piece(&quantifier_flags)?;
-            if let Some(c) = current {
-                current = Some(Self::make_sequence(c, op));
-            } else {
-                current = Some(op);
+        let mut atom = Vec::new();
+        for piece in &branch.pieces {
+            if let (ast::Atom::Literal(literal), None) = (&piece.atom, &piece.quantifier) {
+                atom.push(literal.c);
+                continue;
+            }
+            if !atom.is_empty() {
+                let op = Operation::from(Atom::new(std::mem::take(&mut atom)));
+                current = Some(Self::append(current, op));
             }
+            let op = self.piece(piece);
+            current = Some(Self::append(current, op));
         }
-        if let Some(current) = current {
-            Ok(current)
-        } else {
-            // if we don't run loop, make a nothing node
-            Ok(Operation::from(Nothing))
+        if !atom.is_empty() {
+            current = Some(Self::append(current, Operation::from(Atom::new(atom))));
         }
+        // if there are no pieces, make a nothing node
+        current.unwrap_or(Operation::from(Nothing))
     }
 
-    fn parse_expr(&mut self, compiler_flags: &[u32]) -> Result<Operation, Error> {
-        // create open paren node unless we were called from the top level (which has no parens)
-        let mut paren = None;
-        let mut group = 0;
-        let mut branches = Vec::new();
-        let close_parens = self.capturing_open_paren_count;
-        let mut capturing = true;
-        if (compiler_flags[0] & NODE_TOPLEVEL) == 0 && self.pattern[self.idx] == '(' {
-            // if it's a cluster (rather than a proper subexpression ie with backrefs)
-            if (self.idx + 2) < self.len
-                && self.pattern[self.idx + 1] == '?'
-                && self.pattern[self.idx + 2] == ':'
-            {
-                if self.re_flags.language() != Language::XPath {
-                    return Err(Error::syntax("Non-capturing groups only allowed in XPath"));
-                }
-                paren = Some(2);
-                self.idx += 3;
-                capturing = false;
-            } else {
-                paren = Some(1);
-                self.idx += 1;
-                group = self.capturing_open_paren_count;
-                self.capturing_open_paren_count += 1;
-            }
-        }
-        let mut compiler_flags = compiler_flags.to_vec();
-        compiler_flags[0] &= !NODE_TOPLEVEL;
-
-        // process contents of first branch node
-        branches.push(self.parse_branch()?);
-        // loop through brnaches
-        while self.idx < self.len && self.pattern[self.idx] == '|' {
-            self.idx += 1;
-            branches.push(self.parse_branch()?);
+    fn append(current: Option<Operation>, op: Operation) -> Operation {
+        match current {
+            Some(current) => Self::make_sequence(current, op),
+            None => op,
         }
+    }
 
-        let mut op = if branches.len() == 1 {
+    fn expr(&mut self, expr: &Expr) -> Operation {
+        let mut branches = expr
+            .branches
+            .iter()
+            .map(|branch| self.branch(branch))
+            .collect::<Vec<_>>();
+        if branches.len() == 1 {
             branches.remove(0)
         } else {
-            Operation::from(Choice::new(branches.into_iter().collect()))
-        };
-
-        // create an ending node (either a close paren or an OP_END)
-        if paren.is_some() {
-            if self.idx < self.len && self.pattern[self.idx] == ')' {
-                self.idx += 1;
-            } else {
-                return Err(Error::syntax("Missing close paren"));
-            }
-            if capturing {
-                op = Operation::from(Capture::new(group, op));
-                self.captures.insert(close_parens);
-            }
-        } else {
-            op = Self::make_sequence(op, Operation::from(EndProgram));
+            Operation::from(Choice::new(branches))
         }
-        Ok(op)
     }
 
-    fn there_follows(&self, s: &str) -> bool {
-        let chars = s.chars().collect::<Vec<_>>();
-
-        if (self.idx + chars.len()) > self.len {
-            return false;
-        }
-        for (i, c) in chars.iter().enumerate() {
-            if self.pattern[self.idx + i] != *c {
-                return false;
-            }
-        }
-        true
+    // The largest product of the bounds of nested quantifiers, such as 100
+    // for `(a{10}){10}`. An unbounded quantifier counts one more than its
+    // minimum, for the copy that is repeated.
+    fn repetition(expr: &Expr) -> usize {
+        let mut repetition = 1;
+        for piece in expr.branches.iter().flat_map(|branch| &branch.pieces) {
+            let inner = match &piece.atom {
+                ast::Atom::Group(group) => Self::repetition(&group.expr),
+                ast::Atom::Lookahead(lookahead) => Self::repetition(&lookahead.expr),
+                _ => 1,
+            };
+            let bound = match &piece.quantifier {
+                Some(quantifier) => quantifier
+                    .max()
+                    .unwrap_or_else(|| quantifier.min().saturating_add(1)),
+                None => 1,
+            };
+            repetition = repetition.max(inner.saturating_mul(bound.max(1)));
+        }
+        repetition
     }
 
     fn make_sequence(o1: Operation, o2: Operation) -> Operation {
@@ -947,81 +417,93 @@ impl ReCompiler {
         }
     }
 
-    pub(crate) fn compile(mut self) -> Result<ReProgram, Error> {
+    // In ECMAScript, with the 'i' flag, a character matches a class escape such
+    // as \p{Lu} if it has the same case folding as a character in it, so the
+    // class is extended with the case variants of its characters. Only
+    // characters that change when case mapped have any.
+    fn case_closure(builder: CharacterClassBuilder) -> CharacterClassBuilder {
+        let class = builder.build();
+        let set = class.as_code_point_inversion_list();
+        let closer = CaseMapCloser::new();
+        let mut closed = CodePointInversionListBuilder::new();
+        closed.add_set(set);
+        for range in sets::changes_when_casemapped().iter_ranges() {
+            for c in range.filter_map(char::from_u32) {
+                if set.contains(c) {
+                    closer.add_case_closure_to(c, &mut closed);
+                }
+            }
+        }
+        closed.into()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn compile(self) -> Result<ReProgram, Error> {
+        self.compile_with_diagnostics()
+            .map(|(program, _warnings)| program)
+    }
+
+    pub(crate) fn compile_with_diagnostics(mut self) -> Result<(ReProgram, Vec<Warning>), Error> {
+        if self.pattern.len() > self.limits.max_pattern_length {
+            return Err(Error::LimitExceeded(format!(
+                "Pattern length {} exceeds the limit of {}",
+                self.pattern.len(),
+                self.limits.max_pattern_length
+            )));
+        }
         if self.re_flags.is_literal() {
             // 'q' flag is set
             // create a string node
             let ret = Operation::from(Atom::new(self.pattern.clone()));
             let end_node = Operation::from(EndProgram);
             let seq = Self::make_sequence(ret, end_node);
-            Ok(ReProgram::new(
-                self.pattern,
-                seq,
-                Some(self.capturing_open_paren_count),
-                self.re_flags.clone(),
-            ))
-        } else {
-            if self.re_flags.is_allow_whitespace() {
-                // 'x' flag is set. preprocess the expression to strip whitespace,
-                // other than between square brackets
-                let mut sb = Vec::new();
-                let mut nesting = 0;
-                let mut escaped = false;
-                for ch in self.pattern.iter() {
-                    match ch {
-                        '\\' if !escaped => {
-                            escaped = true;
-                            sb.push(*ch);
-                        }
-                        '[' if !escaped => {
-                            nesting += 1;
-                            sb.push(*ch);
-                        }
-                        ']' if !escaped => {
-                            nesting -= 1;
-                            sb.push(*ch);
-                        }
-                        _ => {
-                            // TODO: wrong whitespace
-                            if nesting == 0 && ch.is_ascii_whitespace() {
-                                // no action
-                            } else {
-                                escaped = false;
-                                sb.push(*ch);
-                            }
-                        }
-                    }
-                }
-                self.pattern = sb;
-                self.len = self.pattern.len();
-            }
-
-            // initialize pass by reference flags value
-            let compiler_flags = vec![NODE_TOPLEVEL];
-
-            // parse expression
-            let operation = self.parse_expr(&compiler_flags)?;
-
-            // should be at end of input
-            if self.idx != self.len {
-                if self.pattern[self.idx] == ')' {
-                    return Err(Error::syntax("Unmatched close paren"));
-                }
-                return Err(Error::syntax("Unexpected input remains"));
-            }
-            let operation = operation.optimize(&self.re_flags);
-
-            let mut program = ReProgram::new(
-                self.pattern,
-                operation,
-                Some(self.capturing_open_paren_count),
-                self.re_flags.clone(),
-            );
-            if self.has_back_references {
-                program.optimization_flags |= OPT_HASBACKREFS;
-            }
-            Ok(program)
-        }
+            // the implicit outer parens
+            let max_parens = 1;
+            return Ok((
+                ReProgram::new(self.pattern, seq, max_parens, self.re_flags.clone()),
+                self.warnings,
+            ));
+        }
+
+        let re_flags = self.re_flags.clone();
+        let mut parser =
+            ReParser::new(std::mem::take(&mut self.pattern), &re_flags).with_limits(&self.limits);
+        let expr = parser.parse()?;
+        self.warnings = std::mem::take(&mut parser.warnings);
+        let repetition = Self::repetition(&expr);
+        if repetition > self.limits.max_repetition {
+            return Err(Error::LimitExceeded(format!(
+                "Nested repetition {} exceeds the limit of {}",
+                repetition, self.limits.max_repetition
+            )));
+        }
+
+        let mut operation = self.expr(&expr);
+        if self.re_flags.language() == Language::FullTextWildcards {
+            // a wildcard pattern matches a whole token
+            operation = Self::make_sequence(Operation::from(Bol), operation);
+            operation = Self::make_sequence(operation, Operation::from(Eol));
+        }
+        let operation = Self::make_sequence(operation, Operation::from(EndProgram));
+        let operation = operation.optimize(&self.re_flags);
+        let size = operation.size();
+        if size > self.limits.max_program_size {
+            return Err(Error::LimitExceeded(format!(
+                "Program size {} exceeds the limit of {}",
+                size, self.limits.max_program_size
+            )));
+        }
+
+        let mut program = ReProgram::new(
+            parser.pattern,
+            operation,
+            parser.capturing_open_paren_count,
+            self.re_flags.clone(),
+        );
+        if parser.has_back_references {
+            program.optimization_flags |= OPT_HASBACKREFS;
+        }
+        Ok((program, self.warnings))
     }
 
     pub(crate) fn no_ambiguity(
@@ -1052,6 +534,7 @@ mod tests {
     use insta::assert_debug_snapshot;
 
     use super::*;
+    use crate::re_flags::Language;
 
     fn compiled(pattern: &str) -> ReProgram {
         let re_flags = ReFlags::new("", Language::XPath).unwrap();
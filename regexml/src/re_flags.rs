@@ -1,17 +1,43 @@
 use crate::re_compiler::Error;
 
-/// Regular expressions can be compiled for XSD, or following
-/// the XPath superset.
+/// Regular expressions can be compiled for XSD, following the XPath
+/// superset, or following ECMAScript as JSON Schema uses it. XQuery and XPath
+/// Full Text wildcard patterns can be compiled as well.
 ///
 /// Only the XPath superset is currently properly tested.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
-pub(crate) enum Language {
+pub enum Language {
     /// Follow the XML Schema definition of regular expressions only,
     /// don't allow XPath extensions
     XSD,
     /// Follow the XPath 3.1 definition of regular expressions, which
     /// includes some additional features.
     XPath,
+    /// Follow the XPath 4.0 definition of regular expressions. The syntax is
+    /// that of XPath 3.1, except that the `x` flag also removes whitespace
+    /// inside character class expressions. Replacing, tokenizing and
+    /// analyzing strings allow patterns that match the empty string.
+    XPath40,
+    /// Follow the ECMAScript (JavaScript) syntax in Unicode mode, as JSON
+    /// Schema recommends for its `pattern` keyword. `\d`, `\w` and `\b`
+    /// only know ASCII characters, and there are word boundaries and
+    /// lookaheads, but no lookbehinds or named groups.
+    EcmaScript,
+    /// Follow the wildcard syntax of the XQuery and XPath Full Text 3.0
+    /// "using wildcards" option: `.` followed by nothing, `?`, `*`, `+` or
+    /// `{n,m}` matches any characters, a backslash escapes the character
+    /// that follows it, and everything else stands for itself. A pattern
+    /// has to match the whole string, as it matches a whole token. Only the
+    /// `i` flag is available.
+    FullTextWildcards,
+}
+
+impl Language {
+    // whether the XPath extensions to XSD regular expressions are allowed
+    pub(crate) fn is_xpath(self) -> bool {
+        matches!(self, Language::XPath | Language::XPath40)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +71,12 @@ impl ReFlags {
             if c == ';' {
                 break;
             }
+            if language == Language::FullTextWildcards && matches!(c, 'm' | 's' | 'x' | 'q') {
+                return Err(Error::InvalidFlags(format!(
+                    "'{}' flag is not available for full-text wildcards",
+                    c
+                )));
+            }
             match c {
                 'i' => {
                     r.case_independent = true;
@@ -57,7 +89,7 @@ impl ReFlags {
                 }
                 'q' => {
                     r.literal = true;
-                    if language != Language::XPath {
+                    if !language.is_xpath() {
                         return Err(Error::InvalidFlags(
                             "'q' flag requires XPath 3.0 to be enabled".to_string(),
                         ));
@@ -65,6 +97,11 @@ impl ReFlags {
                 }
                 'x' => {
                     r.allow_whitespace = true;
+                    if language == Language::EcmaScript {
+                        return Err(Error::InvalidFlags(
+                            "'x' flag is not available in ECMAScript".to_string(),
+                        ));
+                    }
                 }
                 _ => {
                     return Err(Error::InvalidFlags(format!("Unrecognized flag '{}'", c)));
@@ -115,15 +152,42 @@ impl ReFlags {
         self.language
     }
 
-    // This appears to do nothing in the Java original; it's triggered
-    // by the 'g' flag
-    // pub(crate) fn is_debug(&self) -> bool {
-    //     self.debug
-    // }
+    // In the Java original this does nothing; here the 'g' flag traces
+    // matching to stderr.
+    pub(crate) fn is_debug(&self) -> bool {
+        self.debug
+    }
+
+    // The flags as a string, in the order they are listed in the XPath
+    // specification, followed by the extensions after a ';'.
+    pub(crate) fn to_flag_string(&self) -> String {
+        let mut flags = String::new();
+        for (set, c) in [
+            (self.single_line, 's'),
+            (self.multi_line, 'm'),
+            (self.case_independent, 'i'),
+            (self.allow_whitespace, 'x'),
+            (self.literal, 'q'),
+        ] {
+            if set {
+                flags.push(c);
+            }
+        }
+        if self.debug || self.allow_unknown_block_names {
+            flags.push(';');
+            if self.debug {
+                flags.push('g');
+            }
+            if self.allow_unknown_block_names {
+                flags.push('k');
+            }
+        }
+        flags
+    }
 
-    // We haven't implemented this support yet; in the Java original it
-    // gives a warning.
-    // pub(crate) fn is_allow_unknown_block_names(&self) -> bool {
-    //     self.allow_unknown_block_names
-    // }
+    // Unknown block names are accepted (matching any character) and
+    // reported as a warning rather than an error.
+    pub(crate) fn is_allow_unknown_block_names(&self) -> bool {
+        self.allow_unknown_block_names
+    }
 }
@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 #[cfg(test)]
 use crate::operation::Operation;
@@ -7,9 +7,14 @@ use icu_casemap::CaseMapper;
 use crate::{history::History, op_repeat::Repeat};
 
 use crate::{
+    character_class::CharacterClass,
+    diagnosis::{Diagnosis, Expectations},
+    haystack::{Haystack, Search},
     operation::OperationControl,
     re_compiler::Error,
+    re_flags::Language,
     re_program::{ReProgram, OPT_HASBACKREFS, OPT_HASBOL},
+    trace::TraceSink,
 };
 
 #[derive(Debug)]
@@ -17,11 +22,19 @@ pub(crate) struct ReMatcher<'a> {
     // current program
     pub(crate) program: &'a ReProgram,
     // string being matched against
-    pub(crate) search: Vec<char>,
+    pub(crate) search: Search<'a>,
 
     case_mapper: CaseMapper,
     // parenthesized subexpressions
     state: RefCell<State>,
+    // receives trace events, if tracing
+    tracer: Option<Box<dyn TraceSink + 'a>>,
+    // nesting depth of the operation being traced
+    trace_depth: Cell<usize>,
+    // whether an operation tried to read past the end of the string
+    hit_end: Cell<bool>,
+    // what failed operations expected, if diagnosing
+    expectations: Option<RefCell<Expectations>>,
 }
 
 #[derive(Debug)]
@@ -47,15 +60,85 @@ impl State {
 
 impl<'a> ReMatcher<'a> {
     pub(crate) fn new(program: &'a ReProgram, search: &str) -> Self {
-        let search = search.chars().collect::<Vec<_>>();
+        Self::with_search(program, Search::Chars(search.chars().collect()))
+    }
+
+    pub(crate) fn new_in(program: &'a ReProgram, haystack: &'a dyn Haystack) -> Self {
+        Self::with_search(program, Search::new(haystack))
+    }
+
+    fn with_search(program: &'a ReProgram, search: Search<'a>) -> Self {
         Self {
             program,
             search,
             state: RefCell::new(State::new()),
             case_mapper: CaseMapper::new(),
+            tracer: None,
+            trace_depth: Cell::new(0),
+            hit_end: Cell::new(false),
+            expectations: None,
+        }
+    }
+
+    pub(crate) fn with_tracer(mut self, tracer: Box<dyn TraceSink + 'a>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    pub(crate) fn with_expectations(mut self) -> Self {
+        self.expectations = Some(RefCell::new(Expectations::new()));
+        self
+    }
+
+    // Record that a failed operation would have accepted a character at a
+    // position.
+    pub(crate) fn expect_char(&self, position: usize, c: char) {
+        if let Some(expectations) = &self.expectations {
+            expectations.borrow_mut().char(position, c);
+        }
+    }
+
+    pub(crate) fn expect_class(&self, position: usize, class: &CharacterClass) {
+        if let Some(expectations) = &self.expectations {
+            expectations.borrow_mut().class(position, class);
+        }
+    }
+
+    pub(crate) fn expect_end(&self, position: usize) {
+        if let Some(expectations) = &self.expectations {
+            expectations.borrow_mut().end(position);
         }
     }
 
+    // Why the match failed, if diagnosing.
+    pub(crate) fn diagnosis(&self) -> Option<Diagnosis> {
+        let expectations = self.expectations.as_ref()?.borrow();
+        let found = self.search.get(expectations.position());
+        Some(expectations.diagnosis(found))
+    }
+
+    pub(crate) fn trace_sink(&self) -> Option<&dyn TraceSink> {
+        self.tracer.as_deref()
+    }
+
+    pub(crate) fn trace_depth(&self) -> usize {
+        self.trace_depth.get()
+    }
+
+    pub(crate) fn set_trace_depth(&self, depth: usize) {
+        self.trace_depth.set(depth);
+    }
+
+    // Whether the last search tried to read past the end of the string, so
+    // that more input could have changed its result.
+    pub(crate) fn hit_end(&self) -> bool {
+        self.hit_end.get()
+    }
+
+    pub(crate) fn set_hit_end(&self) {
+        self.hit_end.set(true);
+    }
+
     pub(crate) fn match_at(&self, i: usize, anchored: bool) -> bool {
         // initialize start pointer, paren cache and paren count
         self.set_paren_count(1);
@@ -64,12 +147,16 @@ impl<'a> ReMatcher<'a> {
 
         // allocate backref arrays (unless optimizations indicate otherwise)
         if self.program.optimization_flags & OPT_HASBACKREFS != 0 {
-            self.state.borrow_mut().start_backref = vec![None; self.program.max_parens.unwrap()];
-            self.state.borrow_mut().end_backref = vec![None; self.program.max_parens.unwrap()];
+            self.state.borrow_mut().start_backref = vec![None; self.program.max_parens];
+            self.state.borrow_mut().end_backref = vec![None; self.program.max_parens];
+        }
+
+        if let Some(tracer) = &self.tracer {
+            tracer.attempt(i);
         }
 
         // match against string
-        let mut iter = self.program.operation.matches_iter(self, i);
+        let mut iter = self.program.operation.traced_matches_iter(self, i);
         if let Some(idx) = iter.next() {
             self.set_paren_end(0, idx);
             true
@@ -83,6 +170,7 @@ impl<'a> ReMatcher<'a> {
     pub(crate) fn matches(&mut self, i: usize) -> bool {
         // clear the captured group state
         self.state.borrow_mut().capture_state = CaptureState::new();
+        self.hit_end.set(false);
 
         // can we optimize the search by looking for new lines?
         if self.program.optimization_flags & OPT_HASBOL == OPT_HASBOL {
@@ -95,31 +183,24 @@ impl<'a> ReMatcher<'a> {
             if self.match_at(i, false) {
                 return true;
             }
-            // TODO: transliterated as close to the Java code as possible to
-            // make sure it works correctly. But can be cleaned up.
-            let mut nl: isize = i.try_into().unwrap();
+            let mut nl = i;
             loop {
-                nl = self
-                    .search
-                    .iter()
-                    .enumerate()
-                    .skip(nl.try_into().unwrap())
-                    .find(|(_, c)| **c == '\n')
-                    .map(|(i, _)| i.try_into().unwrap())
-                    .unwrap_or(-1)
-                    + 1;
-
-                if nl >= self.search.len().try_into().unwrap() || nl <= 0 {
+                // the position after the next newline
+                nl = match (nl..self.search.len()).find(|j| self.is_new_line(*j)) {
+                    Some(newline) => newline + 1,
+                    None => return false,
+                };
+                if nl >= self.search.len() && !self.bol_at_end() {
                     // "^" does not match a NL at the end of the string
                     return false;
-                } else if self.match_at(nl.try_into().unwrap(), false) {
+                } else if self.match_at(nl, false) {
                     return true;
                 }
             }
         }
 
         // is the string long enough to match?
-        let actual_length = self.search.len() - i;
+        let actual_length = self.search.len().saturating_sub(i);
         if actual_length < self.program.minimum_length {
             return false;
         }
@@ -128,21 +209,17 @@ impl<'a> ReMatcher<'a> {
         if let Some(prefix) = &self.program.prefix {
             // prefixed-anchored matching is possible
             let ignore_case = self.program.flags.is_case_independent();
-            for j in i..self.search.len() + 1 - prefix.len() {
+            for j in i..(self.search.len() + 1).saturating_sub(prefix.len()) {
                 let mut prefix_ok = true;
-                if ignore_case {
-                    for (k, prefix) in prefix.iter().enumerate() {
-                        if !self.equal_case_blind(self.search[k + j], *prefix) {
-                            prefix_ok = false;
-                            break;
-                        }
-                    }
-                } else {
-                    for (k, prefix) in prefix.iter().enumerate() {
-                        if self.search[k + j] != *prefix {
-                            prefix_ok = false;
-                            break;
-                        }
+                for (k, prefix) in prefix.iter().enumerate() {
+                    let same = match self.search.get(k + j) {
+                        Some(c) if ignore_case => self.equal_case_blind(c, *prefix),
+                        Some(c) => c == *prefix,
+                        None => false,
+                    };
+                    if !same {
+                        prefix_ok = false;
+                        break;
                     }
                 }
 
@@ -159,7 +236,9 @@ impl<'a> ReMatcher<'a> {
             // no prefix known; but the first character must match a predicate
             if let Some(inv_list) = &self.program.initial_char_class {
                 for j in i..self.search.len() {
-                    if inv_list.contains(self.search[j]) && self.match_at(j, false) {
+                    if self.search.get(j).is_some_and(|c| inv_list.contains(c))
+                        && self.match_at(j, false)
+                    {
                         return true;
                     }
                 }
@@ -185,7 +264,28 @@ impl<'a> ReMatcher<'a> {
         self.matches(0)
     }
 
+    // Like `matches`, but only tries a match that starts at `i`.
+    pub(crate) fn matches_at(&mut self, i: usize) -> bool {
+        self.state.borrow_mut().capture_state = CaptureState::new();
+        self.hit_end.set(false);
+        i <= self.search.len() && self.match_at(i, false)
+    }
+
+    // The start and end of the match found last.
+    pub(crate) fn match_range(&self) -> Option<(usize, usize)> {
+        self.get_paren_start(0).zip(self.get_paren_end(0))
+    }
+
     fn check_preconditions(&self, start: usize) -> bool {
+        // preconditions look ahead of the match, so reaching the end while
+        // checking them says nothing about the match
+        let hit_end = self.hit_end.get();
+        let result = self.preconditions_hold(start);
+        self.hit_end.set(hit_end);
+        result
+    }
+
+    fn preconditions_hold(&self, start: usize) -> bool {
         for precondition in &self.program.preconditions {
             if let Some(fixed_position) = precondition.fixed_position {
                 let match_ = precondition
@@ -223,31 +323,13 @@ impl<'a> ReMatcher<'a> {
     }
 
     pub(crate) fn replace(&mut self, replacement: &[char]) -> Result<Vec<char>, Error> {
-        // string to return
-        let mut result = Vec::new();
-
-        // start at position 0 and search the whole string
-        let mut pos = 0;
-        let len = self.search.len();
-
-        let mut first_match = true;
-        let mut simple_replacement = false;
-
-        // try a match at each position
-        while pos < len && self.matches(pos) {
-            // append chars from input string before match
-            // TODO: what happens if this returns None as there is no paren start?
-            if let Some(start) = self.get_paren_start(0) {
-                result.extend(&self.search[pos..start]);
-            }
-            if first_match {
-                simple_replacement = self.program.flags.is_literal();
-                first_match = false;
-            }
-
+        // once it's known there are no references to captured substrings,
+        // the replacement is used as it is
+        let mut simple_replacement = self.program.flags.is_literal();
+        self.replace_with(|matcher, result| {
             if !simple_replacement {
                 // process references to captured substrings
-                let max_capture = self.program.max_parens.unwrap() - 1;
+                let max_capture = matcher.program.max_parens.saturating_sub(1);
                 simple_replacement = true;
                 let mut i = 0;
                 while i < replacement.len() {
@@ -294,7 +376,7 @@ impl<'a> ReMatcher<'a> {
                             let mut n = (ch as usize) - ('0' as usize);
                             if max_capture <= 9 {
                                 if max_capture >= n {
-                                    let captured = self.get_paren(n);
+                                    let captured = matcher.get_paren(n);
                                     if let Some(captured) = captured {
                                         result.extend(captured);
                                     }
@@ -319,7 +401,7 @@ impl<'a> ReMatcher<'a> {
                                         break;
                                     }
                                 }
-                                let captured = self.get_paren(n);
+                                let captured = matcher.get_paren(n);
                                 if let Some(captured) = captured {
                                     result.extend(captured);
                                 }
@@ -335,32 +417,78 @@ impl<'a> ReMatcher<'a> {
                 // append substitution without processing backreferences
                 result.extend(replacement);
             }
+            Ok(())
+        })
+    }
 
-            // move forward, skipping past match
-            let mut newpos = self.get_paren_end(0).unwrap();
+    // Replaces every match with what `replace` appends for it. A zero-length
+    // match is replaced as well, after which matching goes on after the
+    // next character.
+    pub(crate) fn replace_with(
+        &mut self,
+        mut replace: impl FnMut(&Self, &mut Vec<char>) -> Result<(), Error>,
+    ) -> Result<Vec<char>, Error> {
+        // string to return
+        let mut result = Vec::new();
 
-            if newpos == pos {
-                newpos += 1;
-            }
+        // start at position 0 and search the whole string
+        let mut pos = 0;
+        let mut search_start = 0;
+        let len = self.search.len();
+
+        let mut first_match = true;
+
+        // try a match at each position
+        while search_start <= len && self.matches(search_start) {
+            let (start, end) = self
+                .match_range()
+                .ok_or_else(|| Error::internal("Match without a position"))?;
+            // append chars from input string before match
+            result.extend(self.search.slice(pos..start));
+            first_match = false;
 
-            // try new position
-            pos = newpos;
+            replace(self, &mut result)?;
+
+            // move forward, skipping past match
+            pos = end;
+            search_start = end;
+            if start == end {
+                // keep the character after a zero-length match
+                if end < len {
+                    result.extend(self.search.slice(end..end + 1));
+                    pos = end + 1;
+                }
+                search_start = end + 1;
+            }
         }
 
         // if no matches were found, return the input unchanged
         if first_match {
-            return Ok(self.search.to_vec());
+            return Ok(self.search.slice(0..len));
         }
 
         // if there's remaining input, append it
-        result.extend(self.search[pos..len].iter());
+        result.extend(self.search.slice(pos..len));
 
         // return the string buffer
         Ok(result)
     }
 
     pub(crate) fn is_new_line(&self, i: usize) -> bool {
-        self.search[i] == '\n'
+        match self.search.get(i) {
+            Some('\n') => true,
+            // ECMAScript has more line terminators
+            Some('\r' | '\u{2028}' | '\u{2029}') => {
+                self.program.flags.language() == Language::EcmaScript
+            }
+            _ => false,
+        }
+    }
+
+    // Whether "^" matches after a line terminator at the end of the input,
+    // as it does in ECMAScript but not in XPath.
+    pub(crate) fn bol_at_end(&self) -> bool {
+        self.program.flags.language() == Language::EcmaScript
     }
 
     #[cfg(test)]
@@ -369,7 +497,7 @@ impl<'a> ReMatcher<'a> {
         let positions = op.matches_iter(self, start_position);
         let ranges = positions.map(|e| start_position..e);
         ranges
-            .map(|r| self.search[r].iter().collect::<String>())
+            .map(|r| self.search.slice(r).into_iter().collect::<String>())
             .collect()
     }
 
@@ -377,6 +505,11 @@ impl<'a> ReMatcher<'a> {
         if a == b {
             return true;
         }
+        // ECMAScript compares the simple case folding, in which the long s
+        // is the same as an s
+        if self.program.flags.language() == Language::EcmaScript {
+            return self.case_mapper.simple_fold(a) == self.case_mapper.simple_fold(b);
+        }
         let lowercase_a = self.case_mapper.simple_lowercase(a);
         let lowercase_b = self.case_mapper.simple_lowercase(b);
         if lowercase_a == lowercase_b {
@@ -420,12 +553,12 @@ impl<'a> ReMatcher<'a> {
 
     // capture state related
 
-    pub(crate) fn get_paren(&self, group_nr: usize) -> Option<&[char]> {
+    pub(crate) fn get_paren(&self, group_nr: usize) -> Option<Vec<char>> {
         if group_nr < self.paren_count() {
             if let (Some(start), Some(end)) =
                 (self.get_paren_start(group_nr), self.get_paren_end(group_nr))
             {
-                return Some(&self.search[start..end]);
+                return Some(self.search.slice(start..end));
             }
         }
         None
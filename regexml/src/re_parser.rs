@@ -0,0 +1,1245 @@
+use ahash::{HashSet, HashSetExt};
+
+use crate::{
+    ast::{
+        Atom, BackReference, Branch, CharClassExpr, ClassEscape, ClassEscapeKind, ClassItem, Expr,
+        Group, Literal, Lookahead, Piece, Quantifier, QuantifierKind,
+    },
+    category,
+    diagnostics::{Span, Warning, WarningKind},
+    limits::{CompileLimits, MAX_NESTING_DEPTH},
+    re_compiler::Error,
+    re_flags::{Language, ReFlags},
+};
+
+enum Escape {
+    Char(char),
+    Class(ClassEscapeKind, bool),
+    BackReference(usize),
+    // `\b`, or `\B` if negated (ECMAScript only)
+    WordBoundary(bool),
+}
+
+/// Parses a regular expression into an [`Expr`].
+pub(crate) struct ReParser<'a> {
+    // input string; whitespace is stripped from this with the 'x' flag
+    pub(crate) pattern: Vec<char>,
+    // offset in the original pattern for each character in the input
+    // string; these differ if whitespace was stripped by the 'x' flag
+    offsets: Vec<usize>,
+    // length of the pattern string
+    len: usize,
+    // current input index into pattern
+    idx: usize,
+    // total number of paren pairs, including the implicit outer parens
+    pub(crate) capturing_open_paren_count: usize,
+    // groups that have been closed
+    captures: HashSet<usize>,
+    pub(crate) has_back_references: bool,
+
+    re_flags: &'a ReFlags,
+    limits: CompileLimits,
+    // number of groups the input index is in
+    depth: usize,
+
+    // non-fatal problems found while parsing
+    pub(crate) warnings: Vec<Warning>,
+}
+
+impl<'a> ReParser<'a> {
+    pub(crate) fn new(pattern: Vec<char>, re_flags: &'a ReFlags) -> Self {
+        Self {
+            len: pattern.len(),
+            offsets: (0..pattern.len()).collect(),
+            pattern,
+            idx: 0,
+            // the implicit outer parens
+            capturing_open_paren_count: 1,
+            captures: HashSet::new(),
+            has_back_references: false,
+            re_flags,
+            limits: CompileLimits::unlimited(),
+            depth: 0,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_limits(mut self, limits: &CompileLimits) -> Self {
+        self.limits = limits.clone();
+        self
+    }
+
+    // The span in the original pattern that covers the input from start up
+    // to (but not including) end.
+    fn span(&self, start: usize, end: usize) -> Span {
+        let offset = |i: usize| {
+            self.offsets
+                .get(i)
+                .copied()
+                .unwrap_or_else(|| self.offsets.last().map_or(0, |last| last + 1))
+        };
+        if end > start {
+            Span::new(offset(start), offset(end - 1) + 1)
+        } else {
+            Span::new(offset(start), offset(start))
+        }
+    }
+
+    fn warn(&mut self, kind: WarningKind, start: usize, end: usize) {
+        let span = self.span(start, end);
+        self.warnings.push(Warning { kind, span });
+    }
+
+    pub(crate) fn parse(&mut self) -> Result<Expr, Error> {
+        if self.re_flags.is_literal() {
+            // 'q' flag is set; every character stands for itself
+            let pieces = (0..self.len)
+                .map(|i| {
+                    let span = self.span(i, i + 1);
+                    Piece {
+                        atom: Atom::Literal(Literal {
+                            c: self.pattern[i],
+                            escaped: false,
+                            case_sensitive: false,
+                            span,
+                        }),
+                        quantifier: None,
+                        span,
+                    }
+                })
+                .collect();
+            let span = self.span(0, self.len);
+            self.idx = self.len;
+            return Ok(Expr {
+                branches: vec![Branch { pieces, span }],
+                span,
+            });
+        }
+
+        if self.re_flags.language() == Language::FullTextWildcards {
+            return self.parse_wildcards();
+        }
+
+        if self.re_flags.is_allow_whitespace() {
+            self.strip_whitespace();
+        }
+
+        let expr = self.parse_expr()?;
+
+        // should be at end of input
+        if self.idx != self.len {
+            if self.pattern[self.idx] == ')' {
+                return Err(Error::syntax("Unmatched close paren"));
+            }
+            return Err(Error::syntax("Unexpected input remains"));
+        }
+        Ok(expr)
+    }
+
+    // A full-text wildcard pattern: a sequence of characters, escaped
+    // characters and wildcards, without groups or alternatives.
+    fn parse_wildcards(&mut self) -> Result<Expr, Error> {
+        let mut pieces = Vec::new();
+        while self.idx < self.len {
+            let start = self.idx;
+            let piece = match self.pattern[self.idx] {
+                '.' => {
+                    self.idx += 1;
+                    let atom = Atom::Any(self.span(start, self.idx));
+                    let quantifier = self.wildcard_quantifier()?;
+                    Piece {
+                        atom,
+                        quantifier,
+                        span: self.span(start, self.idx),
+                    }
+                }
+                '\\' => {
+                    if self.idx + 1 >= self.len {
+                        return Err(Error::syntax("Escape character at end of wildcard pattern"));
+                    }
+                    self.idx += 2;
+                    let span = self.span(start, self.idx);
+                    Piece {
+                        atom: Atom::Literal(Literal {
+                            c: self.pattern[start + 1],
+                            escaped: true,
+                            case_sensitive: false,
+                            span,
+                        }),
+                        quantifier: None,
+                        span,
+                    }
+                }
+                c => {
+                    self.idx += 1;
+                    let span = self.span(start, self.idx);
+                    Piece {
+                        atom: Atom::Literal(Literal {
+                            c,
+                            escaped: false,
+                            case_sensitive: false,
+                            span,
+                        }),
+                        quantifier: None,
+                        span,
+                    }
+                }
+            };
+            pieces.push(piece);
+        }
+        let span = self.span(0, self.len);
+        Ok(Expr {
+            branches: vec![Branch { pieces, span }],
+            span,
+        })
+    }
+
+    // The qualifier of a wildcard, which can only be `?`, `*`, `+` or
+    // `{n,m}`.
+    fn wildcard_quantifier(&mut self) -> Result<Option<Quantifier>, Error> {
+        let start = self.idx;
+        let kind = match self.pattern.get(self.idx) {
+            Some('?') => QuantifierKind::ZeroOrOne,
+            Some('*') => QuantifierKind::ZeroOrMore,
+            Some('+') => QuantifierKind::OneOrMore,
+            Some('{') => match self.bracket()? {
+                kind @ QuantifierKind::Between(..) => {
+                    return Ok(Some(Quantifier {
+                        kind,
+                        greedy: true,
+                        span: self.span(start, self.idx),
+                    }));
+                }
+                _ => {
+                    return Err(Error::syntax(
+                        "A wildcard qualifier must have the form {n,m}",
+                    ))
+                }
+            },
+            _ => return Ok(None),
+        };
+        self.idx += 1;
+        Ok(Some(Quantifier {
+            kind,
+            greedy: true,
+            span: self.span(start, self.idx),
+        }))
+    }
+
+    // 'x' flag is set. preprocess the expression to strip whitespace,
+    // other than between square brackets before XPath 4.0
+    fn strip_whitespace(&mut self) {
+        let mut sb = Vec::new();
+        let mut offsets = Vec::new();
+        let mut nesting = 0;
+        let mut escaped = false;
+        for (i, ch) in self.pattern.iter().enumerate() {
+            match ch {
+                '\\' if !escaped => {
+                    escaped = true;
+                    sb.push(*ch);
+                    offsets.push(i);
+                }
+                '[' if !escaped => {
+                    nesting += 1;
+                    sb.push(*ch);
+                    offsets.push(i);
+                }
+                ']' if !escaped => {
+                    nesting -= 1;
+                    sb.push(*ch);
+                    offsets.push(i);
+                }
+                _ => {
+                    // TODO: wrong whitespace
+                    let in_class = nesting > 0 && self.re_flags.language() != Language::XPath40;
+                    if !in_class && ch.is_ascii_whitespace() {
+                        if escaped {
+                            self.warnings.push(Warning {
+                                kind: WarningKind::WhitespaceInEscape,
+                                span: Span::new(i, i + 1),
+                            });
+                        }
+                    } else {
+                        escaped = false;
+                        sb.push(*ch);
+                        offsets.push(i);
+                    }
+                }
+            }
+        }
+        self.pattern = sb;
+        self.offsets = offsets;
+        self.len = self.pattern.len();
+    }
+
+    fn bracket(&mut self) -> Result<QuantifierKind, Error> {
+        // current character must be a '{'
+        if self.pattern.get(self.idx) != Some(&'{') {
+            return Err(Error::internal("Quantifier doesn't start with '{'"));
+        }
+        self.idx += 1;
+
+        // next char must be a digit
+        if self.idx >= self.len || !self.pattern[self.idx].is_ascii_digit() {
+            return Err(Error::syntax("Expected digit"));
+        }
+
+        // get min ('m' of {m,n}) number
+        let min = self.repetition()?;
+
+        // if out of input, fail
+        if self.idx >= self.len {
+            return Err(Error::syntax("Expected comma or right bracket"));
+        }
+
+        // if end of expr, optional limit is 0
+        if self.pattern[self.idx] == '}' {
+            self.idx += 1;
+            return Ok(QuantifierKind::Exactly(min));
+        }
+
+        // must have at least {m,} and maybe {m,n}
+        if self.idx >= self.len || self.pattern[self.idx] != ',' {
+            return Err(Error::syntax("Expected comma"));
+        }
+        self.idx += 1;
+
+        // if out of input, fail
+        if self.idx >= self.len {
+            return Err(Error::syntax("Expected comma or right bracket"));
+        }
+
+        // if {m,} max is unlimited
+        if self.pattern[self.idx] == '}' {
+            self.idx += 1;
+            return Ok(QuantifierKind::AtLeast(min));
+        }
+
+        // next char must be a digit
+        if self.idx >= self.len || !self.pattern[self.idx].is_ascii_digit() {
+            return Err(Error::syntax("Unexpected digit"));
+        }
+
+        // get max number
+        let max = self.repetition()?;
+
+        // optional repetitions must be >= 0
+        if max < min {
+            return Err(Error::syntax("Bad range"));
+        }
+
+        // must have close brace
+        if self.idx >= self.len || self.pattern[self.idx] != '}' {
+            return Err(Error::syntax("Missing closing brace"));
+        }
+        self.idx += 1;
+        Ok(QuantifierKind::Between(min, max))
+    }
+
+    // a number in a quantifier, within the limits
+    fn repetition(&mut self) -> Result<usize, Error> {
+        let number = self.number()?;
+        if number > self.limits.max_repetition {
+            return Err(Error::LimitExceeded(format!(
+                "Repetition bound {} exceeds the limit of {}",
+                number, self.limits.max_repetition
+            )));
+        }
+        Ok(number)
+    }
+
+    fn number(&mut self) -> Result<usize, Error> {
+        let mut number = String::new();
+        while self.idx < self.len && self.pattern[self.idx].is_ascii_digit() {
+            number.push(self.pattern[self.idx]);
+            self.idx += 1;
+        }
+        number
+            .parse::<usize>()
+            .map_err(|_| Error::syntax("Expected valid number"))
+    }
+
+    fn escape(&mut self, in_square_brackets: bool) -> Result<Escape, Error> {
+        let escape_start = self.idx;
+        // "Shouldn't" happen
+        if self.pattern.get(self.idx) != Some(&'\\') {
+            return Err(Error::internal("Escape doesn't start with '\\'"));
+        }
+
+        // escape shouldn't occur as last character in string!
+        if self.idx + 1 >= self.len {
+            return Err(Error::syntax("Escape terminates string"));
+        }
+        if self.re_flags.language() == Language::EcmaScript {
+            return self.ecmascript_escape(in_square_brackets);
+        }
+
+        // switch on character after backslash
+        self.idx += 2;
+        let escape_char = self.pattern[self.idx - 1];
+
+        match escape_char {
+            'n' => Ok(Escape::Char('\n')),
+            'r' => Ok(Escape::Char('\r')),
+            't' => Ok(Escape::Char('\t')),
+            '\\' | '|' | '.' | '-' | '^' | '?' | '*' | '+' | '{' | '}' | '(' | ')' | '[' | ']' => {
+                Ok(Escape::Char(escape_char))
+            }
+            '$' => match self.re_flags.language() {
+                Language::XPath | Language::XPath40 | Language::EcmaScript => Ok(Escape::Char('$')),
+                Language::XSD => Err(Error::syntax("In XSD, '$' must not be escaped")),
+                Language::FullTextWildcards => Err(Error::internal(
+                    "Full-text wildcards have no regular expression escapes",
+                )),
+            },
+            's' => Ok(Escape::Class(ClassEscapeKind::Space, false)),
+            'S' => Ok(Escape::Class(ClassEscapeKind::Space, true)),
+            'i' => Ok(Escape::Class(ClassEscapeKind::NameStartChar, false)),
+            'I' => Ok(Escape::Class(ClassEscapeKind::NameStartChar, true)),
+            'c' => Ok(Escape::Class(ClassEscapeKind::NameChar, false)),
+            'C' => Ok(Escape::Class(ClassEscapeKind::NameChar, true)),
+            'd' => Ok(Escape::Class(ClassEscapeKind::Digit, false)),
+            'D' => Ok(Escape::Class(ClassEscapeKind::Digit, true)),
+            'w' => Ok(Escape::Class(ClassEscapeKind::WordChar, false)),
+            'W' => Ok(Escape::Class(ClassEscapeKind::WordChar, true)),
+            'p' | 'P' => {
+                if self.idx == self.len {
+                    return Err(Error::syntax(format!(
+                        "Expected '{{' after \\{}",
+                        escape_char
+                    )));
+                }
+                if self.pattern[self.idx] != '{' {
+                    return Err(Error::syntax(format!(
+                        "Expected '{{' after \\{}",
+                        escape_char
+                    )));
+                }
+                self.idx += 1;
+                let from = self.idx;
+                let close = self
+                    .pattern
+                    .iter()
+                    .skip(from)
+                    .position(|c| *c == '}')
+                    .ok_or(Error::syntax(format!(
+                        "No closing '}}' after \\{}",
+                        escape_char
+                    )))?;
+                let close = from + close;
+                let block = &self.pattern[self.idx..close];
+                let negated = escape_char == 'P';
+
+                if block.len() == 1 || block.len() == 2 {
+                    self.idx = close + 1;
+                    let name = block.iter().collect::<String>();
+                    category::check_category(&name)?;
+                    Ok(Escape::Class(ClassEscapeKind::Category(name), negated))
+                } else if block.starts_with(&['I', 's']) {
+                    let name = block[2..].iter().collect::<String>();
+                    self.idx = close + 1;
+                    if let Some(current) = category::deprecated_block(&name) {
+                        self.warn(
+                            WarningKind::DeprecatedBlock(name.clone(), current.to_string()),
+                            escape_start,
+                            self.idx,
+                        );
+                    }
+                    match category::block(&name) {
+                        Ok(_) => {}
+                        Err(_) if self.re_flags.is_allow_unknown_block_names() => {
+                            // XSD allows processors to accept unknown block
+                            // names; the escape then matches any character
+                            self.warn(
+                                WarningKind::UnknownBlock(name.clone()),
+                                escape_start,
+                                self.idx,
+                            );
+                        }
+                        Err(err) => return Err(err),
+                    }
+                    Ok(Escape::Class(ClassEscapeKind::Block(name), negated))
+                } else {
+                    Err(Error::syntax(format!(
+                        "Unknown character category: {}",
+                        block.iter().collect::<String>()
+                    )))
+                }
+            }
+            '0' => Err(Error::syntax("Octal escapes are not allowed")),
+            '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
+                if in_square_brackets {
+                    return Err(Error::syntax(
+                        "Backreferences not allowed within character classes",
+                    ));
+                }
+                if !self.re_flags.language().is_xpath() {
+                    return Err(Error::syntax("digit not allowed after \\"));
+                }
+                let mut back_ref = (escape_char as usize) - ('0' as usize);
+                while self.idx < self.len {
+                    let c1 = self.pattern[self.idx].to_digit(10);
+                    if let Some(c1) = c1 {
+                        let back_ref2 = back_ref * 10 + (c1 as usize);
+                        if back_ref2 > (self.capturing_open_paren_count - 1) {
+                            break;
+                        }
+                        back_ref = back_ref2;
+                        self.idx += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if !self.captures.contains(&back_ref) {
+                    let explanation = if back_ref > (self.capturing_open_paren_count - 1) {
+                        "(no such group)"
+                    } else {
+                        "(group not yet closed)"
+                    };
+                    return Err(Error::syntax(format!(
+                        "invalid backreference \\{} {}",
+                        back_ref, explanation
+                    )));
+                }
+                self.has_back_references = true;
+                Ok(Escape::BackReference(back_ref))
+            }
+            escape_char => Err(Error::syntax(format!(
+                "Escape character '{}' not allowed",
+                escape_char
+            ))),
+        }
+    }
+
+    // The escapes of ECMAScript in Unicode mode, which differ from those of
+    // XSD and XPath. The backslash has been checked.
+    fn ecmascript_escape(&mut self, in_square_brackets: bool) -> Result<Escape, Error> {
+        self.idx += 2;
+        let escape_char = self.pattern[self.idx - 1];
+
+        match escape_char {
+            't' => Ok(Escape::Char('\t')),
+            'n' => Ok(Escape::Char('\n')),
+            'v' => Ok(Escape::Char('\u{B}')),
+            'f' => Ok(Escape::Char('\u{C}')),
+            'r' => Ok(Escape::Char('\r')),
+            '^' | '$' | '\\' | '.' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|'
+            | '/' => Ok(Escape::Char(escape_char)),
+            '-' if in_square_brackets => Ok(Escape::Char('-')),
+            // a backspace in square brackets
+            'b' if in_square_brackets => Ok(Escape::Char('\u{8}')),
+            'b' => Ok(Escape::WordBoundary(false)),
+            'B' if !in_square_brackets => Ok(Escape::WordBoundary(true)),
+            's' => Ok(Escape::Class(ClassEscapeKind::Space, false)),
+            'S' => Ok(Escape::Class(ClassEscapeKind::Space, true)),
+            'd' => Ok(Escape::Class(ClassEscapeKind::Digit, false)),
+            'D' => Ok(Escape::Class(ClassEscapeKind::Digit, true)),
+            'w' => Ok(Escape::Class(ClassEscapeKind::WordChar, false)),
+            'W' => Ok(Escape::Class(ClassEscapeKind::WordChar, true)),
+            'c' => match self.pattern.get(self.idx) {
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    self.idx += 1;
+                    Ok(Escape::Char(char::from(*letter as u8 % 32)))
+                }
+                _ => Err(Error::syntax("Expected a letter after \\c")),
+            },
+            '0' if !self.there_follows_digit() => Ok(Escape::Char('\0')),
+            '0' => Err(Error::syntax("Octal escapes are not allowed")),
+            '1'..='9' => {
+                if in_square_brackets {
+                    return Err(Error::syntax(
+                        "Backreferences not allowed within character classes",
+                    ));
+                }
+                let start = self.idx - 1;
+                while self.there_follows_digit() {
+                    self.idx += 1;
+                }
+                let back_ref = self.pattern[start..self.idx]
+                    .iter()
+                    .collect::<String>()
+                    .parse::<usize>()
+                    .map_err(|_| Error::syntax("Expected valid number"))?;
+                // the group may come later in the pattern
+                if back_ref > self.group_count() {
+                    return Err(Error::syntax(format!(
+                        "invalid backreference \\{} (no such group)",
+                        back_ref
+                    )));
+                }
+                self.has_back_references = true;
+                Ok(Escape::BackReference(back_ref))
+            }
+            'x' => {
+                let code_point = self.hex_digits(2)?;
+                self.escaped_char(code_point)
+            }
+            'u' => {
+                let code_point = self.unicode_escape()?;
+                self.escaped_char(code_point)
+            }
+            'p' | 'P' => self.ecmascript_property(escape_char == 'P'),
+            'k' => Err(Error::Unsupported(
+                "Named back-references are not supported".to_string(),
+            )),
+            escape_char => Err(Error::syntax(format!(
+                "Escape character '{}' not allowed",
+                escape_char
+            ))),
+        }
+    }
+
+    // The code point of `\u` followed by four hexadecimal digits, or by any
+    // number of them between braces. Two such escapes that form a surrogate
+    // pair stand for a single code point.
+    fn unicode_escape(&mut self) -> Result<u32, Error> {
+        if self.there_follows("{") {
+            self.idx += 1;
+            let start = self.idx;
+            while self.idx < self.len && self.pattern[self.idx].is_ascii_hexdigit() {
+                self.idx += 1;
+            }
+            let digits = self.pattern[start..self.idx].iter().collect::<String>();
+            if digits.is_empty() || !self.there_follows("}") {
+                return Err(Error::syntax(
+                    "Expected hexadecimal digits and '}' after \\u{",
+                ));
+            }
+            self.idx += 1;
+            return u32::from_str_radix(&digits, 16)
+                .ok()
+                .filter(|code_point| *code_point <= 0x10FFFF)
+                .ok_or_else(|| Error::syntax(format!("Code point {} is too large", digits)));
+        }
+        let high = self.hex_digits(4)?;
+        if (0xD800..0xDC00).contains(&high) && self.there_follows("\\u") {
+            let before = self.idx;
+            self.idx += 2;
+            match self.hex_digits(4) {
+                Ok(low) if (0xDC00..0xE000).contains(&low) => {
+                    return Ok(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00));
+                }
+                _ => self.idx = before,
+            }
+        }
+        Ok(high)
+    }
+
+    fn hex_digits(&mut self, count: usize) -> Result<u32, Error> {
+        let digits = self
+            .pattern
+            .get(self.idx..self.idx + count)
+            .filter(|digits| digits.iter().all(char::is_ascii_hexdigit))
+            .ok_or_else(|| Error::syntax(format!("Expected {} hexadecimal digits", count)))?
+            .iter()
+            .collect::<String>();
+        self.idx += count;
+        u32::from_str_radix(&digits, 16).map_err(|_| Error::syntax("Expected valid number"))
+    }
+
+    fn escaped_char(&self, code_point: u32) -> Result<Escape, Error> {
+        // strings can't contain surrogates, so there is nothing to match
+        char::from_u32(code_point).map(Escape::Char).ok_or_else(|| {
+            Error::Unsupported(format!(
+                "Escapes of lone surrogates such as \\u{:X} are not supported",
+                code_point
+            ))
+        })
+    }
+
+    // `\p{..}` in ECMAScript, which knows general categories by their long
+    // names too, as in `\p{Letter}` or `\p{General_Category=L}`
+    fn ecmascript_property(&mut self, negated: bool) -> Result<Escape, Error> {
+        let escape_char = if negated { 'P' } else { 'p' };
+        if !self.there_follows("{") {
+            return Err(Error::syntax(format!(
+                "Expected '{{' after \\{}",
+                escape_char
+            )));
+        }
+        let from = self.idx + 1;
+        let close = self.pattern[from..]
+            .iter()
+            .position(|c| *c == '}')
+            .ok_or(Error::syntax(format!(
+                "No closing '}}' after \\{}",
+                escape_char
+            )))?;
+        let property = self.pattern[from..from + close].iter().collect::<String>();
+        self.idx = from + close + 1;
+        let value = match property.split_once('=') {
+            Some(("General_Category" | "gc", value)) => value,
+            Some(_) => {
+                return Err(Error::Unsupported(format!(
+                    "Only general categories are supported: {}",
+                    property
+                )))
+            }
+            None => property.as_str(),
+        };
+        match category::category_short_name(value) {
+            Some(name) => Ok(Escape::Class(ClassEscapeKind::Category(name), negated)),
+            None => Err(Error::syntax(format!(
+                "Unknown character category: {}",
+                value
+            ))),
+        }
+    }
+
+    // The number of capturing groups in the whole pattern, for ECMAScript
+    // back-references, which may refer to a group that comes later.
+    fn group_count(&self) -> usize {
+        let mut count = 0;
+        let mut in_square_brackets = false;
+        let mut chars = self.pattern.iter().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '[' => in_square_brackets = true,
+                ']' => in_square_brackets = false,
+                '(' if !in_square_brackets && chars.peek() != Some(&&'?') => count += 1,
+                _ => {}
+            }
+        }
+        count
+    }
+
+    fn parse_character_class(&mut self) -> Result<CharClassExpr, Error> {
+        let class_start = self.idx;
+        // check for bad calling or empty class
+        if self.pattern.get(self.idx) != Some(&'[') {
+            return Err(Error::internal("Character class doesn't start with '['"));
+        }
+
+        // check for unterminated or empty class
+        self.idx += 1;
+        let index = self.idx;
+        if self.idx + 1 >= self.len || self.pattern[index] == ']' {
+            return Err(Error::syntax("Missing ']"));
+        }
+
+        // parse class declaration
+        let mut simple_char;
+        let mut negated = false;
+        let mut defining_range = false;
+        let mut range_start: Option<Literal> = None;
+        let mut range_end;
+
+        let mut items = Vec::new();
+        let mut subtraction = None;
+
+        if self.there_follows("^") {
+            if self.there_follows("^-[") {
+                return Err(Error::syntax("Nothing before subtraction operator"));
+            } else if self.there_follows("^]") {
+                return Err(Error::syntax("Empty negative character group"));
+            } else {
+                negated = true;
+                self.idx += 1;
+            }
+        } else if self.there_follows("-[") {
+            return Err(Error::syntax("Nothing before subtraction operator"));
+        }
+
+        while self.idx < self.len && self.pattern[self.idx] != ']' {
+            let ch = self.pattern[self.idx];
+            let char_start = self.idx;
+            simple_char = None;
+            match ch {
+                '[' => {
+                    return Err(Error::syntax("Unescaped '[' within square brackets"));
+                }
+                '\\' => {
+                    // escape always advances the stream
+                    match self.escape(true)? {
+                        Escape::Char(c) => {
+                            simple_char = Some(Literal {
+                                c,
+                                escaped: true,
+                                case_sensitive: false,
+                                span: self.span(char_start, self.idx),
+                            });
+                        }
+                        Escape::Class(kind, negated) => {
+                            if defining_range {
+                                return Err(Error::syntax(
+                                    "Multi-character escape cannot follow '-'",
+                                ));
+                            }
+                            items.push(ClassItem::Escape(ClassEscape {
+                                kind,
+                                negated,
+                                span: self.span(char_start, self.idx),
+                            }));
+                            continue;
+                        }
+                        // escape() doesn't allow these in square brackets
+                        Escape::BackReference(_) | Escape::WordBoundary(_) => {
+                            return Err(Error::internal("Assertion in character class"))
+                        }
+                    }
+                }
+                '-' => {
+                    if self.there_follows("-[") {
+                        self.idx += 1;
+                        self.enter_nesting()?;
+                        subtraction = Some(Box::new(self.parse_character_class()?));
+                        self.depth -= 1;
+                        if !self.there_follows("]") {
+                            return Err(Error::syntax("Expected closing ']' after subtraction"));
+                        }
+                    } else if self.there_follows("-]") {
+                        self.idx += 1;
+                        simple_char = Some(self.literal(char_start));
+                    } else if range_start.is_some() {
+                        defining_range = true;
+                        self.idx += 1;
+                        continue;
+                    } else if defining_range {
+                        return Err(Error::syntax("Bad range"));
+                    } else if self.there_follows("--") && !self.there_follows("--[") {
+                        return Err(Error::syntax("Unescaped hyphen at start of range"));
+                    } else {
+                        self.idx += 1;
+                        simple_char = Some(self.literal(char_start));
+                    }
+                }
+                _ => {
+                    self.idx += 1;
+                    simple_char = Some(self.literal(char_start));
+                }
+            }
+
+            // handle simple character simpleChar
+            if defining_range {
+                // if we are defining a range make it now
+                range_end = simple_char;
+
+                // actually create a range if the range is ok
+                if let (Some(start), Some(end)) = (range_start.take(), range_end) {
+                    if start.c > end.c {
+                        return Err(Error::syntax(format!(
+                            "Bad character range: start ({:?}) > end ({:?})",
+                            start.c, end.c
+                        )));
+                        // Technically this is not an error in XSD, merely a
+                        // no-op; but it is so utterly pointless that it is
+                        // almost certainly a mistake.
+                    }
+                    items.push(ClassItem::Range(start, end));
+                    // we are done defining the range
+                    defining_range = false;
+                }
+            } else if let Some(simple_char) = simple_char {
+                // if simple character and not start of range, include it (see
+                // XSD 1.1 rules)
+                if self.there_follows("-")
+                    && !self.there_follows("-[")
+                    && !self.there_follows("-]")
+                    && !self.there_follows("--[")
+                {
+                    if self.there_follows("--") {
+                        return Err(Error::syntax("Unescaped hyphen cannot act as end of range"));
+                    }
+                    range_start = Some(simple_char);
+                } else {
+                    items.push(ClassItem::Char(Literal {
+                        case_sensitive: self.there_follows("-"),
+                        ..simple_char
+                    }));
+                }
+            }
+        }
+
+        // shouldn't be out of input
+        if self.idx == self.len {
+            return Err(Error::syntax("Unterminated character class"));
+        }
+
+        // absorb the ']' end of class marker
+        self.idx += 1;
+        Ok(CharClassExpr {
+            negated,
+            items,
+            subtraction,
+            span: self.span(class_start, self.idx),
+        })
+    }
+
+    // A character class in ECMAScript. Unlike in XSD it can be empty, '['
+    // doesn't need to be escaped, and there is no subtraction.
+    fn parse_ecmascript_class(&mut self) -> Result<CharClassExpr, Error> {
+        let class_start = self.idx;
+        if self.pattern.get(self.idx) != Some(&'[') {
+            return Err(Error::internal("Character class doesn't start with '['"));
+        }
+        self.idx += 1;
+        let negated = self.there_follows("^");
+        if negated {
+            self.idx += 1;
+        }
+
+        let mut items = Vec::new();
+        while self.idx < self.len && self.pattern[self.idx] != ']' {
+            let item = self.ecmascript_class_atom()?;
+            // a hyphen before the closing ']' is a character
+            if !self.there_follows("-") || self.there_follows("-]") || self.idx + 1 == self.len {
+                items.push(item);
+                continue;
+            }
+            self.idx += 1;
+            match (item, self.ecmascript_class_atom()?) {
+                (ClassItem::Char(start), ClassItem::Char(end)) => {
+                    if start.c > end.c {
+                        return Err(Error::syntax(format!(
+                            "Bad character range: start ({:?}) > end ({:?})",
+                            start.c, end.c
+                        )));
+                    }
+                    items.push(ClassItem::Range(start, end));
+                }
+                _ => {
+                    return Err(Error::syntax(
+                        "Multi-character escape cannot be part of a range",
+                    ))
+                }
+            }
+        }
+
+        if self.idx == self.len {
+            return Err(Error::syntax("Unterminated character class"));
+        }
+        self.idx += 1;
+        Ok(CharClassExpr {
+            negated,
+            items,
+            subtraction: None,
+            span: self.span(class_start, self.idx),
+        })
+    }
+
+    // a character or class escape in an ECMAScript character class
+    fn ecmascript_class_atom(&mut self) -> Result<ClassItem, Error> {
+        let start = self.idx;
+        if self.pattern[self.idx] != '\\' {
+            self.idx += 1;
+            return Ok(ClassItem::Char(self.literal(start)));
+        }
+        match self.escape(true)? {
+            Escape::Char(c) => Ok(ClassItem::Char(Literal {
+                c,
+                escaped: true,
+                case_sensitive: false,
+                span: self.span(start, self.idx),
+            })),
+            Escape::Class(kind, negated) => Ok(ClassItem::Escape(ClassEscape {
+                kind,
+                negated,
+                span: self.span(start, self.idx),
+            })),
+            // escape() doesn't allow these in square brackets
+            Escape::BackReference(_) | Escape::WordBoundary(_) => {
+                Err(Error::internal("Assertion in character class"))
+            }
+        }
+    }
+
+    // the unescaped character at start, which has just been consumed
+    fn literal(&self, start: usize) -> Literal {
+        Literal {
+            c: self.pattern[start],
+            escaped: false,
+            case_sensitive: false,
+            span: self.span(start, self.idx),
+        }
+    }
+
+    fn parse_terminal(&mut self) -> Result<Atom, Error> {
+        let start = self.idx;
+        match self.pattern[self.idx] {
+            '$' if self.re_flags.language() != Language::XSD => {
+                self.idx += 1;
+                Ok(Atom::End(self.span(start, self.idx)))
+            }
+            '^' if self.re_flags.language() != Language::XSD => {
+                self.idx += 1;
+                Ok(Atom::Start(self.span(start, self.idx)))
+            }
+            '.' => {
+                self.idx += 1;
+                Ok(Atom::Any(self.span(start, self.idx)))
+            }
+            '[' if self.re_flags.language() == Language::EcmaScript => {
+                Ok(Atom::CharClass(self.parse_ecmascript_class()?))
+            }
+            '[' => Ok(Atom::CharClass(self.parse_character_class()?)),
+            '(' if self.re_flags.language() == Language::EcmaScript
+                && (self.there_follows("(?=") || self.there_follows("(?!")) =>
+            {
+                Ok(Atom::Lookahead(self.parse_lookahead()?))
+            }
+            '(' if self.re_flags.language() == Language::EcmaScript
+                && (self.there_follows("(?<=") || self.there_follows("(?<!")) =>
+            {
+                Err(Error::Unsupported(
+                    "Lookbehinds are not supported".to_string(),
+                ))
+            }
+            '(' if self.re_flags.language() == Language::EcmaScript
+                && self.there_follows("(?<") =>
+            {
+                Err(Error::Unsupported(
+                    "Named groups are not supported".to_string(),
+                ))
+            }
+            '(' => Ok(Atom::Group(self.parse_group()?)),
+            ')' => Err(Error::syntax("Unescaped closing ')'")),
+            '|' => Err(Error::internal("Atom starts with '|'")),
+            ']' => Err(Error::syntax("Unexpected closing ']'")),
+            '?' | '+' | '{' | '*' => Err(Error::syntax("No expression before quantifier")),
+            '}' => Err(Error::syntax("Unescaped right curly brace")),
+            '\\' => {
+                // don't forget, escape() advances the input stream!
+                let esc = self.escape(false)?;
+                let span = self.span(start, self.idx);
+                match esc {
+                    Escape::BackReference(group) => {
+                        // ECMAScript allows references to later groups
+                        if self.re_flags.language() != Language::EcmaScript
+                            && self.capturing_open_paren_count <= group
+                        {
+                            return Err(Error::syntax("Bad backreference"));
+                        }
+                        Ok(Atom::BackReference(BackReference { group, span }))
+                    }
+                    Escape::Char(c) => Ok(Atom::Literal(Literal {
+                        c,
+                        escaped: true,
+                        case_sensitive: false,
+                        span,
+                    })),
+                    Escape::Class(kind, negated) => Ok(Atom::ClassEscape(ClassEscape {
+                        kind,
+                        negated,
+                        span,
+                    })),
+                    Escape::WordBoundary(false) => Ok(Atom::WordBoundary(span)),
+                    Escape::WordBoundary(true) => Ok(Atom::NotWordBoundary(span)),
+                }
+            }
+            _ => {
+                self.idx += 1;
+                Ok(Atom::Literal(self.literal(start)))
+            }
+        }
+    }
+
+    fn piece(&mut self) -> Result<Piece, Error> {
+        let piece_start = self.idx;
+
+        // get terminal symbol
+        let atom = self.parse_terminal()?;
+        // ECMAScript doesn't allow assertions to be quantified, so a
+        // quantifier that follows one is taken to have nothing before it
+        let assertion = matches!(
+            atom,
+            Atom::Start(_)
+                | Atom::End(_)
+                | Atom::WordBoundary(_)
+                | Atom::NotWordBoundary(_)
+                | Atom::Lookahead(_)
+        );
+        let quantifier = if assertion && self.re_flags.language() == Language::EcmaScript {
+            None
+        } else {
+            self.quantifier(&atom, piece_start)?
+        };
+        Ok(Piece {
+            atom,
+            quantifier,
+            span: self.span(piece_start, self.idx),
+        })
+    }
+
+    fn quantifier(&mut self, atom: &Atom, piece_start: usize) -> Result<Option<Quantifier>, Error> {
+        if self.idx >= self.len {
+            return Ok(None);
+        }
+        let quantifier_start = self.idx;
+        let kind = match self.pattern[self.idx] {
+            '?' | '*' | '+' => {
+                let kind = match self.pattern[self.idx] {
+                    '?' => QuantifierKind::ZeroOrOne,
+                    '*' => QuantifierKind::ZeroOrMore,
+                    _ => QuantifierKind::OneOrMore,
+                };
+                // eat quantifier character
+                self.idx += 1;
+                kind
+            }
+            '{' => {
+                let kind = self.bracket()?;
+                if matches!(
+                    kind,
+                    QuantifierKind::Exactly(0) | QuantifierKind::Between(_, 0)
+                ) {
+                    self.warn(WarningKind::ZeroRepetitions, piece_start, self.idx);
+                }
+                kind
+            }
+            _ => return Ok(None),
+        };
+        let mut quantifier = Quantifier {
+            kind,
+            greedy: true,
+            span: self.span(quantifier_start, self.idx),
+        };
+
+        // A quantifier that allows zero occurrences of '^' or '$' removes
+        // the instruction altogether, and a '?' that follows isn't taken as
+        // part of the quantifier.
+        if matches!(atom, Atom::Start(_) | Atom::End(_)) && quantifier.min() == 0 {
+            return Ok(Some(quantifier));
+        }
+
+        // if the next character is a '?', make the quantifier non-greedy (reluctant)
+        if self.idx < self.len && self.pattern[self.idx] == '?' {
+            if self.re_flags.language() == Language::XSD {
+                return Err(Error::syntax("Reluctant quantifier not allowed in XSD"));
+            }
+            self.idx += 1;
+            quantifier.greedy = false;
+            quantifier.span = self.span(quantifier_start, self.idx);
+        }
+        Ok(Some(quantifier))
+    }
+
+    fn parse_branch(&mut self) -> Result<Branch, Error> {
+        let start = self.idx;
+        // get each possibly quantified piece
+        let mut pieces = Vec::new();
+        while self.idx < self.len && self.pattern[self.idx] != '|' && self.pattern[self.idx] != ')'
+        {
+            pieces.push(self.piece()?);
+        }
+        Ok(Branch {
+            pieces,
+            span: self.span(start, self.idx),
+        })
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, Error> {
+        let start = self.idx;
+        // process contents of first branch node
+        let mut branches = vec![self.parse_branch()?];
+        // loop through branches
+        while self.idx < self.len && self.pattern[self.idx] == '|' {
+            self.idx += 1;
+            branches.push(self.parse_branch()?);
+        }
+        Ok(Expr {
+            branches,
+            span: self.span(start, self.idx),
+        })
+    }
+
+    fn parse_group(&mut self) -> Result<Group, Error> {
+        let start = self.idx;
+        let close_parens = self.capturing_open_paren_count;
+        // if it's a cluster (rather than a proper subexpression ie with backrefs)
+        let capture = if self.there_follows("(?:") {
+            if self.re_flags.language() == Language::XSD {
+                return Err(Error::syntax("Non-capturing groups only allowed in XPath"));
+            }
+            self.idx += 3;
+            None
+        } else {
+            self.idx += 1;
+            let group = self.capturing_open_paren_count;
+            if group > self.limits.max_capture_groups {
+                return Err(Error::LimitExceeded(format!(
+                    "Number of capturing groups exceeds the limit of {}",
+                    self.limits.max_capture_groups
+                )));
+            }
+            self.capturing_open_paren_count += 1;
+            Some(group)
+        };
+
+        let expr = self.group_content()?;
+        if capture.is_some() {
+            self.captures.insert(close_parens);
+        }
+        Ok(Group {
+            capture,
+            expr,
+            span: self.span(start, self.idx),
+        })
+    }
+
+    // `(?=...)` or `(?!...)`
+    fn parse_lookahead(&mut self) -> Result<Lookahead, Error> {
+        let start = self.idx;
+        let negated = self.there_follows("(?!");
+        self.idx += 3;
+        let expr = self.group_content()?;
+        Ok(Lookahead {
+            negated,
+            expr,
+            span: self.span(start, self.idx),
+        })
+    }
+
+    // Go one level deeper into groups or class subtractions. Whatever the
+    // limits, the depth can't exceed MAX_NESTING_DEPTH, as parsing, compiling
+    // and matching recurse into nested expressions and would overflow the
+    // stack.
+    fn enter_nesting(&mut self) -> Result<(), Error> {
+        let limit = self.limits.max_nesting_depth.min(MAX_NESTING_DEPTH);
+        if self.depth >= limit {
+            return Err(Error::LimitExceeded(format!(
+                "Nesting of groups exceeds the limit of {}",
+                limit
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // the expression in a group and the closing paren
+    fn group_content(&mut self) -> Result<Expr, Error> {
+        self.enter_nesting()?;
+        let expr = self.parse_expr()?;
+        self.depth -= 1;
+
+        if self.idx < self.len && self.pattern[self.idx] == ')' {
+            self.idx += 1;
+        } else {
+            return Err(Error::syntax("Missing close paren"));
+        }
+        Ok(expr)
+    }
+
+    fn there_follows_digit(&self) -> bool {
+        self.pattern
+            .get(self.idx)
+            .is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn there_follows(&self, s: &str) -> bool {
+        let chars = s.chars().collect::<Vec<_>>();
+
+        if (self.idx + chars.len()) > self.len {
+            return false;
+        }
+        for (i, c) in chars.iter().enumerate() {
+            if self.pattern[self.idx + i] != *c {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -1,3 +1,5 @@
+use ahash::{HashMap, HashMapExt};
+
 use crate::{
     character_class::CharacterClass,
     op_repeat::Repeat,
@@ -25,15 +27,18 @@ pub(crate) struct ReProgram {
     pub(crate) preconditions: Vec<RegexPrecondition>,
     pub(crate) minimum_length: usize,
     pub(crate) optimization_flags: u32,
-    pub(crate) max_parens: Option<usize>,
-    pub(crate) backtracking_limit: Option<usize>,
+    // the number of capturing groups, plus one for the whole match
+    pub(crate) max_parens: usize,
+    // for each capturing group, the group it is nested in; 0 if it's not in
+    // any
+    pub(crate) nesting_table: HashMap<usize, usize>,
 }
 
 impl ReProgram {
     pub(crate) fn new(
         pattern: Vec<char>,
         operation: Operation,
-        max_parens: Option<usize>,
+        max_parens: usize,
         flags: ReFlags,
     ) -> Self {
         let minimum_length = operation.get_minimum_match_length();
@@ -43,13 +48,12 @@ impl ReProgram {
         let mut initial_char_class = None;
 
         let precondition_operation = if let Operation::Sequence(op) = operation.clone() {
-            let first = op.operations.first().unwrap();
-            match first {
-                Operation::Bol(_) => {
+            match op.operations.first() {
+                Some(Operation::Bol(_)) => {
                     optimization_flags |= OPT_HASBOL;
                 }
-                Operation::Atom(atom) => prefix = Some(atom.atom.clone()),
-                Operation::CharClass(char_class) => {
+                Some(Operation::Atom(atom)) => prefix = Some(atom.atom.clone()),
+                Some(Operation::CharClass(char_class)) => {
                     initial_char_class = Some(char_class.character_class.clone());
                 }
                 _ => {}
@@ -59,6 +63,9 @@ impl ReProgram {
             None
         };
 
+        let mut nesting_table = HashMap::new();
+        compute_nesting_table(&operation, 0, &mut nesting_table);
+
         let mut r = Self {
             pattern,
             operation,
@@ -68,8 +75,8 @@ impl ReProgram {
             preconditions: Vec::new(),
             optimization_flags,
             max_parens,
+            nesting_table,
             minimum_length,
-            backtracking_limit: None,
         };
         if let Some(precondition_operation) = precondition_operation {
             r.add_precondition(precondition_operation, None, 0);
@@ -116,12 +123,10 @@ impl ReProgram {
                         fp = Some(0);
                     }
                     self.add_precondition(o.clone(), fp, mp);
-                    if let (Some(some_fp), Some(match_length)) = (fp, o.get_match_length()) {
-                        fp = Some(some_fp + match_length);
-                    } else {
-                        fp = None;
-                    }
-                    mp += o.get_minimum_match_length();
+                    fp = fp
+                        .zip(o.get_match_length())
+                        .and_then(|(fp, match_length)| fp.checked_add(match_length));
+                    mp = mp.saturating_add(o.get_minimum_match_length());
                 }
             }
             _ => {}
@@ -179,3 +184,22 @@ impl ReProgram {
         op
     }
 }
+
+// Record the group each capturing group is nested in. Unlike scanning the
+// pattern for parentheses, this follows the groups as the compiler numbered
+// them, whatever the flags.
+fn compute_nesting_table(
+    operation: &Operation,
+    parent: usize,
+    nesting_table: &mut HashMap<usize, usize>,
+) {
+    let parent = if let Operation::Capture(capture) = operation {
+        nesting_table.insert(capture.group_nr, parent);
+        capture.group_nr
+    } else {
+        parent
+    };
+    for operand in operation.operands() {
+        compute_nesting_table(operand, parent, nesting_table);
+    }
+}
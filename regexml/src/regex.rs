@@ -1,34 +1,88 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::ops::Range;
+
+use crate::complexity::{Complexity, ComplexityAnalyzer};
+use crate::diagnosis::Diagnosis;
+use crate::diagnostics::Warning;
+use crate::generate::{GenerateOptions, Generator, RngLike};
+use crate::haystack::{Haystack, Utf16};
+use crate::limits::CompileLimits;
+use crate::nfa::Nfa;
 #[cfg(test)]
 use crate::operation::Operation;
+use crate::operation::OperationControl;
+use crate::program_dump;
 use crate::re_compiler::ReCompiler;
 use crate::re_flags::Language;
 use crate::re_flags::ReFlags;
 use crate::re_matcher::ReMatcher;
-use crate::re_program::ReProgram;
+use crate::re_parser::ReParser;
+use crate::re_program::{ReProgram, OPT_HASBACKREFS};
+use crate::regex_match::{Match, PartialMatch};
+use crate::trace::{DebugOutput, PrintTracer, Tracer};
+use crate::translate::{Dialect, Translation, Translator};
 
-pub use crate::analyze_string::AnalyzeIter;
+pub use crate::analyze_string::{AnalyzeIter, PositionedAnalyzeIter};
 pub use crate::re_compiler::Error;
 
 /// A XML-style regular expression.
 #[derive(Debug)]
 pub struct Regex {
+    // the pattern as it was passed in
+    pattern: String,
     re_program: ReProgram,
     matches_empty_string: bool,
+    // where the ';g' flag logs to; stderr if not set
+    debug_output: Option<DebugOutput>,
 }
 
 impl Regex {
     fn new(re: &str, flags: &str, language: Language) -> Result<Self, Error> {
+        Self::compile_with_diagnostics(re, flags, language).map(|(regex, _warnings)| regex)
+    }
+
+    /// Create a regular expression from a string, following the rules of
+    /// the given language.
+    ///
+    /// Besides the regular expression this returns a list of warnings: things
+    /// in the pattern that are allowed, but are likely to be a mistake.
+    pub fn compile_with_diagnostics(
+        re: &str,
+        flags: &str,
+        language: Language,
+    ) -> Result<(Self, Vec<Warning>), Error> {
+        Self::with_limits(re, flags, language, &CompileLimits::unlimited())
+    }
+
+    /// Create a regular expression from a string, following the rules of
+    /// the given language, within limits on the resources it may use.
+    ///
+    /// Use this for patterns from untrusted sources. A pattern that exceeds
+    /// the limits gives [`Error::LimitExceeded`]. Like
+    /// [`Regex::compile_with_diagnostics`], this also returns the warnings
+    /// for the pattern.
+    pub fn with_limits(
+        re: &str,
+        flags: &str,
+        language: Language,
+        limits: &CompileLimits,
+    ) -> Result<(Self, Vec<Warning>), Error> {
         let re_flags = ReFlags::new(flags, language)?;
         let pattern = re.chars().collect();
-        let re_compiler = ReCompiler::new(pattern, re_flags);
-        let re_program = re_compiler.compile()?;
+        let re_compiler = ReCompiler::new(pattern, re_flags).with_limits(limits);
+        let (re_program, warnings) = re_compiler.compile_with_diagnostics()?;
         // we need to check if the regex matches the empty string
-        let mut matcher = ReMatcher::new(&re_program, "");
-        let matches_empty_string = matcher.is_match();
-        Ok(Self {
-            re_program,
-            matches_empty_string,
-        })
+        let matches_empty_string = ReMatcher::new(&re_program, "").is_match();
+        Ok((
+            Self {
+                pattern: re.to_string(),
+                re_program,
+                matches_empty_string,
+                debug_output: None,
+            },
+            warnings,
+        ))
     }
 
     /// Create a regular expression from a string, using XPath 3.1 rules.
@@ -36,21 +90,250 @@ impl Regex {
         Self::new(re, flags, Language::XPath)
     }
 
+    /// Create a regular expression from a string, using XPath 4.0 rules.
+    ///
+    /// Unlike with XPath 3.1, such a regular expression can be used with
+    /// [`Regex::replace_all`], [`Regex::tokenize`] and [`Regex::analyze`]
+    /// even if it matches the empty string.
+    pub fn xpath40(re: &str, flags: &str) -> Result<Self, Error> {
+        Self::new(re, flags, Language::XPath40)
+    }
+
     /// Create a regular expression from a string, using XML Schema 1.1 rules.
     pub fn xsd(re: &str, flags: &str) -> Result<Self, Error> {
         Self::new(re, flags, Language::XSD)
     }
 
+    /// Create a regular expression from a string, using the ECMAScript rules
+    /// that JSON Schema specifies for the `pattern` keyword.
+    ///
+    /// As in JSON Schema, a pattern isn't implicitly anchored, so use
+    /// [`Regex::is_match`] to check a string against it. Groups inside a
+    /// lookahead take part in the match, but don't keep what they captured.
+    pub fn ecmascript(re: &str, flags: &str) -> Result<Self, Error> {
+        Self::new(re, flags, Language::EcmaScript)
+    }
+
+    /// Create a regular expression from a wildcard pattern of the XQuery and
+    /// XPath Full Text 3.0 "using wildcards" option, as used by
+    /// `contains text`.
+    ///
+    /// The pattern has to match a whole token, so [`Regex::is_match`] only
+    /// succeeds if it matches the whole string. Pass the `i` flag for the
+    /// `case insensitive` match option.
+    pub fn full_text_wildcards(re: &str, flags: &str) -> Result<Self, Error> {
+        Self::new(re, flags, Language::FullTextWildcards)
+    }
+
+    /// The pattern this regular expression was compiled from.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The flags this regular expression was compiled with.
+    ///
+    /// This is a canonical form of the flags that were passed in: each flag
+    /// once, in the order `smixq`, followed by `;` and the extension flags,
+    /// if any.
+    pub fn flags(&self) -> String {
+        self.re_program.flags.to_flag_string()
+    }
+
+    /// The language whose rules this regular expression follows.
+    pub fn language(&self) -> Language {
+        self.re_program.flags.language()
+    }
+
+    /// The number of capturing groups in the pattern. These can be referred
+    /// to as `$1` up to `$N` in a replacement string.
+    pub fn capture_count(&self) -> usize {
+        self.re_program.max_parens.saturating_sub(1)
+    }
+
+    /// The minimum length of a match, in characters.
+    pub fn minimum_length(&self) -> usize {
+        self.re_program.minimum_length
+    }
+
+    /// The length of every match in characters, if all matches have the same
+    /// length.
+    pub fn fixed_length(&self) -> Option<usize> {
+        self.re_program.operation.get_match_length()
+    }
+
+    /// Returns `true` if this regular expression matches the empty string.
+    /// Unless they follow XPath 4.0 rules, such regular expressions can't be
+    /// used with [`Regex::replace_all`], [`Regex::tokenize`] and
+    /// [`Regex::analyze`].
+    pub fn matches_empty_string(&self) -> bool {
+        self.matches_empty_string
+    }
+
+    /// Returns `true` if the pattern contains back-references such as `\1`.
+    pub fn has_back_references(&self) -> bool {
+        self.re_program.optimization_flags & OPT_HASBACKREFS != 0
+    }
+
+    /// Check whether the whole string matches this regular expression, as
+    /// an XSD pattern facet requires, and if not, whether it could still
+    /// match once more text is added to its end. This is useful to validate
+    /// text while it is being typed.
+    ///
+    /// A string is reported as a [`PartialMatch::Prefix`] if the matcher
+    /// matched all of it on some path and then needed more input. This may
+    /// be too generous for patterns that can never be completed, such as
+    /// one with a back-reference to a group that can't match.
+    pub fn partial_match(&self, haystack: &str) -> PartialMatch {
+        // no text that is longer than every match can be completed
+        if let Some(length) = self.fixed_length() {
+            if haystack.chars().count() > length {
+                return PartialMatch::Impossible;
+            }
+        }
+        let matcher = self.matcher(haystack);
+        if matcher.match_at(0, true) {
+            PartialMatch::Full
+        } else if matcher.hit_end() {
+            PartialMatch::Prefix
+        } else {
+            PartialMatch::Impossible
+        }
+    }
+
+    /// Explain why the whole string doesn't match this regular expression,
+    /// as an XSD pattern facet requires. Returns `None` if it matches.
+    ///
+    /// The [`Diagnosis`] gives the furthest position that any attempt to
+    /// match reached, and what would have been accepted there.
+    pub fn diagnose(&self, haystack: &str) -> Option<Diagnosis> {
+        let matcher = self.matcher(haystack).with_expectations();
+        if matcher.match_at(0, true) {
+            return None;
+        }
+        matcher.diagnosis()
+    }
+
+    /// Generate a string that matches this regular expression as a whole,
+    /// for test data or examples. The same options and random numbers give
+    /// the same string.
+    ///
+    /// Returns `None` if no matching string was found within
+    /// [`GenerateOptions::max_attempts`], which happens if the pattern can't
+    /// match anything, or only rarely matches what is generated, as with
+    /// anchors in the middle of a pattern. It also returns `None` if the
+    /// pattern only matches strings longer than
+    /// [`GenerateOptions::max_length`].
+    pub fn generate(&self, rng: &mut impl RngLike, options: &GenerateOptions) -> Option<String> {
+        let mut generator = Generator::new(&self.re_program, rng, options);
+        (0..options.max_attempts)
+            .filter_map(|_| generator.candidate())
+            .find(|candidate| self.matcher(candidate).match_at(0, true))
+    }
+
+    /// Generate a string that doesn't match this regular expression as a
+    /// whole, for negative tests. The string is made from a matching one by
+    /// changing, removing or inserting a character, so that it's close to
+    /// being valid.
+    ///
+    /// Returns `None` if no such string was found within
+    /// [`GenerateOptions::max_attempts`], as for `.*`, which matches
+    /// everything.
+    pub fn generate_non_matching(
+        &self,
+        rng: &mut impl RngLike,
+        options: &GenerateOptions,
+    ) -> Option<String> {
+        let mut generator = Generator::new(&self.re_program, rng, options);
+        (0..options.max_attempts)
+            .map(|_| generator.mutated())
+            .find(|candidate| !self.matcher(candidate).match_at(0, true))
+    }
+
     /// Returns `true` if the argument matches this regular expression.
     pub fn is_match(&self, haystack: &str) -> bool {
         let mut matcher = self.matcher(haystack);
         matcher.is_match()
     }
 
-    // returns an error if this regex is known to match an empty string.
-    // caches the last result so it doesn't have to do the match again.
+    /// Returns `true` if the haystack contains a match that starts at or
+    /// after byte offset `start`.
+    ///
+    /// Unlike matching a slice of the haystack, the text before `start` is
+    /// still seen by `^` and `$`, so `^` doesn't match at `start` unless that
+    /// is the start of the haystack (or, with the `m` flag, of a line).
+    /// There is no match if `start` is not at a character boundary.
+    pub fn is_match_at(&self, haystack: &str, start: usize) -> bool {
+        self.find_at(haystack, start).is_some()
+    }
+
+    /// Find the first match that starts at or after byte offset `start` in
+    /// the haystack. See [`Regex::is_match_at`] for how `start` is treated.
+    pub fn find_at<'h>(&self, haystack: &'h str, start: usize) -> Option<Match<'h>> {
+        let start = char_offset(haystack, start)?;
+        let mut matcher = self.matcher(haystack);
+        if !matcher.matches(start) {
+            return None;
+        }
+        found(haystack, &matcher)
+    }
+
+    /// Find a match that starts exactly at byte offset `start` in the
+    /// haystack. See [`Regex::is_match_at`] for how `start` is treated.
+    pub fn match_prefix<'h>(&self, haystack: &'h str, start: usize) -> Option<Match<'h>> {
+        let start = char_offset(haystack, start)?;
+        let mut matcher = self.matcher(haystack);
+        if !matcher.matches_at(start) {
+            return None;
+        }
+        found(haystack, &matcher)
+    }
+
+    /// Returns `true` if the haystack matches this regular expression.
+    ///
+    /// Unlike [`Regex::is_match`] this doesn't copy the text, so it can be
+    /// used for text that isn't stored as one string, such as a
+    /// [`Chunked`](crate::Chunked) sequence of segments.
+    pub fn is_match_in(&self, haystack: impl Haystack) -> bool {
+        self.matcher_in(&haystack).is_match()
+    }
+
+    /// Find the first match that starts at or after code point position
+    /// `start` in the haystack. The match is returned as a range of code
+    /// point positions; use [`Haystack::byte_offset`] to turn these into
+    /// byte offsets.
+    pub fn find_in(&self, haystack: impl Haystack, start: usize) -> Option<Range<usize>> {
+        if start > haystack.len() {
+            return None;
+        }
+        let mut matcher = self.matcher_in(&haystack);
+        if !matcher.matches(start) {
+            return None;
+        }
+        matcher.match_range().map(|(start, end)| start..end)
+    }
+
+    /// Returns `true` if the UTF-16 text matches this regular expression.
+    /// See [`Utf16`] for how lone surrogates are treated.
+    pub fn is_match_utf16(&self, haystack: &[u16]) -> bool {
+        self.is_match_in(Utf16::new(haystack))
+    }
+
+    /// Find the first match that starts at or after `start` in the UTF-16
+    /// text. `start` and the returned range are offsets in UTF-16 code
+    /// units. There is no match if `start` is in the middle of a surrogate
+    /// pair. See [`Utf16`] for how lone surrogates are treated.
+    pub fn find_utf16(&self, haystack: &[u16], start: usize) -> Option<Range<usize>> {
+        let haystack = Utf16::new(haystack);
+        let start = haystack.position(start)?;
+        let found = self.find_in(&haystack, start)?;
+        Some(haystack.unit_offset(found.start)..haystack.unit_offset(found.end))
+    }
+
+    // returns an error if this regex matches an empty string, unless it
+    // follows XPath 4.0 rules, which allow it. whether it does is found out
+    // once, when the regex is compiled.
     fn check_matches_empty_string(&self) -> Result<(), Error> {
-        if !self.matches_empty_string {
+        if !self.matches_empty_string || self.language() == Language::XPath40 {
             Ok(())
         } else {
             Err(Error::MatchesEmptyString)
@@ -59,6 +342,9 @@ impl Regex {
 
     /// Returns a string with all pieces matching this regular expression replaced
     /// by the replacement.
+    ///
+    /// With XPath 4.0 rules a zero-length match is replaced too, so that
+    /// replacing `x*` with `-` turns `"abc"` into `"-a-b-c-"`.
     pub fn replace_all(&self, haystack: &str, replacement: &str) -> Result<String, Error> {
         self.check_matches_empty_string()?;
 
@@ -69,7 +355,45 @@ impl Regex {
             .map(|chars| chars.into_iter().collect())
     }
 
+    /// Returns a string with all pieces matching this regular expression
+    /// replaced by what the action returns for them, like the action
+    /// function of `fn:replace` in XPath 4.0.
+    ///
+    /// The action is called with the matched string and the strings captured
+    /// by the groups `1` to `N`; a group that didn't take part in the match
+    /// captured the empty string. The result is used as it is, without
+    /// processing `$` and `\`.
+    pub fn replace_with(
+        &self,
+        haystack: &str,
+        mut action: impl FnMut(&str, &[&str]) -> String,
+    ) -> Result<String, Error> {
+        self.check_matches_empty_string()?;
+
+        let mut matcher = self.matcher(haystack);
+        matcher
+            .replace_with(|matcher, result| {
+                let captured = (0..matcher.program.max_parens)
+                    .map(|n| {
+                        matcher
+                            .get_paren(n)
+                            .map(|chars| chars.into_iter().collect::<String>())
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>();
+                let groups = captured[1..].iter().map(String::as_str).collect::<Vec<_>>();
+                result.extend(action(&captured[0], &groups).chars());
+                Ok(())
+            })
+            .map(|chars| chars.into_iter().collect())
+    }
+
     /// Returns an iterator of the input string tokenized by the regular expression.
+    ///
+    /// With XPath 4.0 rules a zero-length match separates tokens too, unless
+    /// it is at the start or end of the input or right after another match,
+    /// so that tokenizing `"abc"` with the empty pattern gives `"a"`, `"b"`
+    /// and `"c"`.
     pub fn tokenize<'a>(&'a self, haystack: &str) -> Result<TokenIter<'a>, Error> {
         // if we input the empty string, we should return no tokens
         if haystack.is_empty() {
@@ -86,8 +410,115 @@ impl Regex {
         })
     }
 
-    pub(crate) fn matcher(&self, search: &str) -> ReMatcher {
-        ReMatcher::new(&self.re_program, search)
+    /// Returns `true` if the argument matches this regular expression,
+    /// reporting each step of the matching process to the tracer.
+    ///
+    /// Use [`PrintTracer`] for a readable log of the matching process.
+    pub fn is_match_traced(&self, haystack: &str, tracer: &mut dyn Tracer) -> bool {
+        let mut matcher =
+            ReMatcher::new(&self.re_program, haystack).with_tracer(Box::new(RefCell::new(tracer)));
+        matcher.is_match()
+    }
+
+    /// Send the log of the `;g` flag to this writer instead of stderr.
+    ///
+    /// This has no effect if the regular expression was compiled without
+    /// the `;g` flag.
+    pub fn with_debug_output(mut self, out: impl Write + Send + 'static) -> Self {
+        self.debug_output = Some(DebugOutput::new(out));
+        self
+    }
+
+    /// Describe the compiled program of this regular expression.
+    ///
+    /// This is a text dump of the optimized operations, one per line, with
+    /// the operations they consist of indented below them. It shows how
+    /// repeats are matched (`GreedyFixed`, `ReluctantFixed`,
+    /// `UnambiguousRepeat` or the general `Repeat`), along with the
+    /// optimizations used to skip positions that can't match: the literal
+    /// prefix, the class of initial characters, the preconditions and the
+    /// minimum length of a match.
+    ///
+    /// The format is meant to be read, and to be compared between patterns;
+    /// it is not meant to be parsed.
+    pub fn program_dump(&self) -> String {
+        program_dump::dump(&self.re_program)
+    }
+
+    /// Look for structures in the pattern that can make matching take
+    /// excessive time, such as nested repeats (`(a+)+`) and repeated
+    /// alternatives that can match the same text (`(a|a)*`).
+    ///
+    /// This is a static analysis of the pattern, meant to reject or sandbox
+    /// patterns from untrusted sources before they are used. It reports the
+    /// spans of what it found, and classifies the worst case as polynomial
+    /// or exponential in the length of the input. Like any such analysis it
+    /// is a heuristic: it can miss problems and report harmless patterns.
+    /// If the pattern can't be analyzed this returns an error rather than a
+    /// low risk.
+    pub fn analyze_complexity(&self) -> Result<Complexity, Error> {
+        let mut parser = ReParser::new(self.pattern.chars().collect(), &self.re_program.flags);
+        let expr = parser.parse()?;
+        Ok(ComplexityAnalyzer::new(&self.re_program.flags).analyze(&expr))
+    }
+
+    /// Translate the pattern to the syntax of another regular expression
+    /// engine, so that the same strings can be validated elsewhere.
+    ///
+    /// The translation matches the same strings. An XSD pattern, like a
+    /// full-text wildcard pattern, is anchored to match the whole string; an
+    /// XPath pattern is left unanchored, as [`Regex::is_match`] looks for a
+    /// match anywhere. Classes such as `\i`,
+    /// `\w` and `.`, blocks such as `\p{IsGreek}` and class subtractions
+    /// are written out as ranges, as other engines don't know them or give
+    /// them another meaning. With the `i` flag, characters are written out
+    /// with their other cases. If the pattern uses a feature the dialect
+    /// doesn't have, such as a back-reference for the Rust `regex` crate, this
+    /// returns [`Error::Unsupported`].
+    pub fn translate(&self, dialect: Dialect) -> Result<Translation, Error> {
+        let mut parser = ReParser::new(self.pattern.chars().collect(), &self.re_program.flags);
+        let expr = parser.parse()?;
+        Translator::new(dialect, self.re_program.flags.clone()).translate(&expr)
+    }
+
+    /// Render the compiled regular expression as an automaton in Graphviz
+    /// DOT format.
+    ///
+    /// Edges are labeled with the characters they accept: a single
+    /// character, or a class such as `[a-z]`, `\d` or `[^\p{Lu}]`, where
+    /// class escapes and categories are shown by name. Dashed edges consume
+    /// no input. Boxes mark the start `(1` and end `1)` of capturing groups,
+    /// and diamonds the anchors `^` and `$`. Large repeats, and repeats of
+    /// large expressions, are drawn as a loop through an octagon with the
+    /// bounds.
+    ///
+    /// The automaton follows the optimized program, so equivalent patterns
+    /// may give different drawings.
+    pub fn to_dot(&self) -> String {
+        Nfa::new(&self.re_program.operation).to_dot()
+    }
+
+    pub(crate) fn re_program(&self) -> &ReProgram {
+        &self.re_program
+    }
+
+    pub(crate) fn matcher(&self, search: &str) -> ReMatcher<'_> {
+        self.traced(ReMatcher::new(&self.re_program, search))
+    }
+
+    fn matcher_in<'a>(&'a self, haystack: &'a dyn Haystack) -> ReMatcher<'a> {
+        self.traced(ReMatcher::new_in(&self.re_program, haystack))
+    }
+
+    // trace the matcher if the 'g' flag is set
+    fn traced<'a>(&self, matcher: ReMatcher<'a>) -> ReMatcher<'a> {
+        if !self.re_program.flags.is_debug() {
+            return matcher;
+        }
+        match &self.debug_output {
+            Some(out) => matcher.with_tracer(Box::new(RefCell::new(PrintTracer::new(out.clone())))),
+            None => matcher.with_tracer(Box::new(RefCell::new(PrintTracer::stderr()))),
+        }
     }
 
     #[cfg(test)]
@@ -100,10 +531,17 @@ impl Regex {
     /// provides access to matched subgroups.
     pub fn analyze<'a>(&'a self, haystack: &str) -> Result<AnalyzeIter<'a>, Error> {
         self.check_matches_empty_string()?;
-        Ok(AnalyzeIter::new(
-            &self.re_program.pattern,
-            self.matcher(haystack),
-        ))
+        Ok(AnalyzeIter::new(self.matcher(haystack)))
+    }
+
+    /// Like [`Regex::analyze`], but each entry, and each string and group
+    /// within a match, comes with its position in the haystack, in
+    /// characters and in bytes.
+    pub fn analyze_positioned<'a>(
+        &'a self,
+        haystack: &str,
+    ) -> Result<PositionedAnalyzeIter<'a>, Error> {
+        self.analyze(haystack).map(PositionedAnalyzeIter::new)
     }
 
     // TODO: continue translating ARegexIterator
@@ -118,18 +556,45 @@ pub struct TokenIter<'a> {
     prev_end: Option<usize>,
 }
 
+impl TokenIter<'_> {
+    // The next match that separates tokens. A zero-length match at the start
+    // or end of the input, or right after the previous separator, doesn't
+    // separate anything, so it is skipped.
+    fn next_separator(&mut self, prev_end: usize) -> Option<(usize, usize)> {
+        let len = self.matcher.search.len();
+        let mut search_start = prev_end;
+        while search_start <= len && self.matcher.matches(search_start) {
+            let (start, end) = self.matcher.match_range()?;
+            if start < end || (start != prev_end && start != len) {
+                return Some((start, end));
+            }
+            search_start = start + 1;
+        }
+        None
+    }
+}
+
 impl Iterator for TokenIter<'_> {
     type Item = String;
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(prev_end) = self.prev_end {
-            if self.matcher.matches(prev_end) {
-                let start = self.matcher.get_paren_start(0).unwrap();
-                let current = self.matcher.search[prev_end..start].iter().collect();
-                self.prev_end = self.matcher.get_paren_end(0);
+            if let Some((start, end)) = self.next_separator(prev_end) {
+                let current = self
+                    .matcher
+                    .search
+                    .slice(prev_end..start)
+                    .into_iter()
+                    .collect();
+                self.prev_end = Some(end);
                 Some(current)
             } else {
-                let current = self.matcher.search[prev_end..].iter().collect();
+                let current = self
+                    .matcher
+                    .search
+                    .slice(prev_end..self.matcher.search.len())
+                    .into_iter()
+                    .collect();
                 self.prev_end = None;
                 Some(current)
             }
@@ -138,3 +603,30 @@ impl Iterator for TokenIter<'_> {
         }
     }
 }
+
+// the character offset of a byte offset, if it is at a character boundary
+fn char_offset(haystack: &str, byte_offset: usize) -> Option<usize> {
+    haystack
+        .is_char_boundary(byte_offset)
+        .then(|| haystack[..byte_offset].chars().count())
+}
+
+// the byte offset of a character offset
+fn byte_offset(haystack: &str, char_offset: usize) -> usize {
+    haystack
+        .char_indices()
+        .nth(char_offset)
+        .map(|(offset, _)| offset)
+        .unwrap_or(haystack.len())
+}
+
+// the match the matcher found last
+fn found<'h>(haystack: &'h str, matcher: &ReMatcher) -> Option<Match<'h>> {
+    let (start, end) = matcher.match_range()?;
+    Some(Match::new(
+        haystack,
+        byte_offset(haystack, start),
+        byte_offset(haystack, end),
+        matcher.hit_end(),
+    ))
+}
@@ -0,0 +1,75 @@
+use std::ops::Range;
+
+/// A match of a regular expression in a haystack, as found by
+/// [`Regex::find_at`](crate::Regex::find_at) and
+/// [`Regex::match_prefix`](crate::Regex::match_prefix).
+///
+/// Offsets are in bytes, so they can be used to slice the haystack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'h> {
+    haystack: &'h str,
+    start: usize,
+    end: usize,
+    hit_end: bool,
+}
+
+impl<'h> Match<'h> {
+    pub(crate) fn new(haystack: &'h str, start: usize, end: usize, hit_end: bool) -> Self {
+        Self {
+            haystack,
+            start,
+            end,
+            hit_end,
+        }
+    }
+
+    /// The byte offset of the start of the match.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The byte offset just past the end of the match.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The byte range of the match.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns `true` if the match is empty.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The length of the match in bytes.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// The matched text.
+    pub fn as_str(&self) -> &'h str {
+        &self.haystack[self.range()]
+    }
+
+    /// Returns `true` if the matcher tried to read past the end of the
+    /// haystack while finding this match. If so, more input could have given
+    /// a different match: `a+` finds `"aa"` in `"aa"` with this set, as
+    /// `"aaa"` would give a longer match, while `a+?` finds `"a"` without it.
+    pub fn hit_end(&self) -> bool {
+        self.hit_end
+    }
+}
+
+/// How a string matches a regular expression as a whole, as returned by
+/// [`Regex::partial_match`](crate::Regex::partial_match).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialMatch {
+    /// The whole string matches.
+    Full,
+    /// The string doesn't match, but it is the start of a string that may.
+    Prefix,
+    /// No string that starts with this string matches.
+    Impossible,
+}
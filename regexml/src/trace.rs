@@ -0,0 +1,379 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::operation::{Operation, OperationControl};
+use crate::re_matcher::ReMatcher;
+
+/// Receives events while a regular expression is matched.
+///
+/// Pass a tracer to [`Regex::is_match_traced`](crate::Regex::is_match_traced)
+/// to follow how the matcher steps through the compiled expression. All
+/// methods do nothing by default, so a tracer only needs to implement the
+/// events it is interested in. Positions are character offsets into the
+/// input.
+///
+/// Compiling a pattern with the `;g` flag traces all matching with a
+/// [`PrintTracer`] that writes to stderr, or to the writer given to
+/// [`Regex::with_debug_output`](crate::Regex::with_debug_output).
+pub trait Tracer {
+    /// A match of the whole expression is tried at this position.
+    fn attempt(&mut self, _position: usize) {}
+
+    /// The operation starts matching at this position.
+    fn enter(&mut self, _operation: &TracedOperation, _position: usize) {}
+
+    /// The operation matched the input from `start` to `end`.
+    fn success(&mut self, _operation: &TracedOperation, _start: usize, _end: usize) {}
+
+    /// A later part of the expression failed, so the operation that started
+    /// at `start` is asked for another way to match.
+    fn backtrack(&mut self, _operation: &TracedOperation, _start: usize) {}
+
+    /// The operation that started at `start` has no (more) ways to match.
+    fn fail(&mut self, _operation: &TracedOperation, _start: usize) {}
+}
+
+impl<T: Tracer + ?Sized> Tracer for &mut T {
+    fn attempt(&mut self, position: usize) {
+        (**self).attempt(position)
+    }
+
+    fn enter(&mut self, operation: &TracedOperation, position: usize) {
+        (**self).enter(operation, position)
+    }
+
+    fn success(&mut self, operation: &TracedOperation, start: usize, end: usize) {
+        (**self).success(operation, start, end)
+    }
+
+    fn backtrack(&mut self, operation: &TracedOperation, start: usize) {
+        (**self).backtrack(operation, start)
+    }
+
+    fn fail(&mut self, operation: &TracedOperation, start: usize) {
+        (**self).fail(operation, start)
+    }
+}
+
+/// The kinds of operation a compiled regular expression is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OperationKind {
+    /// A literal string
+    Atom,
+    /// A back-reference to a captured group
+    BackReference,
+    /// The start of the input or of a line (`^`)
+    Bol,
+    /// A capturing group
+    Capture,
+    /// A set of characters, such as `[a-z]`, `\d` or `.`
+    CharClass,
+    /// A choice between alternatives (`|`)
+    Choice,
+    /// The end of the expression
+    EndProgram,
+    /// The end of the input or of a line (`$`)
+    Eol,
+    /// An empty expression
+    Nothing,
+    /// A quantified operation
+    Repeat,
+    /// Operations that match one after the other
+    Sequence,
+    /// A word boundary (`\b`) or a position that is not one (`\B`)
+    WordBoundary,
+    /// A lookahead (`(?=...)` or `(?!...)`)
+    Lookahead,
+}
+
+/// An operation that is being matched, as reported to a [`Tracer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedOperation {
+    /// The kind of operation
+    pub kind: OperationKind,
+    /// What the operation matches, such as `"abc"` for an atom or `{0,}`
+    /// for a repeat. This is empty if the kind says it all.
+    pub description: String,
+    /// How deeply the operation is nested; the top-level operation is at
+    /// depth 0
+    pub depth: usize,
+}
+
+impl TracedOperation {
+    fn new(operation: &Operation, depth: usize) -> Self {
+        let (kind, description) = match operation {
+            Operation::Atom(atom) => (
+                OperationKind::Atom,
+                format!("{:?}", atom.atom.iter().collect::<String>()),
+            ),
+            Operation::BackReference(back_reference) => (
+                OperationKind::BackReference,
+                format!("\\{}", back_reference.group_nr),
+            ),
+            Operation::Bol(_) => (OperationKind::Bol, String::new()),
+            Operation::Capture(capture) => (
+                OperationKind::Capture,
+                format!("group {}", capture.group_nr),
+            ),
+            Operation::CharClass(char_class) => (
+                OperationKind::CharClass,
+                char_class.character_class.summary(),
+            ),
+            Operation::Choice(choice) => (
+                OperationKind::Choice,
+                format!("{} branches", choice.branches.len()),
+            ),
+            Operation::EndProgram(_) => (OperationKind::EndProgram, String::new()),
+            Operation::Eol(_) => (OperationKind::Eol, String::new()),
+            Operation::Nothing(_) => (OperationKind::Nothing, String::new()),
+            Operation::Sequence(sequence) => (
+                OperationKind::Sequence,
+                format!("{} operations", sequence.operations.len()),
+            ),
+            Operation::WordBoundary(word_boundary) => (
+                OperationKind::WordBoundary,
+                if word_boundary.negated { "\\B" } else { "\\b" }.to_string(),
+            ),
+            Operation::Lookahead(lookahead) => (
+                OperationKind::Lookahead,
+                if lookahead.negated { "?!" } else { "?=" }.to_string(),
+            ),
+            Operation::Repeat(_)
+            | Operation::GreedyFixed(_)
+            | Operation::ReluctantFixed(_)
+            | Operation::UnambiguousRepeat(_) => {
+                let repeat = operation.repeat_operation().unwrap();
+                (OperationKind::Repeat, repeat.bounds())
+            }
+        };
+        Self {
+            kind,
+            description,
+            depth,
+        }
+    }
+}
+
+impl fmt::Display for TracedOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.kind)?;
+        if !self.description.is_empty() {
+            write!(f, " {}", self.description)?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`Tracer`] that writes an indented log of the matching process.
+///
+/// Each operation is indented below the operation it is part of. Matching
+/// `ab` against `"ab"` logs:
+///
+/// ```text
+/// attempt at 0
+///   Sequence 2 operations at 0
+///     Atom "ab" at 0
+///     Atom "ab" matched 0..2
+///     EndProgram at 2
+///     EndProgram matched 2..2
+///   Sequence 2 operations matched 0..2
+/// ```
+#[derive(Debug)]
+pub struct PrintTracer<W: Write> {
+    out: W,
+}
+
+impl PrintTracer<io::Stderr> {
+    /// A tracer that writes to stderr.
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W: Write> PrintTracer<W> {
+    /// A tracer that writes to the given writer.
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Get back the writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    // the log is a debugging aid, so a failure to write it is ignored
+    fn line(&mut self, depth: usize, line: fmt::Arguments<'_>) {
+        let _ = writeln!(self.out, "{:indent$}{}", "", line, indent = depth * 2);
+    }
+}
+
+impl<W: Write> Tracer for PrintTracer<W> {
+    fn attempt(&mut self, position: usize) {
+        self.line(0, format_args!("attempt at {}", position));
+    }
+
+    fn enter(&mut self, operation: &TracedOperation, position: usize) {
+        self.line(
+            operation.depth + 1,
+            format_args!("{} at {}", operation, position),
+        );
+    }
+
+    fn success(&mut self, operation: &TracedOperation, start: usize, end: usize) {
+        self.line(
+            operation.depth + 1,
+            format_args!("{} matched {}..{}", operation, start, end),
+        );
+    }
+
+    fn backtrack(&mut self, operation: &TracedOperation, start: usize) {
+        self.line(
+            operation.depth + 1,
+            format_args!("{} backtracking at {}", operation, start),
+        );
+    }
+
+    fn fail(&mut self, operation: &TracedOperation, start: usize) {
+        self.line(
+            operation.depth + 1,
+            format_args!("{} failed at {}", operation, start),
+        );
+    }
+}
+
+// The writer the ';g' flag logs to. It is shared by all the matchers of a
+// regex, which can run on different threads.
+#[derive(Clone)]
+pub(crate) struct DebugOutput(Arc<Mutex<dyn Write + Send>>);
+
+impl DebugOutput {
+    pub(crate) fn new(out: impl Write + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(out)))
+    }
+}
+
+impl Write for DebugOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // a panic while logging leaves nothing inconsistent in the writer
+        let mut out = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut out = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        out.flush()
+    }
+}
+
+impl fmt::Debug for DebugOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DebugOutput")
+    }
+}
+
+// The matcher holds its tracer behind a shared reference, so that the
+// matcher stays covariant in its lifetime; this gives the tracer the
+// mutability it needs.
+pub(crate) trait TraceSink {
+    fn attempt(&self, position: usize);
+    fn enter(&self, operation: &TracedOperation, position: usize);
+    fn success(&self, operation: &TracedOperation, start: usize, end: usize);
+    fn backtrack(&self, operation: &TracedOperation, start: usize);
+    fn fail(&self, operation: &TracedOperation, start: usize);
+}
+
+impl<T: Tracer> TraceSink for RefCell<T> {
+    fn attempt(&self, position: usize) {
+        self.borrow_mut().attempt(position)
+    }
+
+    fn enter(&self, operation: &TracedOperation, position: usize) {
+        self.borrow_mut().enter(operation, position)
+    }
+
+    fn success(&self, operation: &TracedOperation, start: usize, end: usize) {
+        self.borrow_mut().success(operation, start, end)
+    }
+
+    fn backtrack(&self, operation: &TracedOperation, start: usize) {
+        self.borrow_mut().backtrack(operation, start)
+    }
+
+    fn fail(&self, operation: &TracedOperation, start: usize) {
+        self.borrow_mut().fail(operation, start)
+    }
+}
+
+impl fmt::Debug for dyn TraceSink + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TraceSink")
+    }
+}
+
+// Wraps the matches of an operation to report them to the tracer. The
+// operations an operation is made of are created and advanced while it is
+// advanced, so the nesting depth is raised for the duration of that.
+pub(crate) struct TraceIter<'a> {
+    matcher: &'a ReMatcher<'a>,
+    sink: &'a dyn TraceSink,
+    operation: TracedOperation,
+    position: usize,
+    base: Box<dyn Iterator<Item = usize> + 'a>,
+    matched: bool,
+    done: bool,
+}
+
+impl<'a> TraceIter<'a> {
+    pub(crate) fn new(
+        operation: &'a Operation,
+        matcher: &'a ReMatcher<'a>,
+        sink: &'a dyn TraceSink,
+        position: usize,
+    ) -> Self {
+        let depth = matcher.trace_depth();
+        let traced = TracedOperation::new(operation, depth);
+        sink.enter(&traced, position);
+        matcher.set_trace_depth(depth + 1);
+        let base = operation.matches_iter(matcher, position);
+        matcher.set_trace_depth(depth);
+        Self {
+            matcher,
+            sink,
+            operation: traced,
+            position,
+            base,
+            matched: false,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for TraceIter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.done {
+            return None;
+        }
+        if self.matched {
+            self.sink.backtrack(&self.operation, self.position);
+        }
+        self.matcher.set_trace_depth(self.operation.depth + 1);
+        let next = self.base.next();
+        self.matcher.set_trace_depth(self.operation.depth);
+        match next {
+            Some(end) => {
+                self.matched = true;
+                self.sink.success(&self.operation, self.position, end);
+            }
+            None => {
+                self.done = true;
+                self.sink.fail(&self.operation, self.position);
+            }
+        }
+        next
+    }
+}
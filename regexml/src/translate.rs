@@ -0,0 +1,391 @@
+use icu_casemap::CaseMapCloser;
+use icu_collections::codepointinvlist::{CodePointInversionList, CodePointInversionListBuilder};
+
+use crate::{
+    ast::{Atom, Branch, ClassEscape, ClassEscapeKind, Expr, Literal, Piece, QuantifierKind},
+    character_class::CharacterClassBuilder,
+    re_compiler::{Error, ReCompiler},
+    re_flags::{Language, ReFlags},
+};
+
+const SURROGATES: std::ops::RangeInclusive<u32> = 0xD800..=0xDFFF;
+const MAX_CODE_POINT: u32 = 0x10FFFF;
+
+/// A regular expression syntax that a pattern can be translated to with
+/// [`Regex::translate`](crate::Regex::translate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// JavaScript regular expressions, as used by JSON Schema. The pattern
+    /// has to be used with the flags of the [`Translation`], which always
+    /// include `u`.
+    EcmaScript,
+    /// Perl-compatible regular expressions, in UTF mode.
+    Pcre,
+    /// The syntax of the Rust `regex` crate.
+    Rust,
+}
+
+/// A pattern translated to another dialect, as returned by
+/// [`Regex::translate`](crate::Regex::translate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    /// The translated pattern
+    pub pattern: String,
+    /// The flags to use with the pattern. This is only used for
+    /// [`Dialect::EcmaScript`]; the other dialects have the flags inline in
+    /// the pattern, so this is empty for them.
+    pub flags: String,
+}
+
+// Translates a syntax tree to another dialect. Every part of the pattern
+// that stands for a set of characters is written out as that set, except
+// for Unicode categories, which all dialects know by the same names, so
+// that differences in what classes such as `\w` or `.` mean don't matter.
+pub(crate) struct Translator {
+    dialect: Dialect,
+    re_flags: ReFlags,
+    compiler: ReCompiler,
+    case_map_closer: Option<CaseMapCloser<icu_casemap::CaseMapper>>,
+    out: String,
+}
+
+impl Translator {
+    pub(crate) fn new(dialect: Dialect, re_flags: ReFlags) -> Self {
+        Self {
+            dialect,
+            case_map_closer: re_flags.is_case_independent().then(CaseMapCloser::new),
+            compiler: ReCompiler::new(Vec::new(), re_flags.clone()),
+            re_flags,
+            out: String::new(),
+        }
+    }
+
+    pub(crate) fn translate(mut self, expr: &Expr) -> Result<Translation, Error> {
+        let multi_line = self.re_flags.is_multi_line();
+        if multi_line && !self.native_multi_line() && self.dialect == Dialect::Rust {
+            return Err(Error::Unsupported(
+                "Multi-line mode with ECMAScript line terminators is not supported by the Rust regex crate"
+                    .to_string(),
+            ));
+        }
+        let native_multi_line = multi_line && self.native_multi_line();
+        if native_multi_line && self.dialect != Dialect::EcmaScript {
+            self.out.push_str("(?m)");
+        }
+        // an XSD pattern, or a full-text wildcard pattern, has to match the
+        // whole string
+        let anchored = matches!(
+            self.re_flags.language(),
+            Language::XSD | Language::FullTextWildcards
+        );
+        if anchored {
+            self.out.push_str(match self.dialect {
+                Dialect::EcmaScript => "^(?:",
+                Dialect::Pcre | Dialect::Rust => "\\A(?:",
+            });
+        }
+        self.expr(expr)?;
+        if anchored {
+            self.out.push_str(match self.dialect {
+                Dialect::EcmaScript => ")$",
+                Dialect::Pcre | Dialect::Rust => ")\\z",
+            });
+        }
+        let flags = match self.dialect {
+            Dialect::EcmaScript if native_multi_line => "um",
+            Dialect::EcmaScript => "u",
+            Dialect::Pcre | Dialect::Rust => "",
+        };
+        Ok(Translation {
+            pattern: self.out,
+            flags: flags.to_string(),
+        })
+    }
+
+    // In multi-line mode, XPath only breaks lines at a newline, while
+    // ECMAScript also breaks them at a carriage return, U+2028 and U+2029.
+    // The multi-line mode of the dialect can only be used if it agrees
+    // with the pattern; otherwise the anchors are written as lookarounds.
+    fn native_multi_line(&self) -> bool {
+        (self.re_flags.language() == Language::EcmaScript) == (self.dialect == Dialect::EcmaScript)
+    }
+
+    // the characters that end a line in multi-line mode
+    fn line_terminators(&self) -> &'static [u32] {
+        if self.re_flags.language() == Language::EcmaScript {
+            &[0x0A, 0x0D, 0x2028, 0x2029]
+        } else {
+            &[0x0A]
+        }
+    }
+
+    // a line anchor written as a lookaround: not preceded (or followed) by a
+    // character that is not a line terminator
+    fn line_anchor(&mut self, lookaround: &str) {
+        self.out.push_str(lookaround);
+        self.out.push_str("[^");
+        for code_point in self.line_terminators() {
+            self.escaped(*code_point);
+        }
+        self.out.push_str("])");
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        for (i, branch) in expr.branches.iter().enumerate() {
+            if i > 0 {
+                self.out.push('|');
+            }
+            self.branch(branch)?;
+        }
+        Ok(())
+    }
+
+    fn branch(&mut self, branch: &Branch) -> Result<(), Error> {
+        for (i, piece) in branch.pieces.iter().enumerate() {
+            // shield a back-reference from a digit that follows
+            let followed_by_digit = matches!(
+                branch.pieces.get(i + 1).map(|next| &next.atom),
+                Some(Atom::Literal(Literal { c: '0'..='9', .. }))
+            );
+            // an assertion can't be quantified in every dialect, a group can
+            let quantified_assertion = piece.quantifier.is_some()
+                && matches!(
+                    piece.atom,
+                    Atom::Start(_)
+                        | Atom::End(_)
+                        | Atom::WordBoundary(_)
+                        | Atom::NotWordBoundary(_)
+                        | Atom::Lookahead(_)
+                );
+            if (followed_by_digit && matches!(piece.atom, Atom::BackReference(_)))
+                || quantified_assertion
+            {
+                self.out.push_str("(?:");
+                self.atom(&piece.atom)?;
+                self.out.push(')');
+            } else {
+                self.atom(&piece.atom)?;
+            }
+            self.quantifier(piece);
+        }
+        Ok(())
+    }
+
+    fn quantifier(&mut self, piece: &Piece) {
+        let Some(quantifier) = &piece.quantifier else {
+            return;
+        };
+        match quantifier.kind {
+            QuantifierKind::ZeroOrOne => self.out.push('?'),
+            QuantifierKind::ZeroOrMore => self.out.push('*'),
+            QuantifierKind::OneOrMore => self.out.push('+'),
+            QuantifierKind::Exactly(n) => self.out.push_str(&format!("{{{}}}", n)),
+            QuantifierKind::AtLeast(n) => self.out.push_str(&format!("{{{},}}", n)),
+            QuantifierKind::Between(min, max) => self.out.push_str(&format!("{{{},{}}}", min, max)),
+        }
+        if !quantifier.greedy {
+            self.out.push('?');
+        }
+    }
+
+    fn atom(&mut self, atom: &Atom) -> Result<(), Error> {
+        match atom {
+            Atom::Literal(literal) => match &self.case_map_closer {
+                Some(closer) => {
+                    let mut builder = CodePointInversionListBuilder::new();
+                    builder.add_char(literal.c);
+                    closer.add_case_closure_to(literal.c, &mut builder);
+                    self.set(&builder.build());
+                }
+                None => self.char(literal.c),
+            },
+            Atom::Any(_) => {
+                let any = self.compiler.any_char();
+                self.set(any.as_code_point_inversion_list());
+            }
+            Atom::Start(_) if self.re_flags.is_multi_line() && !self.native_multi_line() => {
+                self.line_anchor("(?<!")
+            }
+            Atom::End(_) if self.re_flags.is_multi_line() && !self.native_multi_line() => {
+                self.line_anchor("(?!")
+            }
+            Atom::Start(_) => self.out.push('^'),
+            Atom::End(_) => match self.dialect {
+                // '$' also matches before a newline at the end in PCRE
+                Dialect::Pcre if !self.re_flags.is_multi_line() => self.out.push_str("\\z"),
+                _ => self.out.push('$'),
+            },
+            Atom::ClassEscape(escape) => self.class_escape(escape),
+            Atom::CharClass(class) => {
+                let class = self.compiler.character_class(class).build();
+                self.set(class.as_code_point_inversion_list());
+            }
+            Atom::Group(group) => {
+                self.out
+                    .push_str(if group.capture.is_some() { "(" } else { "(?:" });
+                self.expr(&group.expr)?;
+                self.out.push(')');
+            }
+            Atom::BackReference(back_reference) => {
+                if self.dialect == Dialect::Rust {
+                    return Err(Error::Unsupported(
+                        "Back-references are not supported by the Rust regex crate".to_string(),
+                    ));
+                }
+                self.out.push_str(&format!("\\{}", back_reference.group));
+            }
+            Atom::WordBoundary(_) | Atom::NotWordBoundary(_) | Atom::Lookahead(_)
+                if self.dialect == Dialect::Rust =>
+            {
+                return Err(Error::Unsupported(
+                    "Word boundaries and lookaheads are not supported by the Rust regex crate"
+                        .to_string(),
+                ));
+            }
+            // ECMAScript word characters are ASCII, and so are PCRE ones
+            // unless UCP is enabled
+            Atom::WordBoundary(_) => self.out.push_str("\\b"),
+            Atom::NotWordBoundary(_) => self.out.push_str("\\B"),
+            Atom::Lookahead(lookahead) => {
+                self.out
+                    .push_str(if lookahead.negated { "(?!" } else { "(?=" });
+                self.expr(&lookahead.expr)?;
+                self.out.push(')');
+            }
+        }
+        Ok(())
+    }
+
+    fn class_escape(&mut self, escape: &ClassEscape) {
+        // all dialects know the Unicode categories
+        let folded =
+            self.re_flags.language() == Language::EcmaScript && self.re_flags.is_case_independent();
+        let category = match &escape.kind {
+            // in ECMAScript, the 'i' flag adds the case variants of the
+            // characters in a category
+            ClassEscapeKind::Category(_) if folded => None,
+            ClassEscapeKind::Category(name) => Some(name.as_str()),
+            ClassEscapeKind::Digit if self.re_flags.language() != Language::EcmaScript => {
+                Some("Nd")
+            }
+            _ => None,
+        };
+        match category {
+            Some(name) => {
+                let p = if escape.negated { 'P' } else { 'p' };
+                self.out.push_str(&format!("\\{}{{{}}}", p, name));
+            }
+            None => {
+                let class: CharacterClassBuilder = self.compiler.class_escape(escape);
+                self.set(class.build().as_code_point_inversion_list());
+            }
+        }
+    }
+
+    // A set of characters, as a single character or a class of ranges.
+    fn set(&mut self, set: &CodePointInversionList) {
+        let ranges = without_surrogates(set);
+        if let [(start, end)] = ranges[..] {
+            if start == end {
+                if let Some(c) = char::from_u32(start) {
+                    self.char(c);
+                    return;
+                }
+            }
+        }
+        let complement = complement(&ranges);
+        self.out.push('[');
+        // `[]` and `[^]` aren't allowed everywhere
+        if !complement.is_empty() && (complement.len() < ranges.len() || ranges.is_empty()) {
+            self.out.push('^');
+            self.ranges(&complement);
+        } else {
+            self.ranges(&ranges);
+        }
+        self.out.push(']');
+    }
+
+    fn ranges(&mut self, ranges: &[(u32, u32)]) {
+        for (start, end) in ranges {
+            self.class_code_point(*start);
+            if end > start {
+                if end - start > 1 {
+                    self.out.push('-');
+                }
+                self.class_code_point(*end);
+            }
+        }
+    }
+
+    fn char(&mut self, c: char) {
+        match c {
+            '\\' | '|' | '.' | '?' | '*' | '+' | '{' | '}' | '(' | ')' | '[' | ']' | '^' | '$' => {
+                self.out.push('\\');
+                self.out.push(c);
+            }
+            '/' if self.dialect == Dialect::EcmaScript => self.out.push_str("\\/"),
+            c if c.is_ascii_graphic() || c == ' ' => self.out.push(c),
+            c => self.escaped(c as u32),
+        }
+    }
+
+    fn class_code_point(&mut self, code_point: u32) {
+        match char::from_u32(code_point) {
+            Some(c @ ('\\' | ']' | '[' | '^' | '-')) => {
+                self.out.push('\\');
+                self.out.push(c);
+            }
+            // these combine into set operators in the Rust regex crate
+            Some(c @ ('&' | '~')) if self.dialect == Dialect::Rust => {
+                self.out.push('\\');
+                self.out.push(c);
+            }
+            Some('/') if self.dialect == Dialect::EcmaScript => self.out.push_str("\\/"),
+            Some(c) if c.is_ascii_graphic() || c == ' ' => self.out.push(c),
+            _ => self.escaped(code_point),
+        }
+    }
+
+    fn escaped(&mut self, code_point: u32) {
+        match self.dialect {
+            Dialect::EcmaScript => self.out.push_str(&format!("\\u{{{:X}}}", code_point)),
+            Dialect::Pcre | Dialect::Rust => self.out.push_str(&format!("\\x{{{:X}}}", code_point)),
+        }
+    }
+}
+
+// The ranges of a set, leaving out surrogates, which aren't characters and
+// can't be written in every dialect.
+fn without_surrogates(set: &CodePointInversionList) -> Vec<(u32, u32)> {
+    let mut ranges = Vec::new();
+    for range in set.iter_ranges() {
+        let (start, end) = (*range.start(), *range.end());
+        if start < *SURROGATES.start() {
+            ranges.push((start, end.min(*SURROGATES.start() - 1)));
+        }
+        if end > *SURROGATES.end() {
+            ranges.push((start.max(*SURROGATES.end() + 1), end));
+        }
+    }
+    ranges
+}
+
+// The ranges of the characters that are not in the given ranges, leaving
+// out surrogates.
+fn complement(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut excluded = ranges.to_vec();
+    excluded.push(SURROGATES.into_inner());
+    excluded.sort_unstable();
+    let mut complement = Vec::new();
+    let mut next = 0;
+    for (start, end) in excluded {
+        if start > next {
+            complement.push((next, start - 1));
+        }
+        next = next.max(end + 1);
+    }
+    if next <= MAX_CODE_POINT {
+        complement.push((next, MAX_CODE_POINT));
+    }
+    complement
+}
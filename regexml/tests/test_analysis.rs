@@ -0,0 +1,100 @@
+use regexml::analysis::{equivalent, intersects, is_empty_language, is_subset};
+use regexml::{Error, PartialMatch, Regex};
+
+fn xsd(pattern: &str) -> Regex {
+    Regex::xsd(pattern, "").unwrap()
+}
+
+#[test]
+fn test_is_subset() {
+    let verdict = is_subset(&xsd("[a-c]+"), &xsd("[a-z]*")).unwrap();
+    assert!(verdict.holds);
+    assert_eq!(verdict.example, None);
+
+    let verdict = is_subset(&xsd("[a-z]*"), &xsd("[a-c]+")).unwrap();
+    assert!(!verdict.holds);
+    assert_eq!(verdict.example.as_deref(), Some(""));
+
+    let verdict = is_subset(&xsd(r"\p{L}{2}"), &xsd(r"\p{Lu}\p{Ll}")).unwrap();
+    assert!(!verdict.holds);
+    let example = verdict.example.unwrap();
+    assert_eq!(xsd(r"\p{L}{2}").partial_match(&example), PartialMatch::Full);
+    assert_ne!(
+        xsd(r"\p{Lu}\p{Ll}").partial_match(&example),
+        PartialMatch::Full
+    );
+}
+
+#[test]
+fn test_is_subset_repeats() {
+    assert!(is_subset(&xsd("(ab){2,3}"), &xsd("(ab)+")).unwrap().holds);
+    assert!(is_subset(&xsd("a{3,}"), &xsd("aa+")).unwrap().holds);
+    let verdict = is_subset(&xsd("a{1,5}"), &xsd("a{2,4}")).unwrap();
+    assert_eq!(verdict.example.as_deref(), Some("a"));
+    assert!(is_subset(&xsd("(a*)*"), &xsd("a*")).unwrap().holds);
+}
+
+#[test]
+fn test_intersects() {
+    let verdict = intersects(&xsd(r"\d+"), &xsd("[5-9]x?")).unwrap();
+    assert!(verdict.holds);
+    assert_eq!(verdict.example.as_deref(), Some("5"));
+
+    let verdict = intersects(&xsd(r"\d+"), &xsd(r"\p{L}+")).unwrap();
+    assert!(!verdict.holds);
+    assert_eq!(verdict.example, None);
+}
+
+#[test]
+fn test_equivalent() {
+    assert!(equivalent(&xsd("(a|b)*"), &xsd("[ab]*")).unwrap().holds);
+    assert!(equivalent(&xsd("a+a*"), &xsd("a{1,}")).unwrap().holds);
+    assert!(equivalent(&xsd(r"\d"), &xsd(r"\p{Nd}")).unwrap().holds);
+    let verdict = equivalent(&xsd("ab|ac"), &xsd("a[bcd]")).unwrap();
+    assert!(!verdict.holds);
+    assert_eq!(verdict.example.as_deref(), Some("ad"));
+}
+
+#[test]
+fn test_flags() {
+    let case_blind = Regex::xpath("abc", "i").unwrap();
+    assert!(equivalent(&case_blind, &xsd("[aA][bB][cC]")).unwrap().holds);
+    let anchored = Regex::xpath("^ab$", "").unwrap();
+    assert!(equivalent(&anchored, &xsd("ab")).unwrap().holds);
+    let impossible = Regex::xpath("a^b", "").unwrap();
+    assert!(is_empty_language(&impossible).unwrap().holds);
+}
+
+#[test]
+fn test_is_empty_language() {
+    assert!(is_empty_language(&xsd("[a-z-[a-z]]")).unwrap().holds);
+    assert!(!is_empty_language(&xsd("x[a-z-[a-z]]*y{0}z")).unwrap().holds);
+    let verdict = is_empty_language(&xsd("x[a-z-[a-z]]*z")).unwrap();
+    assert!(!verdict.holds);
+    assert_eq!(verdict.example.as_deref(), Some("xz"));
+}
+
+#[test]
+fn test_unsupported() {
+    let back_reference = Regex::xpath(r"(a)\1", "").unwrap();
+    assert!(matches!(
+        is_subset(&back_reference, &xsd("a*")),
+        Err(Error::Unsupported(_))
+    ));
+    let multi_line = Regex::xpath("^a$", "m").unwrap();
+    assert!(matches!(
+        is_empty_language(&multi_line),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_limit() {
+    // telling these apart takes remembering the last characters read, which
+    // needs a state for every combination of them
+    let verdict = equivalent(&xsd("[ab]*a[ab]{4}"), &xsd("[ab]*b[ab]{4}")).unwrap();
+    assert!(!verdict.holds);
+    assert_eq!(verdict.example.as_deref(), Some("aaaaa"));
+    let result = equivalent(&xsd("[ab]*a[ab]{20}"), &xsd("[ab]*b[ab]{20}"));
+    assert!(matches!(result, Err(Error::LimitExceeded(_))));
+}
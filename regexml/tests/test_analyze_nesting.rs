@@ -0,0 +1,75 @@
+use regexml::{AnalyzeEntry, MatchEntry, Regex};
+
+fn string(s: &str) -> MatchEntry {
+    MatchEntry::String(s.to_string())
+}
+
+fn group(nr: usize, value: Vec<MatchEntry>) -> MatchEntry {
+    MatchEntry::Group { nr, value }
+}
+
+// the empty group 2 is inside group 1
+fn nested() -> Vec<AnalyzeEntry> {
+    vec![AnalyzeEntry::Match(vec![group(
+        1,
+        vec![string("a"), group(2, vec![])],
+    )])]
+}
+
+// the empty group 2 follows group 1
+fn adjacent() -> Vec<AnalyzeEntry> {
+    vec![AnalyzeEntry::Match(vec![
+        group(1, vec![string("a")]),
+        group(2, vec![]),
+    ])]
+}
+
+fn analyze(pattern: &str, flags: &str, haystack: &str) -> Vec<AnalyzeEntry> {
+    Regex::xpath(pattern, flags)
+        .unwrap()
+        .analyze(haystack)
+        .unwrap()
+        .collect()
+}
+
+#[test]
+fn test_nesting() {
+    assert_eq!(analyze("(a(b?))", "", "a"), nested());
+    assert_eq!(analyze("(a)(b?)", "", "a"), adjacent());
+}
+
+#[test]
+fn test_nesting_non_capturing() {
+    assert_eq!(analyze("(?:x)?(a(?:y?)(b?))", "", "a"), nested());
+    assert_eq!(analyze("(a)(?:(b?))", "", "a"), adjacent());
+}
+
+#[test]
+fn test_nesting_escaped_brackets() {
+    assert_eq!(analyze(r"\[?(a(b?))", "", "a"), nested());
+    assert_eq!(analyze(r"\]?(a)(b?)", "", "a"), adjacent());
+    assert_eq!(analyze(r"[\]]?(a)(b?)", "", "a"), adjacent());
+    assert_eq!(analyze(r"[(]?(a)(b?)", "", "a"), adjacent());
+}
+
+#[test]
+fn test_nesting_x_flag() {
+    assert_eq!(analyze("( a ( b? ) )", "x", "a"), nested());
+    assert_eq!(analyze("( a ) ( b? )", "x", "a"), adjacent());
+}
+
+#[test]
+fn test_nesting_q_flag() {
+    // with q the parentheses are ordinary characters, so there are no groups
+    assert_eq!(
+        analyze("(a)(b?)", "q", "x(a)(b?)"),
+        vec![
+            AnalyzeEntry::NonMatch("x".to_string()),
+            AnalyzeEntry::Match(vec![string("(a)(b?)")]),
+        ]
+    );
+    assert_eq!(
+        analyze("((", "q", "(("),
+        vec![AnalyzeEntry::Match(vec![string("((")])]
+    );
+}
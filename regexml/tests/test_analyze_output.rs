@@ -0,0 +1,85 @@
+use regexml::{
+    write_analyze_json, write_analyze_json_io, write_analyze_xml, write_analyze_xml_io, Regex,
+};
+
+fn xml(pattern: &str, haystack: &str) -> String {
+    let regex = Regex::xpath(pattern, "").unwrap();
+    let mut out = String::new();
+    write_analyze_xml(&mut out, regex.analyze(haystack).unwrap()).unwrap();
+    out
+}
+
+fn json(pattern: &str, haystack: &str) -> String {
+    let regex = Regex::xpath(pattern, "").unwrap();
+    let mut out = String::new();
+    write_analyze_json(&mut out, regex.analyze(haystack).unwrap()).unwrap();
+    out
+}
+
+#[test]
+fn test_xml() {
+    assert_eq!(
+        xml("a(b)(c?)", "xabz"),
+        concat!(
+            r#"<analyze-string-result xmlns="http://www.w3.org/2005/xpath-functions">"#,
+            r#"<non-match>x</non-match>"#,
+            r#"<match>a<group nr="1">b</group><group nr="2"/></match>"#,
+            r#"<non-match>z</non-match>"#,
+            r#"</analyze-string-result>"#
+        )
+    );
+}
+
+#[test]
+fn test_xml_empty() {
+    assert_eq!(
+        xml("a", ""),
+        r#"<analyze-string-result xmlns="http://www.w3.org/2005/xpath-functions"/>"#
+    );
+}
+
+#[test]
+fn test_xml_escaping() {
+    assert_eq!(
+        xml("&", "<&>\r\n\u{1}\""),
+        concat!(
+            r#"<analyze-string-result xmlns="http://www.w3.org/2005/xpath-functions">"#,
+            r#"<non-match>&lt;</non-match><match>&amp;</match>"#,
+            "<non-match>&gt;&#xD;\n&#x1;\"</non-match>",
+            r#"</analyze-string-result>"#
+        )
+    );
+}
+
+#[test]
+fn test_json() {
+    assert_eq!(
+        json("a(b(c?))", "xab"),
+        r#"[{"non-match":"x"},{"match":["a",{"nr":1,"value":["b",{"nr":2,"value":[]}]}]}]"#
+    );
+    assert_eq!(json("a", ""), "[]");
+    assert_eq!(
+        json("b", "\"\\\n\u{1}b"),
+        r#"[{"non-match":"\"\\\n\u0001"},{"match":["b"]}]"#
+    );
+}
+
+#[test]
+fn test_io() {
+    let regex = Regex::xpath("é", "").unwrap();
+    let mut out = Vec::new();
+    write_analyze_xml_io(&mut out, regex.analyze("aé").unwrap()).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        concat!(
+            r#"<analyze-string-result xmlns="http://www.w3.org/2005/xpath-functions">"#,
+            r#"<non-match>a</non-match><match>é</match></analyze-string-result>"#
+        )
+    );
+    let mut out = Vec::new();
+    write_analyze_json_io(&mut out, regex.analyze("aé").unwrap()).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"[{"non-match":"a"},{"match":["é"]}]"#
+    );
+}
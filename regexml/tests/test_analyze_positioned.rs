@@ -0,0 +1,159 @@
+use regexml::{Position, PositionedAnalyzeEntry, PositionedMatchEntry, Regex};
+
+fn position(start: usize, end: usize, byte_start: usize, byte_end: usize) -> Position {
+    Position {
+        start,
+        end,
+        byte_start,
+        byte_end,
+    }
+}
+
+#[test]
+fn test_positions() {
+    let regex = Regex::xpath("b(c)", "").unwrap();
+    let entries = regex
+        .analyze_positioned("abcd")
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        vec![
+            PositionedAnalyzeEntry::NonMatch {
+                position: position(0, 1, 0, 1),
+                text: "a".to_string()
+            },
+            PositionedAnalyzeEntry::Match {
+                position: position(1, 3, 1, 3),
+                entries: vec![
+                    PositionedMatchEntry::String {
+                        position: position(1, 2, 1, 2),
+                        text: "b".to_string()
+                    },
+                    PositionedMatchEntry::Group {
+                        nr: 1,
+                        position: position(2, 3, 2, 3),
+                        value: vec![PositionedMatchEntry::String {
+                            position: position(2, 3, 2, 3),
+                            text: "c".to_string()
+                        }]
+                    }
+                ]
+            },
+            PositionedAnalyzeEntry::NonMatch {
+                position: position(3, 4, 3, 4),
+                text: "d".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_positions_multibyte() {
+    // é is two bytes, 𝄞 four
+    let regex = Regex::xpath("𝄞(x?)", "").unwrap();
+    let entries = regex.analyze_positioned("é𝄞é").unwrap().collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        vec![
+            PositionedAnalyzeEntry::NonMatch {
+                position: position(0, 1, 0, 2),
+                text: "é".to_string()
+            },
+            PositionedAnalyzeEntry::Match {
+                position: position(1, 2, 2, 6),
+                entries: vec![
+                    PositionedMatchEntry::String {
+                        position: position(1, 2, 2, 6),
+                        text: "𝄞".to_string()
+                    },
+                    PositionedMatchEntry::Group {
+                        nr: 1,
+                        position: position(2, 2, 6, 6),
+                        value: vec![]
+                    }
+                ]
+            },
+            PositionedAnalyzeEntry::NonMatch {
+                position: position(2, 3, 6, 8),
+                text: "é".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_positions_slice_input() {
+    let haystack = "één twee drie";
+    let regex = Regex::xpath(r"\w+", "").unwrap();
+    for entry in regex.analyze_positioned(haystack).unwrap() {
+        if let PositionedAnalyzeEntry::Match { position, entries } = entry {
+            let PositionedMatchEntry::String { text, .. } = &entries[0] else {
+                panic!("expected a string");
+            };
+            assert_eq!(&haystack[position.byte_start..position.byte_end], text);
+        }
+    }
+}
+
+// every string in the output is found at its position in the input
+fn check_text(haystack: &str, position: &Position, text: &str) {
+    assert_eq!(&haystack[position.byte_start..position.byte_end], text);
+    assert_eq!(
+        haystack
+            .chars()
+            .skip(position.start)
+            .take(position.end - position.start)
+            .collect::<String>(),
+        text
+    );
+}
+
+fn check_entries(haystack: &str, entries: &[PositionedMatchEntry], start: usize, end: usize) {
+    let mut offset = start;
+    for entry in entries {
+        match entry {
+            PositionedMatchEntry::String { position, text } => {
+                assert_eq!(position.start, offset);
+                check_text(haystack, position, text);
+                offset = position.end;
+            }
+            PositionedMatchEntry::Group {
+                position, value, ..
+            } => {
+                assert!(position.start >= offset && position.end <= end);
+                check_entries(haystack, value, position.start, position.end);
+                offset = position.end;
+            }
+        }
+    }
+    assert_eq!(offset, end);
+}
+
+#[test]
+fn test_positions_from_groups() {
+    for (regex, haystack) in [
+        (Regex::xpath("(é|b)+(𝄞?)", "").unwrap(), "xébé𝄞yb"),
+        (Regex::xpath("((a)|(b))*c", "").unwrap(), "aabcbc"),
+        (Regex::xpath("(a(b?))(c)", "").unwrap(), "éac"),
+        (Regex::xpath40("é*", "").unwrap(), "aéé"),
+        (Regex::ecmascript("a(?=(bé))", "").unwrap(), "éabé"),
+    ] {
+        let mut offset = 0;
+        for entry in regex.analyze_positioned(haystack).unwrap() {
+            match entry {
+                PositionedAnalyzeEntry::Match { position, entries } => {
+                    assert_eq!(position.start, offset);
+                    check_entries(haystack, &entries, position.start, position.end);
+                    offset = position.end;
+                }
+                PositionedAnalyzeEntry::NonMatch { position, text } => {
+                    assert_eq!(position.start, offset);
+                    check_text(haystack, &position, &text);
+                    offset = position.end;
+                }
+            }
+        }
+        assert_eq!(offset, haystack.chars().count());
+    }
+}
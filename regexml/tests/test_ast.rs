@@ -0,0 +1,202 @@
+use regexml::ast::{
+    parse, Atom, CharClassExpr, ClassEscape, ClassEscapeKind, ClassItem, Expr, Literal,
+    QuantifierKind,
+};
+use regexml::{Error, Language, Span};
+
+fn xpath(pattern: &str) -> Expr {
+    parse(pattern, "", Language::XPath).unwrap()
+}
+
+fn literal(c: char, start: usize) -> Literal {
+    Literal {
+        c,
+        escaped: false,
+        case_sensitive: false,
+        span: Span::new(start, start + 1),
+    }
+}
+
+#[test]
+fn test_branches() {
+    let expr = xpath("ab|c|");
+    assert_eq!(expr.span, Span::new(0, 5));
+    assert_eq!(expr.branches.len(), 3);
+    assert_eq!(expr.branches[0].span, Span::new(0, 2));
+    assert_eq!(expr.branches[0].pieces.len(), 2);
+    assert_eq!(expr.branches[1].span, Span::new(3, 4));
+    assert_eq!(expr.branches[2].span, Span::new(5, 5));
+    assert!(expr.branches[2].pieces.is_empty());
+}
+
+#[test]
+fn test_quantifiers() {
+    let expr = xpath("a*b{2,3}?c{4,}");
+    let pieces = &expr.branches[0].pieces;
+
+    let star = pieces[0].quantifier.as_ref().unwrap();
+    assert_eq!(star.kind, QuantifierKind::ZeroOrMore);
+    assert_eq!((star.min(), star.max()), (0, None));
+
+    let between = pieces[1].quantifier.as_ref().unwrap();
+    assert_eq!(between.kind, QuantifierKind::Between(2, 3));
+    assert!(!between.greedy);
+    assert_eq!(between.span, Span::new(3, 9));
+    assert_eq!(pieces[1].span, Span::new(2, 9));
+
+    let at_least = pieces[2].quantifier.as_ref().unwrap();
+    assert_eq!(at_least.kind, QuantifierKind::AtLeast(4));
+    assert_eq!(at_least.max(), None);
+}
+
+#[test]
+fn test_groups_and_back_references() {
+    let expr = xpath(r"(a)(?:b(c))\2");
+    let pieces = &expr.branches[0].pieces;
+    let Atom::Group(first) = &pieces[0].atom else {
+        panic!("expected group");
+    };
+    assert_eq!(first.capture, Some(1));
+    assert_eq!(first.span, Span::new(0, 3));
+    let Atom::Group(second) = &pieces[1].atom else {
+        panic!("expected group");
+    };
+    assert_eq!(second.capture, None);
+    assert_eq!(second.expr.span, Span::new(6, 10));
+    let Atom::Group(nested) = &second.expr.branches[0].pieces[1].atom else {
+        panic!("expected group");
+    };
+    assert_eq!(nested.capture, Some(2));
+    let Atom::BackReference(back_reference) = &pieces[2].atom else {
+        panic!("expected back-reference");
+    };
+    assert_eq!(back_reference.group, 2);
+    assert_eq!(back_reference.span, Span::new(11, 13));
+}
+
+#[test]
+fn test_escapes() {
+    let expr = xpath(r"\*\P{Lu}\p{IsBasicLatin}^$.");
+    let atoms = expr.branches[0]
+        .pieces
+        .iter()
+        .map(|piece| piece.atom.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        atoms,
+        vec![
+            Atom::Literal(Literal {
+                c: '*',
+                escaped: true,
+                case_sensitive: false,
+                span: Span::new(0, 2)
+            }),
+            Atom::ClassEscape(ClassEscape {
+                kind: ClassEscapeKind::Category("Lu".to_string()),
+                negated: true,
+                span: Span::new(2, 8)
+            }),
+            Atom::ClassEscape(ClassEscape {
+                kind: ClassEscapeKind::Block("BasicLatin".to_string()),
+                negated: false,
+                span: Span::new(8, 24)
+            }),
+            Atom::Start(Span::new(24, 25)),
+            Atom::End(Span::new(25, 26)),
+            Atom::Any(Span::new(26, 27)),
+        ]
+    );
+}
+
+#[test]
+fn test_xsd_anchors_are_literals() {
+    let expr = parse("^a$", "", Language::XSD).unwrap();
+    let atoms = expr.branches[0]
+        .pieces
+        .iter()
+        .map(|piece| piece.atom.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        atoms,
+        vec![
+            Atom::Literal(literal('^', 0)),
+            Atom::Literal(literal('a', 1)),
+            Atom::Literal(literal('$', 2)),
+        ]
+    );
+}
+
+#[test]
+fn test_character_class() {
+    let expr = xpath(r"[^a-z\d_-[aeiou]]");
+    let Atom::CharClass(class) = &expr.branches[0].pieces[0].atom else {
+        panic!("expected character class");
+    };
+    assert_eq!(
+        class,
+        &CharClassExpr {
+            negated: true,
+            items: vec![
+                ClassItem::Range(literal('a', 2), literal('z', 4)),
+                ClassItem::Escape(ClassEscape {
+                    kind: ClassEscapeKind::Digit,
+                    negated: false,
+                    span: Span::new(5, 7)
+                }),
+                ClassItem::Char(Literal {
+                    case_sensitive: true,
+                    ..literal('_', 7)
+                }),
+            ],
+            subtraction: Some(Box::new(CharClassExpr {
+                negated: false,
+                items: "aeiou"
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| ClassItem::Char(literal(c, 10 + i)))
+                    .collect(),
+                subtraction: None,
+                span: Span::new(9, 16)
+            })),
+            span: Span::new(0, 17)
+        }
+    );
+    assert_eq!(class.items[0].span(), Span::new(2, 5));
+}
+
+#[test]
+fn test_spans_with_whitespace_flag() {
+    let expr = parse("a  b {2}", "x", Language::XPath).unwrap();
+    let pieces = &expr.branches[0].pieces;
+    assert_eq!(pieces[0].span, Span::new(0, 1));
+    assert_eq!(pieces[1].span, Span::new(3, 8));
+    assert_eq!(pieces[1].quantifier.as_ref().unwrap().span, Span::new(5, 8));
+}
+
+#[test]
+fn test_literal_flag() {
+    let expr = parse("a.*", "q", Language::XPath).unwrap();
+    let atoms = expr.branches[0]
+        .pieces
+        .iter()
+        .map(|piece| piece.atom.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        atoms,
+        vec![
+            Atom::Literal(literal('a', 0)),
+            Atom::Literal(literal('.', 1)),
+            Atom::Literal(literal('*', 2)),
+        ]
+    );
+}
+
+#[test]
+fn test_errors_match_compiler() {
+    for pattern in ["a{3,1}", "(a", "a)", r"\1", "[a-", r"\p{Xx}", "*"] {
+        let parse_error = parse(pattern, "", Language::XPath).unwrap_err();
+        let compile_error = regexml::Regex::xpath(pattern, "").unwrap_err();
+        assert_eq!(parse_error, compile_error, "{}", pattern);
+        assert!(matches!(parse_error, Error::Syntax(_)));
+    }
+}
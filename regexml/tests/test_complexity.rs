@@ -0,0 +1,115 @@
+use regexml::{ComplexityKind, Regex, Risk, Span};
+
+fn risk(pattern: &str, flags: &str) -> Risk {
+    Regex::xpath(pattern, flags)
+        .unwrap()
+        .analyze_complexity()
+        .unwrap()
+        .risk
+}
+
+#[test]
+fn test_nested_quantifier() {
+    let complexity = Regex::xpath("x(a+)+y", "")
+        .unwrap()
+        .analyze_complexity()
+        .unwrap();
+    assert_eq!(complexity.risk, Risk::Exponential);
+    assert_eq!(complexity.issues.len(), 1);
+    let issue = &complexity.issues[0];
+    assert_eq!(issue.kind, ComplexityKind::NestedQuantifier);
+    assert_eq!(issue.span, Span::new(1, 6));
+    assert_eq!(issue.related, vec![Span::new(2, 4)]);
+    assert_eq!(
+        issue.to_string(),
+        "Repeat consists of another repeat at 1..6"
+    );
+
+    assert_eq!(risk(r"^(\w+\s?)*$", ""), Risk::Exponential);
+    assert_eq!(risk("((ab)*)*", ""), Risk::Exponential);
+    assert_eq!(risk("(x+x+)+y", ""), Risk::Exponential);
+    // a bounded outer repeat limits the damage
+    assert_eq!(risk("(a{1,3}){1,10}", ""), Risk::Polynomial);
+}
+
+#[test]
+fn test_repeat_absorbs_required_text() {
+    let complexity = Regex::xpath("(.*a){20}", "")
+        .unwrap()
+        .analyze_complexity()
+        .unwrap();
+    assert_eq!(complexity.risk, Risk::Polynomial);
+    let issue = &complexity.issues[0];
+    assert_eq!(issue.kind, ComplexityKind::NestedQuantifier);
+    assert_eq!(issue.span, Span::new(0, 9));
+    assert_eq!(issue.related, vec![Span::new(1, 3), Span::new(3, 4)]);
+
+    assert_eq!(risk("(.*a)*", ""), Risk::Exponential);
+    assert_eq!(risk("(a.*){2,5}x", ""), Risk::Polynomial);
+    assert_eq!(risk(r"(\w+ab)+", ""), Risk::Exponential);
+    // the required text can't be matched by the repeat, so it marks where
+    // each repetition ends
+    assert_eq!(risk("(a*b){20}", ""), Risk::Low);
+    assert_eq!(risk("(.*\n){20}", ""), Risk::Low);
+}
+
+#[test]
+fn test_overlapping_alternation() {
+    let complexity = Regex::xpath(r"(\d|[0-5])*", "")
+        .unwrap()
+        .analyze_complexity()
+        .unwrap();
+    assert_eq!(complexity.risk, Risk::Exponential);
+    let issue = &complexity.issues[0];
+    assert_eq!(issue.kind, ComplexityKind::OverlappingAlternation);
+    assert_eq!(issue.related, vec![Span::new(1, 3), Span::new(4, 9)]);
+
+    // the alternatives only share a prefix
+    assert_eq!(risk("(a|ab)*", ""), Risk::Polynomial);
+    // the alternatives only overlap when ignoring case
+    assert_eq!(risk("(A|a)*", ""), Risk::Low);
+    assert_eq!(risk("(A|a)*", "i"), Risk::Exponential);
+}
+
+#[test]
+fn test_adjacent_quantifiers() {
+    let complexity = Regex::xpath(r"\d+x?\d*", "")
+        .unwrap()
+        .analyze_complexity()
+        .unwrap();
+    assert_eq!(complexity.risk, Risk::Polynomial);
+    let issue = &complexity.issues[0];
+    assert_eq!(issue.kind, ComplexityKind::AdjacentQuantifiers);
+    assert_eq!(issue.span, Span::new(0, 8));
+    assert_eq!(issue.related, vec![Span::new(0, 3), Span::new(5, 8)]);
+}
+
+#[test]
+fn test_safe_patterns() {
+    for pattern in [
+        "(a+b)+",
+        r"(\s*,\s*)+",
+        "a*b*",
+        "[a-z]+@[a-z]+",
+        r"\d+-\d+",
+        "(ab|cd)*",
+        "(a+)?",
+    ] {
+        let complexity = Regex::xpath(pattern, "")
+            .unwrap()
+            .analyze_complexity()
+            .unwrap();
+        assert_eq!(complexity.risk, Risk::Low, "{}", pattern);
+        assert!(complexity.issues.is_empty());
+    }
+}
+
+#[test]
+fn test_spans_with_whitespace_flag() {
+    let complexity = Regex::xpath("( a + ) +", "x")
+        .unwrap()
+        .analyze_complexity()
+        .unwrap();
+    assert_eq!(complexity.issues[0].span, Span::new(0, 9));
+    assert_eq!(complexity.issues[0].related, vec![Span::new(2, 5)]);
+}
@@ -0,0 +1,74 @@
+use regexml::{Diagnosis, Regex};
+
+fn diagnose(pattern: &str, s: &str) -> Option<Diagnosis> {
+    Regex::xsd(pattern, "").unwrap().diagnose(s)
+}
+
+#[test]
+fn test_diagnose_class() {
+    let diagnosis = diagnose(r"\d{3}-\d{4}", "555-12x4").unwrap();
+    assert_eq!(diagnosis.position, 6);
+    assert_eq!(diagnosis.found, Some('x'));
+    assert_eq!(diagnosis.expected.as_deref(), Some(r"\d"));
+    assert!(!diagnosis.end_expected);
+    assert_eq!(
+        diagnosis.to_string(),
+        r"at position 6 expected \d but found 'x'"
+    );
+}
+
+#[test]
+fn test_diagnose_matches() {
+    assert_eq!(diagnose(r"\d{3}-\d{4}", "555-1234"), None);
+}
+
+#[test]
+fn test_diagnose_end() {
+    let diagnosis = diagnose("abc", "ab").unwrap();
+    assert_eq!(diagnosis.position, 2);
+    assert_eq!(diagnosis.found, None);
+    assert_eq!(diagnosis.expected.as_deref(), Some("[c]"));
+    assert_eq!(
+        diagnosis.to_string(),
+        "at position 2 expected [c] but found the end of the string"
+    );
+
+    let diagnosis = diagnose("ab?", "abb").unwrap();
+    assert_eq!(diagnosis.position, 2);
+    assert_eq!(diagnosis.expected, None);
+    assert!(diagnosis.end_expected);
+    assert_eq!(
+        diagnosis.to_string(),
+        "at position 2 expected the end of the string but found 'b'"
+    );
+}
+
+#[test]
+fn test_diagnose_alternatives() {
+    // both branches get to position 1
+    let diagnosis = diagnose("ab|a[0-9]", "ax").unwrap();
+    assert_eq!(diagnosis.position, 1);
+    assert_eq!(diagnosis.expected.as_deref(), Some("[0-9b]"));
+
+    // the furthest branch wins
+    let diagnosis = diagnose("abc|ab[0-9]|x", "abz").unwrap();
+    assert_eq!(diagnosis.position, 2);
+    assert_eq!(diagnosis.expected.as_deref(), Some("[0-9c]"));
+
+    let diagnosis = diagnose("a*", "aab").unwrap();
+    assert_eq!(diagnosis.position, 2);
+    assert_eq!(diagnosis.expected.as_deref(), Some("[a]"));
+    assert!(diagnosis.end_expected);
+    assert_eq!(
+        diagnosis.to_string(),
+        "at position 2 expected [a] or the end of the string but found 'b'"
+    );
+}
+
+#[test]
+fn test_diagnose_back_reference() {
+    let regex = Regex::xpath(r"(\w+)-\1", "").unwrap();
+    let diagnosis = regex.diagnose("ab-ac").unwrap();
+    assert_eq!(diagnosis.position, 4);
+    assert_eq!(diagnosis.expected.as_deref(), Some("[b]"));
+}
@@ -0,0 +1,96 @@
+use regexml::{Error, Language, Regex, Span, Warning, WarningKind};
+
+fn warnings(re: &str, flags: &str) -> Vec<Warning> {
+    let (_, warnings) = Regex::compile_with_diagnostics(re, flags, Language::XPath).unwrap();
+    warnings
+}
+
+#[test]
+fn test_no_warnings() {
+    assert_eq!(warnings(r"^[a-z]+\d{2,3}$", ""), vec![]);
+}
+
+#[test]
+fn test_unknown_block_is_error_by_default() {
+    let err = Regex::compile_with_diagnostics(r"\p{IsFoo}", "", Language::XPath).unwrap_err();
+    assert_eq!(err, Error::Syntax("Unknown Unicode block: Foo".to_string()));
+}
+
+#[test]
+fn test_unknown_block_allowed() {
+    let (regex, warnings) =
+        Regex::compile_with_diagnostics(r"a\p{IsFoo}", ";k", Language::XPath).unwrap();
+    assert_eq!(
+        warnings,
+        vec![Warning {
+            kind: WarningKind::UnknownBlock("Foo".to_string()),
+            span: Span::new(1, 10),
+        }]
+    );
+    assert!(regex.is_match("ax"));
+    assert!(regex.is_match("a\u{1F600}"));
+}
+
+#[test]
+fn test_unknown_block_negated_allowed() {
+    let (regex, _) =
+        Regex::compile_with_diagnostics(r"^\P{IsFoo}$", ";k", Language::XPath).unwrap();
+    assert!(regex.is_match("x"));
+}
+
+#[test]
+fn test_deprecated_block() {
+    assert_eq!(
+        warnings(r"[\p{IsGreek}]", ""),
+        vec![Warning {
+            kind: WarningKind::DeprecatedBlock("Greek".to_string(), "GreekandCoptic".to_string()),
+            span: Span::new(1, 12),
+        }]
+    );
+}
+
+#[test]
+fn test_whitespace_in_escape() {
+    assert_eq!(
+        warnings(r"a \ d", "x"),
+        vec![Warning {
+            kind: WarningKind::WhitespaceInEscape,
+            span: Span::new(3, 4),
+        }]
+    );
+}
+
+#[test]
+fn test_zero_repetitions() {
+    // see test_infinite_loop
+    assert_eq!(
+        warnings(r#"(( a | ( bc ) ) {0,0} )+ xyz"#, "x"),
+        vec![Warning {
+            kind: WarningKind::ZeroRepetitions,
+            span: Span::new(1, 21),
+        }]
+    );
+}
+
+#[test]
+fn test_empty_subtraction() {
+    assert_eq!(
+        warnings(r"x[a-c-[a-z]]", ""),
+        vec![Warning {
+            kind: WarningKind::EmptySubtraction,
+            span: Span::new(1, 12),
+        }]
+    );
+}
+
+#[test]
+fn test_warning_display() {
+    let warning = Warning {
+        kind: WarningKind::ZeroRepetitions,
+        span: Span::new(1, 6),
+    };
+    assert_eq!(
+        warning.to_string(),
+        "Quantifier allows no repetitions; expression is never matched at 1..6"
+    );
+}
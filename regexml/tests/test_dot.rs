@@ -0,0 +1,60 @@
+use regexml::Regex;
+
+fn dot(pattern: &str) -> String {
+    Regex::xpath(pattern, "").unwrap().to_dot()
+}
+
+#[test]
+fn test_dot() {
+    assert_eq!(
+        dot("ab"),
+        r#"digraph regex {
+  rankdir=LR;
+  node [shape=circle, label=""];
+  start [shape=point];
+  start -> 0;
+  1 [shape=doublecircle];
+  0 -> 2 [label="a"];
+  2 -> 1 [label="b"];
+}
+"#
+    );
+}
+
+#[test]
+fn test_captures_and_anchors() {
+    let dot = dot(r"^(a)\1$");
+    assert!(dot.contains(r#"[shape=box, label="(1"]"#));
+    assert!(dot.contains(r#"[shape=box, label="1)"]"#));
+    assert!(dot.contains(r#"[shape=diamond, label="^"]"#));
+    assert!(dot.contains(r#"[shape=diamond, label="$"]"#));
+    assert!(dot.contains(r#"[label="\\1"]"#));
+}
+
+#[test]
+fn test_class_labels() {
+    assert!(dot(r"[\d\p{Lu}]").contains(r#"[label="[\\p{Lu}\\d]"]"#));
+    assert!(dot(r"x\s").contains(r#"[label="\\s"]"#));
+    assert!(dot(".").contains(r#"[label="[^\\n\\r]"]"#));
+    assert!(dot("\"").contains(r#"[label="\""]"#));
+}
+
+#[test]
+fn test_repeats() {
+    // small repeats are unrolled
+    assert_eq!(dot("a{3}b").matches(r#"[label="a"]"#).count(), 3);
+    // large ones are drawn as a loop
+    let large = dot("a{2,20}b");
+    assert_eq!(large.matches(r#"[label="a"]"#).count(), 1);
+    assert!(large.contains(r#"[shape=octagon, label="{2,20}"]"#));
+}
+
+#[test]
+fn test_nested_repeats() {
+    // unrolling nested repeats must not multiply the drawing
+    let nested = dot("((((a{8}){8}){8}){8}){8}");
+    assert!(nested.len() < 5_000);
+    assert!(nested.contains(r#"[shape=octagon, label="{8,8}"]"#));
+    let pattern = format!("{}a{}", "(?:".repeat(60), "){8}".repeat(60));
+    assert!(dot(&pattern).len() < 20_000);
+}
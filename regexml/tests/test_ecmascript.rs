@@ -0,0 +1,133 @@
+use regexml::ast::{explain, parse};
+use regexml::{Error, Language, Regex};
+
+fn regex(pattern: &str) -> Regex {
+    Regex::ecmascript(pattern, "").unwrap()
+}
+
+#[test]
+fn test_lookahead() {
+    let regex = regex(r"^(?=.*\d)(?!.*  )[\w ]+$");
+    assert!(regex.is_match("abc 1"));
+    assert!(!regex.is_match("abc"));
+    assert!(!regex.is_match("abc  1"));
+}
+
+#[test]
+fn test_lookahead_does_not_consume() {
+    let regex = regex("a(?=b)");
+    let m = regex.find_at("xab", 0).unwrap();
+    assert_eq!(m.as_str(), "a");
+}
+
+#[test]
+fn test_word_boundary() {
+    let regex = regex(r"\bcat\b");
+    assert!(regex.is_match("a cat!"));
+    assert!(!regex.is_match("concatenate"));
+    let regex = self::regex(r"\Bcat\B");
+    assert!(regex.is_match("concatenate"));
+    assert!(!regex.is_match("a cat!"));
+}
+
+#[test]
+fn test_backspace_in_class() {
+    assert!(regex(r"^[\b]$").is_match("\u{0008}"));
+}
+
+#[test]
+fn test_unicode_escapes() {
+    assert!(regex(r"^é\u{1F600}\x41$").is_match("é😀A"));
+    assert!(regex(r"^😀$").is_match("😀"));
+}
+
+#[test]
+fn test_line_terminators() {
+    let regex = Regex::ecmascript("^b$", "m").unwrap();
+    assert!(regex.is_match("a\rb\u{2028}c"));
+    assert!(!Regex::ecmascript("^.$", "").unwrap().is_match("\u{2029}"));
+}
+
+#[test]
+fn test_case_folding() {
+    // with 'i', characters are compared by their simple case folding, and
+    // a class matches the case variants of its characters
+    let regex = |pattern| Regex::ecmascript(pattern, "i").unwrap();
+    assert!(regex(r"^\p{Lu}$").is_match("a"));
+    assert!(regex(r"^\P{Ll}$").is_match("a"));
+    assert!(!regex(r"^\p{Lu}$").is_match("1"));
+    assert!(regex("^ſ$").is_match("s"));
+    assert!(regex("^ſ$").is_match("S"));
+    assert!(regex("^s$").is_match("ſ"));
+    assert!(regex("^k$").is_match("\u{212A}"));
+    assert!(regex(r"^(s)\1$").is_match("sſ"));
+    assert!(regex(r"^\w$").is_match("ſ"));
+    assert!(!regex(r"^\W$").is_match("s"));
+    assert!(!regex(r"^\W$").is_match("ſ"));
+    assert!(regex(r"^[^\p{Lu}]$").is_match("1"));
+    assert!(!regex(r"^[^\p{Lu}]$").is_match("a"));
+}
+
+#[test]
+fn test_unset_back_reference() {
+    // an unset group matches the empty string
+    assert!(regex(r"^(?:(a)|b)\1$").is_match("b"));
+    assert!(regex(r"^\1(a)$").is_match("a"));
+}
+
+#[test]
+fn test_empty_classes() {
+    assert!(!regex("[]").is_match("a"));
+    assert!(regex("^[^]$").is_match("\n"));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        Regex::ecmascript("(?<=a)b", "").unwrap_err(),
+        Error::Unsupported("Lookbehinds are not supported".to_string())
+    );
+    assert_eq!(
+        Regex::ecmascript("(?<name>a)", "").unwrap_err(),
+        Error::Unsupported("Named groups are not supported".to_string())
+    );
+    assert!(matches!(
+        Regex::ecmascript(r"a\b+", "").unwrap_err(),
+        Error::Syntax(_)
+    ));
+    assert!(matches!(
+        Regex::ecmascript(r"[\d-a]", "").unwrap_err(),
+        Error::Syntax(_)
+    ));
+    assert_eq!(
+        Regex::ecmascript("a", "x").unwrap_err(),
+        Error::InvalidFlags("'x' flag is not available in ECMAScript".to_string())
+    );
+}
+
+#[test]
+fn test_not_in_xpath() {
+    assert!(Regex::xpath("(?=a)", "").is_err());
+    assert!(Regex::xpath(r"\b", "").is_err());
+}
+
+#[test]
+fn test_print() {
+    let expr = parse(r"\bx(?=y)(?!z)\B", "", Language::EcmaScript).unwrap();
+    assert_eq!(
+        expr.to_pattern(Language::EcmaScript).unwrap(),
+        r"\bx(?=y)(?!z)\B"
+    );
+    assert!(matches!(
+        expr.to_pattern(Language::XPath),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_explain() {
+    assert_eq!(
+        explain(r"\bx(?!y)", "", Language::EcmaScript).unwrap().text,
+        "a word boundary, then the character 'x', then a position not followed by (the character 'y')"
+    );
+}
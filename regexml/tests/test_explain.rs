@@ -0,0 +1,78 @@
+use regexml::ast::explain;
+use regexml::{Explanation, Language, Span};
+
+fn text(pattern: &str, flags: &str, language: Language) -> String {
+    explain(pattern, flags, language).unwrap().text
+}
+
+#[test]
+fn test_explain_tree() {
+    let explanation = explain(r"[A-Z]{2}\d{4}", "", Language::XSD).unwrap();
+    assert_eq!(
+        explanation,
+        Explanation {
+            text: "exactly 2 characters from A\u{2013}Z, then exactly 4 decimal digits (\\p{Nd})"
+                .to_string(),
+            span: Span::new(0, 13),
+            children: vec![
+                Explanation {
+                    text: "exactly 2 characters from A\u{2013}Z".to_string(),
+                    span: Span::new(0, 8),
+                    children: vec![Explanation {
+                        text: "A\u{2013}Z".to_string(),
+                        span: Span::new(1, 4),
+                        children: vec![],
+                    }],
+                },
+                Explanation {
+                    text: "exactly 4 decimal digits (\\p{Nd})".to_string(),
+                    span: Span::new(8, 13),
+                    children: vec![],
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn test_explain_literals() {
+    assert_eq!(
+        text("abc|x?y*?", "", Language::XPath),
+        "either the text \"abc\", or optionally the character 'x', then any number of 'y' characters (as few as possible)"
+    );
+    assert_eq!(text(" ", "", Language::XPath), "a space");
+    assert_eq!(text("", "", Language::XPath), "the empty string");
+}
+
+#[test]
+fn test_explain_categories_and_blocks() {
+    assert_eq!(
+        text(r"\P{Lu}", "", Language::XSD),
+        "a character that is not an uppercase letter (\\p{Lu})"
+    );
+    assert_eq!(
+        text(r"\p{IsBasicLatin}+", "", Language::XSD),
+        "one or more characters in the Basic Latin block (U+0000\u{2013}U+007F)"
+    );
+    assert_eq!(
+        text(r"\p{IsFoo}", ";k", Language::XSD),
+        "any character (unknown block Foo)"
+    );
+}
+
+#[test]
+fn test_explain_character_class_subtraction() {
+    assert_eq!(
+        text(r"[a-z-[aeiou]]", "", Language::XSD),
+        "a character from a\u{2013}z, except those from a, e, i, o or u"
+    );
+}
+
+#[test]
+fn test_explain_groups_and_flags() {
+    assert_eq!(
+        text(r"^(a|b){2,3}\1$", "mi", Language::XPath),
+        "the start of a line, then between 2 and 3 repetitions of group 1 (either the character 'a', or the character 'b'), then the same text as matched by group 1, then the end of a line, ignoring case"
+    );
+    assert_eq!(text(".{1}", "s", Language::XPath), "exactly one character");
+}
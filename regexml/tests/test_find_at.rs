@@ -0,0 +1,64 @@
+use regexml::Regex;
+
+#[test]
+fn test_find_at() {
+    let regex = Regex::xpath("b+", "").unwrap();
+    let found = regex.find_at("abbcb", 0).unwrap();
+    assert_eq!(found.range(), 1..3);
+    assert_eq!(found.as_str(), "bb");
+    let found = regex.find_at("abbcb", 2).unwrap();
+    assert_eq!(found.range(), 2..3);
+    let found = regex.find_at("abbcb", 3).unwrap();
+    assert_eq!(found.range(), 4..5);
+    assert!(regex.find_at("abbcb", 5).is_none());
+    assert!(regex.find_at("abbcb", 6).is_none());
+}
+
+#[test]
+fn test_is_match_at() {
+    let regex = Regex::xpath("b", "").unwrap();
+    assert!(regex.is_match_at("abc", 1));
+    assert!(!regex.is_match_at("abc", 2));
+}
+
+#[test]
+fn test_match_prefix() {
+    let regex = Regex::xpath("b+", "").unwrap();
+    assert_eq!(regex.match_prefix("abbcb", 1).unwrap().as_str(), "bb");
+    assert!(regex.match_prefix("abbcb", 0).is_none());
+    assert!(regex.match_prefix("abbcb", 3).is_none());
+    assert_eq!(regex.match_prefix("abbcb", 4).unwrap().range(), 4..5);
+
+    let regex = Regex::xpath("x*", "").unwrap();
+    let found = regex.match_prefix("ab", 2).unwrap();
+    assert!(found.is_empty());
+    assert_eq!(found.start(), 2);
+}
+
+#[test]
+fn test_anchors_see_whole_haystack() {
+    let regex = Regex::xpath("^b", "").unwrap();
+    assert!(!regex.is_match_at("ab", 1));
+    assert!(regex.match_prefix("ab", 1).is_none());
+
+    let regex = Regex::xpath("^b", "m").unwrap();
+    assert!(regex.is_match_at("a\nb", 2));
+    assert_eq!(regex.match_prefix("a\nb", 2).unwrap().range(), 2..3);
+
+    let regex = Regex::xpath("a$", "").unwrap();
+    assert!(!regex.is_match_at("abab", 2));
+    assert!(regex.match_prefix("aba", 2).is_some());
+}
+
+#[test]
+fn test_byte_offsets() {
+    let regex = Regex::xpath("é+", "").unwrap();
+    let haystack = "aéébé";
+    let found = regex.find_at(haystack, 1).unwrap();
+    assert_eq!(found.range(), 1..5);
+    assert_eq!(found.as_str(), "éé");
+    assert_eq!(regex.find_at(haystack, 5).unwrap().range(), 6..8);
+    // not at a character boundary
+    assert!(regex.find_at(haystack, 2).is_none());
+    assert!(regex.match_prefix(haystack, 2).is_none());
+}
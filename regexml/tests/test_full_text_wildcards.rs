@@ -0,0 +1,114 @@
+use regexml::ast::{explain, parse};
+use regexml::{Dialect, Error, Language, Regex};
+
+fn wildcards(pattern: &str) -> Regex {
+    Regex::full_text_wildcards(pattern, "").unwrap()
+}
+
+#[test]
+fn test_wildcards() {
+    assert!(wildcards("qu.ck").is_match("quick"));
+    assert!(!wildcards("qu.ck").is_match("quck"));
+    assert!(wildcards("qu.?ck").is_match("quck"));
+    assert!(wildcards("qu.?ck").is_match("quick"));
+    assert!(wildcards("qu.*").is_match("qu"));
+    assert!(wildcards("qu.*").is_match("quickly"));
+    assert!(!wildcards("qu.+").is_match("qu"));
+    assert!(wildcards("qu.+").is_match("quack"));
+    assert!(wildcards("qu.{1,3}k").is_match("quick"));
+    assert!(!wildcards("qu.{3,4}k").is_match("quick"));
+}
+
+#[test]
+fn test_whole_token() {
+    let regex = wildcards("nation.*");
+    assert!(regex.is_match("nationality"));
+    assert!(!regex.is_match("international"));
+    assert!(!wildcards("ion").is_match("nation"));
+}
+
+#[test]
+fn test_wildcard_matches_newline() {
+    assert!(wildcards("a.b").is_match("a\nb"));
+}
+
+#[test]
+fn test_other_characters_are_literal() {
+    assert!(wildcards("a+b*[c]").is_match("a+b*[c]"));
+    assert!(!wildcards("a+b").is_match("aab"));
+    assert!(wildcards("^$|(").is_match("^$|("));
+}
+
+#[test]
+fn test_escapes() {
+    assert!(wildcards(r"3\.14").is_match("3.14"));
+    assert!(!wildcards(r"3\.14").is_match("3x14"));
+    assert!(wildcards(r"a\\b").is_match(r"a\b"));
+    assert!(wildcards(r"\a\.*").is_match("a.*"));
+}
+
+#[test]
+fn test_case_insensitive() {
+    let regex = Regex::full_text_wildcards("Qu.ck", "i").unwrap();
+    assert!(regex.is_match("QUICK"));
+    assert!(regex.is_match("quack"));
+}
+
+#[test]
+fn test_errors() {
+    assert!(matches!(
+        Regex::full_text_wildcards(r"abc\", "").unwrap_err(),
+        Error::Syntax(_)
+    ));
+    assert!(matches!(
+        Regex::full_text_wildcards("a.{3}", "").unwrap_err(),
+        Error::Syntax(_)
+    ));
+    assert!(matches!(
+        Regex::full_text_wildcards("a.{3,}", "").unwrap_err(),
+        Error::Syntax(_)
+    ));
+    assert!(matches!(
+        Regex::full_text_wildcards("a.{3,1}", "").unwrap_err(),
+        Error::Syntax(_)
+    ));
+    assert_eq!(
+        Regex::full_text_wildcards("a", "m").unwrap_err(),
+        Error::InvalidFlags("'m' flag is not available for full-text wildcards".to_string())
+    );
+}
+
+#[test]
+fn test_print() {
+    let expr = parse(r"a.?b.{2,3}\.", "", Language::FullTextWildcards).unwrap();
+    assert_eq!(
+        expr.to_pattern(Language::FullTextWildcards).unwrap(),
+        r"a.?b.{2,3}\."
+    );
+    assert_eq!(expr.to_pattern(Language::XPath).unwrap(), r"a.?b.{2,3}\.");
+
+    let expr = parse(r"(a|b)", "", Language::XPath).unwrap();
+    assert!(matches!(
+        expr.to_pattern(Language::FullTextWildcards),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_translate() {
+    let translation = wildcards(r"a.+\?").translate(Dialect::Pcre).unwrap();
+    assert_eq!(
+        translation.pattern,
+        r"\A(?:a[\x{0}-\x{D7FF}\x{E000}-\x{10FFFF}]+\?)\z"
+    );
+}
+
+#[test]
+fn test_explain() {
+    assert_eq!(
+        explain("a.*", "", Language::FullTextWildcards)
+            .unwrap()
+            .text,
+        "the character 'a', then any number of characters of any kind"
+    );
+}
@@ -0,0 +1,183 @@
+use regexml::{GenerateOptions, PartialMatch, Regex, RepeatBias, RngLike, SeededRng};
+
+const PATTERNS: &[&str] = &[
+    r"\d{3}-\d{4}",
+    r"[A-Z]{2}[0-9]+",
+    r"(cat|dog)s?",
+    r"\p{Lu}\p{Ll}*",
+    r"[a-z-[aeiou]]{5}",
+    r"(ab|c)\1",
+    r"\p{IsGreek}+",
+    r".{2,4}",
+    r"\i\c*",
+    r"x?",
+];
+
+#[test]
+fn test_generate_matches() {
+    let mut rng = SeededRng::new(42);
+    for pattern in PATTERNS {
+        let regex = Regex::xpath(pattern, "").unwrap();
+        for _ in 0..20 {
+            let generated = regex
+                .generate(&mut rng, &GenerateOptions::default())
+                .unwrap();
+            assert_eq!(
+                regex.partial_match(&generated),
+                PartialMatch::Full,
+                "{} {:?}",
+                pattern,
+                generated
+            );
+        }
+    }
+}
+
+#[test]
+fn test_generate_deterministic() {
+    let regex = Regex::xpath(r"[a-z]+\d*", "").unwrap();
+    let options = GenerateOptions::default();
+    let first = (0..5)
+        .map(|_| regex.generate(&mut SeededRng::new(7), &options))
+        .collect::<Vec<_>>();
+    assert!(first.iter().all(|generated| *generated == first[0]));
+    let mut rng = SeededRng::new(7);
+    let sequence = (0..5)
+        .map(|_| regex.generate(&mut rng, &options))
+        .collect::<Vec<_>>();
+    let mut rng = SeededRng::new(7);
+    let again = (0..5)
+        .map(|_| regex.generate(&mut rng, &options))
+        .collect::<Vec<_>>();
+    assert_eq!(sequence, again);
+}
+
+#[test]
+fn test_repeat_bias() {
+    let regex = Regex::xpath("a{2,5}b*", "").unwrap();
+    let mut rng = SeededRng::new(1);
+    let options = GenerateOptions {
+        repeat_bias: RepeatBias::Min,
+        ..Default::default()
+    };
+    assert_eq!(regex.generate(&mut rng, &options).as_deref(), Some("aa"));
+    let options = GenerateOptions {
+        repeat_bias: RepeatBias::Max,
+        max_unbounded_repeats: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        regex.generate(&mut rng, &options).as_deref(),
+        Some("aaaaabb")
+    );
+}
+
+#[test]
+fn test_representative_chars() {
+    let regex = Regex::xpath(r"\p{L}{10}", "").unwrap();
+    let mut rng = SeededRng::new(3);
+    let generated = regex
+        .generate(&mut rng, &GenerateOptions::default())
+        .unwrap();
+    assert!(generated.chars().all(|c| c.is_ascii_alphabetic()));
+
+    let regex = Regex::xpath(r"\p{IsCyrillic}", "").unwrap();
+    let generated = regex
+        .generate(&mut rng, &GenerateOptions::default())
+        .unwrap();
+    assert!(('\u{400}'..'\u{410}').contains(&generated.chars().next().unwrap()));
+
+    let options = GenerateOptions {
+        representative_chars: false,
+        ..Default::default()
+    };
+    let regex = Regex::xpath(r".{20}", "").unwrap();
+    let generated = regex.generate(&mut rng, &options).unwrap();
+    assert!(!generated.is_ascii());
+}
+
+#[test]
+fn test_generate_impossible() {
+    let mut rng = SeededRng::new(0);
+    let options = GenerateOptions::default();
+    let regex = Regex::xpath("[a-z-[a-z]]", "").unwrap();
+    assert_eq!(regex.generate(&mut rng, &options), None);
+    let regex = Regex::xpath("a^b", "").unwrap();
+    assert_eq!(regex.generate(&mut rng, &options), None);
+    let regex = Regex::xpath(".*", "s").unwrap();
+    assert_eq!(regex.generate_non_matching(&mut rng, &options), None);
+}
+
+#[test]
+fn test_generate_non_matching() {
+    let mut rng = SeededRng::new(5);
+    for pattern in PATTERNS {
+        let regex = Regex::xpath(pattern, "").unwrap();
+        for _ in 0..20 {
+            let generated = regex
+                .generate_non_matching(&mut rng, &GenerateOptions::default())
+                .unwrap();
+            assert_ne!(
+                regex.partial_match(&generated),
+                PartialMatch::Full,
+                "{} {:?}",
+                pattern,
+                generated
+            );
+        }
+    }
+}
+
+struct Counter(u64);
+
+impl RngLike for Counter {
+    fn next_u64(&mut self) -> u64 {
+        self.0 += 1;
+        self.0
+    }
+}
+
+#[test]
+fn test_own_rng() {
+    let regex = Regex::xpath("[abc]{3}", "").unwrap();
+    let generated = regex.generate(&mut Counter(0), &GenerateOptions::default());
+    assert_eq!(generated.as_deref(), Some("cab"));
+}
+
+#[test]
+fn test_max_length() {
+    let mut rng = SeededRng::new(0);
+    let options = GenerateOptions {
+        max_length: 100,
+        ..Default::default()
+    };
+    // too long to generate, so nothing is allocated for it
+    let regex = Regex::xpath("a{1000000000}", "").unwrap();
+    assert_eq!(regex.generate(&mut rng, &options), None);
+    let regex = Regex::xpath("(?:ab){60}", "").unwrap();
+    assert_eq!(regex.generate(&mut rng, &options), None);
+    let regex = Regex::xpath("(a)\\1{200}", "").unwrap();
+    assert_eq!(regex.generate(&mut rng, &options), None);
+    let regex = Regex::xpath("(?:ab){50}", "").unwrap();
+    assert_eq!(
+        regex.generate(&mut rng, &options).map(|s| s.len()),
+        Some(100)
+    );
+
+    // repetitions are cut short to fit
+    let options = GenerateOptions {
+        repeat_bias: RepeatBias::Max,
+        max_length: 100,
+        ..Default::default()
+    };
+    let regex = Regex::xpath("x(?:ab){0,1000000000}", "").unwrap();
+    assert_eq!(
+        regex.generate(&mut rng, &options).map(|s| s.len()),
+        Some(99)
+    );
+    let regex = Regex::xpath("a{0,1000000000}", "").unwrap();
+    assert_eq!(
+        regex.generate(&mut rng, &options).map(|s| s.len()),
+        Some(100)
+    );
+}
@@ -0,0 +1,85 @@
+use regexml::{Chunked, Haystack, Regex};
+
+#[test]
+fn test_chunked() {
+    let haystack = Chunked::new(["ab", "", "cé", "d"]);
+    assert_eq!(haystack.len(), 5);
+    assert_eq!(haystack.char_at(0), Some('a'));
+    assert_eq!(haystack.char_at(2), Some('c'));
+    assert_eq!(haystack.char_at(3), Some('é'));
+    assert_eq!(haystack.char_at(5), None);
+    assert_eq!(haystack.byte_offset(4), 5);
+    assert_eq!(haystack.byte_offset(5), 6);
+
+    let empty = Chunked::new(Vec::<&str>::new());
+    assert!(empty.is_empty());
+    assert_eq!(empty.char_at(0), None);
+}
+
+#[test]
+fn test_match_across_chunks() {
+    let haystack = Chunked::new(["xa", "bb", "by"]);
+    let regex = Regex::xpath("ab+", "").unwrap();
+    assert!(regex.is_match_in(&haystack));
+    assert_eq!(regex.find_in(&haystack, 0), Some(1..5));
+    assert_eq!(regex.find_in(&haystack, 2), None);
+
+    let regex = Regex::xpath(r"(b)\1", "").unwrap();
+    assert_eq!(regex.find_in(&haystack, 0), Some(2..4));
+}
+
+#[test]
+fn test_anchors() {
+    let haystack = Chunked::new(["one\n", "two"]);
+    let regex = Regex::xpath("^two$", "").unwrap();
+    assert!(!regex.is_match_in(&haystack));
+    let regex = Regex::xpath("^two$", "m").unwrap();
+    assert_eq!(regex.find_in(&haystack, 0), Some(4..7));
+}
+
+#[test]
+fn test_str_and_chars() {
+    let regex = Regex::xpath("é.", "i").unwrap();
+    let text = "aÉb";
+    assert_eq!(regex.find_in(text, 0), Some(1..3));
+    assert_eq!(text.byte_offset(1), 1);
+    assert_eq!(text.byte_offset(3), 4);
+
+    let chars = text.chars().collect::<Vec<_>>();
+    assert_eq!(regex.find_in(chars.as_slice(), 0), Some(1..3));
+    assert_eq!(chars.as_slice().byte_offset(2), 3);
+    assert_eq!(regex.find_in(chars.as_slice(), 4), None);
+}
+
+#[test]
+fn test_same_as_string() {
+    let regex = Regex::xpath(r"(\w+)\s(\w+)", "").unwrap();
+    let text = "hello big world";
+    let haystack = Chunked::new(["hel", "lo b", "ig", " world"]);
+    assert_eq!(regex.is_match(text), regex.is_match_in(&haystack));
+    assert_eq!(regex.find_in(text, 0), regex.find_in(&haystack, 0));
+    assert_eq!(regex.find_in(text, 1), regex.find_in(&haystack, 1));
+}
+
+#[test]
+fn test_chunked_out_of_order() {
+    let haystack = Chunked::new(["aé", "", "bΣc", "d"]);
+    let text = "aébΣcd";
+    for position in [5, 0, 4, 3, 1, 2, 6, 2, 5, 0] {
+        assert_eq!(haystack.char_at(position), text.chars().nth(position));
+        assert_eq!(haystack.byte_offset(position), text.byte_offset(position));
+    }
+}
+
+#[test]
+fn test_long_text() {
+    // reading a string or chunks one position after the other must not
+    // scan them from the start each time
+    let text = "é".repeat(200_000) + "x";
+    let regex = Regex::xpath("é*x", "").unwrap();
+    assert_eq!(regex.find_in(text.as_str(), 0), Some(0..200_001));
+    let haystack = Chunked::new(text.split_inclusive('é'));
+    assert_eq!(regex.find_in(&haystack, 0), Some(0..200_001));
+    let haystack = Chunked::new([text.as_str()]);
+    assert_eq!(regex.find_in(&haystack, 100_000), Some(100_000..200_001));
+}
@@ -0,0 +1,50 @@
+use regexml::{Language, Regex};
+
+#[test]
+fn test_pattern_flags_language() {
+    let regex = Regex::xpath(" a b ", "xmix;k").unwrap();
+    assert_eq!(regex.pattern(), " a b ");
+    assert_eq!(regex.flags(), "mix;k");
+    assert_eq!(regex.language(), Language::XPath);
+
+    let regex = Regex::xsd("a", "").unwrap();
+    assert_eq!(regex.flags(), "");
+    assert_eq!(regex.language(), Language::XSD);
+}
+
+#[test]
+fn test_capture_count() {
+    assert_eq!(Regex::xpath("abc", "").unwrap().capture_count(), 0);
+    assert_eq!(
+        Regex::xpath("(a)(?:b)((c)d)", "").unwrap().capture_count(),
+        3
+    );
+    // parentheses are literal with q
+    assert_eq!(Regex::xpath("(a)", "q").unwrap().capture_count(), 0);
+}
+
+#[test]
+fn test_lengths() {
+    let regex = Regex::xpath("ab[cd]", "").unwrap();
+    assert_eq!(regex.minimum_length(), 3);
+    assert_eq!(regex.fixed_length(), Some(3));
+
+    let regex = Regex::xpath("a(bc)?d+", "").unwrap();
+    assert_eq!(regex.minimum_length(), 2);
+    assert_eq!(regex.fixed_length(), None);
+
+    let regex = Regex::xpath("a{3}|bcd", "").unwrap();
+    assert_eq!(regex.fixed_length(), Some(3));
+}
+
+#[test]
+fn test_matches_empty_string() {
+    assert!(Regex::xpath("a*", "").unwrap().matches_empty_string());
+    assert!(!Regex::xpath("a+", "").unwrap().matches_empty_string());
+}
+
+#[test]
+fn test_has_back_references() {
+    assert!(Regex::xpath(r"(a)\1", "").unwrap().has_back_references());
+    assert!(!Regex::xpath(r"(a)1", "").unwrap().has_back_references());
+}
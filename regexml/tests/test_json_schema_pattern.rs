@@ -0,0 +1,92 @@
+// these test cases are a transliteration of test cases in pattern.json and
+// optional/ecmascript-regex.json, part of the JSON Schema test suite
+
+use regexml::Regex;
+
+fn valid(pattern: &str, s: &str) -> bool {
+    Regex::ecmascript(pattern, "").unwrap().is_match(s)
+}
+
+#[test]
+fn test_pattern_validation() {
+    assert!(valid("^a*$", "aaa"));
+    assert!(!valid("^a*$", "abc"));
+}
+
+#[test]
+fn test_pattern_is_not_anchored() {
+    assert!(valid("a+", "xxaayy"));
+}
+
+#[test]
+fn test_dollar_does_not_match_final_newline() {
+    assert!(valid("^abc$", "abc"));
+    assert!(!valid("^abc$", "abc\n"));
+}
+
+#[test]
+fn test_tab_escape() {
+    assert!(valid(r"^\t$", "\t"));
+    assert!(!valid(r"^\t$", "\\t"));
+    assert!(!valid(r"^\t$", "\u{0009}\u{0009}"));
+}
+
+#[test]
+fn test_control_escape() {
+    assert!(valid(r"^\cC$", "\u{0003}"));
+    assert!(!valid(r"^\cC$", "\\cC"));
+    assert!(valid(r"^\cc$", "\u{0003}"));
+    assert!(!valid(r"^\cc$", "\\cc"));
+}
+
+#[test]
+fn test_digit_is_ascii() {
+    assert!(valid(r"^\d$", "0"));
+    // NKO DIGIT ZERO
+    assert!(!valid(r"^\d$", "\u{07C0}"));
+    assert!(valid(r"^\D$", "\u{07C0}"));
+    assert!(!valid(r"^\D$", "0"));
+}
+
+#[test]
+fn test_word_char_is_ascii() {
+    assert!(valid(r"^\w$", "a"));
+    assert!(!valid(r"^\w$", "é"));
+    assert!(valid(r"^\W$", "é"));
+    assert!(!valid(r"^\W$", "a"));
+}
+
+#[test]
+fn test_space_is_unicode() {
+    for s in [
+        " ", "\t", "\u{000B}", "\u{000C}", "\u{00A0}", "\u{FEFF}", "\n", "\u{2029}", "\u{2003}",
+    ] {
+        assert!(valid(r"^\s$", s), "{:?}", s);
+        assert!(!valid(r"^\S$", s), "{:?}", s);
+    }
+    for s in ["\u{0001}", "\u{2013}"] {
+        assert!(!valid(r"^\s$", s), "{:?}", s);
+        assert!(valid(r"^\S$", s), "{:?}", s);
+    }
+}
+
+#[test]
+fn test_unicode_semantics() {
+    assert!(valid(r"\p{Letter}cole", "l'école"));
+    assert!(valid(r"\p{Letter}cole", "l'ecole"));
+    assert!(!valid(r"\wcole", "l'école"));
+    assert!(valid(r"\wcole", "l'ecole"));
+    assert!(!valid(r"[a-z]cole", "l'école"));
+    assert!(valid(r"[a-z]cole", "l'ecole"));
+}
+
+#[test]
+fn test_digits() {
+    assert!(valid(r"^\d+$", "42"));
+    assert!(!valid(r"^\d+$", "-%#"));
+    // BENGALI DIGIT FOUR, BENGALI DIGIT TWO
+    assert!(!valid(r"^\d+$", "৪২"));
+    assert!(valid(r"^\p{digit}+$", "42"));
+    assert!(valid(r"^\p{digit}+$", "৪২"));
+    assert!(!valid(r"^\p{digit}+$", "-%#"));
+}
@@ -0,0 +1,144 @@
+use regexml::{
+    CompileLimits, Error, Language, Regex, Span, Warning, WarningKind, MAX_NESTING_DEPTH,
+};
+
+fn compile(pattern: &str, limits: &CompileLimits) -> Result<Regex, Error> {
+    Regex::with_limits(pattern, "", Language::XPath, limits).map(|(regex, _warnings)| regex)
+}
+
+fn exceeds(pattern: &str, limits: &CompileLimits) -> bool {
+    matches!(compile(pattern, limits), Err(Error::LimitExceeded(_)))
+}
+
+#[test]
+fn test_default_limits_allow_ordinary_patterns() {
+    let limits = CompileLimits::default();
+    let regex = compile(r"^(\d{4})-(\d{2})-(\d{2})$", &limits).unwrap();
+    assert!(regex.is_match("2024-01-31"));
+}
+
+#[test]
+fn test_pattern_length() {
+    let limits = CompileLimits {
+        max_pattern_length: 5,
+        ..CompileLimits::default()
+    };
+    assert!(compile("abcde", &limits).is_ok());
+    assert!(exceeds("abcdef", &limits));
+    // literal patterns count too
+    assert!(matches!(
+        Regex::with_limits("abcdef", "q", Language::XPath, &limits),
+        Err(Error::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn test_nesting_depth_without_limits() {
+    // too deep to compile without overflowing the stack
+    let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(Regex::xpath(&nested(MAX_NESTING_DEPTH), "").is_ok());
+    assert!(matches!(
+        Regex::xpath(&nested(MAX_NESTING_DEPTH + 1), ""),
+        Err(Error::LimitExceeded(_))
+    ));
+    assert!(matches!(
+        Regex::xpath(&nested(10_000), ""),
+        Err(Error::LimitExceeded(_))
+    ));
+    let subtractions = format!("{}{}", "[a-".repeat(1_000), "]".repeat(1_000));
+    assert!(matches!(
+        Regex::xsd(&subtractions, ""),
+        Err(Error::LimitExceeded(_))
+    ));
+    let limits = CompileLimits {
+        max_nesting_depth: usize::MAX,
+        ..CompileLimits::unlimited()
+    };
+    assert!(exceeds(&nested(10_000), &limits));
+}
+
+#[test]
+fn test_nesting_depth() {
+    let limits = CompileLimits::default();
+    let deep = format!("{}a{}", "(".repeat(10_000), ")".repeat(10_000));
+    let limits = CompileLimits {
+        max_pattern_length: usize::MAX,
+        ..limits
+    };
+    assert!(exceeds(&deep, &limits));
+
+    let limits = CompileLimits {
+        max_nesting_depth: 2,
+        ..CompileLimits::default()
+    };
+    assert!(compile("((a))", &limits).is_ok());
+    assert!(compile("(a)(b)(c)", &limits).is_ok());
+    assert!(exceeds("(((a)))", &limits));
+    assert!(exceeds("(?:(?:(?:a)))", &limits));
+}
+
+#[test]
+fn test_repetition() {
+    let limits = CompileLimits::default();
+    assert!(exceeds("x{4000000000}", &limits));
+    assert!(exceeds("x{1,20000}", &limits));
+    assert!(compile("x{1,10000}", &limits).is_ok());
+}
+
+#[test]
+fn test_warnings() {
+    let (regex, warnings) = Regex::with_limits(
+        r"[\p{IsGreek}]",
+        "",
+        Language::XPath,
+        &CompileLimits::default(),
+    )
+    .unwrap();
+    assert!(regex.is_match("\u{3B1}"));
+    assert_eq!(
+        warnings,
+        vec![Warning {
+            kind: WarningKind::DeprecatedBlock("Greek".to_string(), "GreekandCoptic".to_string()),
+            span: Span::new(1, 12),
+        }]
+    );
+}
+
+#[test]
+fn test_nested_repetition() {
+    let limits = CompileLimits::default();
+    assert!(exceeds("((a{100}){100}){100}", &limits));
+    assert!(exceeds("(?:a{2,}){10000}", &limits));
+    assert!(exceeds("((a{5000}){3})+", &limits));
+    assert!(exceeds("(a{5000}){2,}", &limits));
+    assert!(compile("(a{100}){100}", &limits).is_ok());
+    assert!(compile("(a{100})*b{100}", &limits).is_ok());
+    assert!(compile("(a{3333}){2,}", &limits).is_ok());
+}
+
+#[test]
+fn test_capture_groups() {
+    let limits = CompileLimits {
+        max_capture_groups: 2,
+        ..CompileLimits::default()
+    };
+    assert!(compile("(a)(b)(?:c)", &limits).is_ok());
+    assert!(exceeds("(a)(b)(c)", &limits));
+}
+
+#[test]
+fn test_program_size() {
+    let limits = CompileLimits {
+        max_program_size: 10,
+        ..CompileLimits::default()
+    };
+    assert!(compile("abc", &limits).is_ok());
+    assert!(exceeds("a|b|c|d|e|f|g|h|i|j|k", &limits));
+}
+
+#[test]
+fn test_unlimited() {
+    let pattern = format!("{}a{}", "(".repeat(100), ")".repeat(100));
+    assert!(compile(&pattern, &CompileLimits::unlimited()).is_ok());
+    assert!(Regex::xpath(&pattern, "").is_ok());
+}
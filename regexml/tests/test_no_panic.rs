@@ -0,0 +1,259 @@
+// Compiling and matching must never panic, whatever the pattern, flags or
+// input. These tests feed generated patterns through the public API; the
+// generator is seeded, so failures can be reproduced.
+
+use regexml::{ast, AnalyzeEntry, Chunked, CompileLimits, Dialect, Language, MatchEntry, Regex};
+
+// A small linear congruential generator, so the tests are deterministic and
+// don't need a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+const PATTERN_PIECES: &[&str] = &[
+    "a",
+    "b",
+    "ab",
+    ".",
+    "^",
+    "$",
+    "(",
+    ")",
+    "(?:",
+    "[",
+    "]",
+    "[^",
+    "-",
+    "-[",
+    "|",
+    "*",
+    "+",
+    "?",
+    "*?",
+    "+?",
+    "??",
+    "{",
+    "}",
+    "{0}",
+    "{2}",
+    "{1,}",
+    "{0,3}",
+    "{3,1}",
+    ",",
+    "\\",
+    "\\1",
+    "\\2",
+    "\\10",
+    "\\d",
+    "\\w",
+    "\\s",
+    "\\i",
+    "\\c",
+    "\\p{L}",
+    "\\P{Nd}",
+    "\\p{IsBasicLatin}",
+    "\\p{",
+    "\\n",
+    "\\t",
+    "\\.",
+    "\\-",
+    "\\^",
+    " ",
+    "#",
+    "é",
+    "Σ",
+    "\u{10000}",
+    "\r",
+    "\n",
+    "(a)",
+    "(b*)",
+    "(?:a|)",
+    "[a-z]",
+    "[a-[b]]",
+    "{99999999999999999999}",
+    "{6148914691236517206}",
+    "{9223372036854775808}",
+    ".{9223372036854775808,9223372036854775808}",
+    "(?=",
+    "(?!",
+    "\\b",
+    "\\B",
+    "\\u{41}",
+    "\\cA",
+];
+
+const FLAGS: &[&str] = &[
+    "", "i", "m", "s", "x", "q", "j", "k", "imsx", "q;j", ";k", "z",
+];
+
+const HAYSTACKS: &[&str] = &[
+    "",
+    "a",
+    "ab",
+    "aab\nb",
+    "\n",
+    "xyz",
+    "AbAbA",
+    "éΣ\u{10000}",
+    "a-b c\r\nd",
+];
+
+const LANGUAGES: &[Language] = &[
+    Language::XSD,
+    Language::XPath,
+    Language::XPath40,
+    Language::EcmaScript,
+    Language::FullTextWildcards,
+];
+
+const REPLACEMENTS: &[&str] = &["", "x", "$0", "$1", "$12", "\\$", "\\\\", "$", "\\", "\\x"];
+
+fn pattern(rng: &mut Rng) -> String {
+    let len = rng.below(10);
+    (0..len).map(|_| rng.pick(PATTERN_PIECES)).collect()
+}
+
+fn exercise(pattern: &str, flags: &str, language: Language, rng: &mut Rng) {
+    if let Ok(expr) = ast::parse(pattern, flags, language) {
+        let _ = expr.to_pattern(Language::XPath);
+        let _ = expr.to_canonical_pattern(Language::XSD);
+    }
+    let _ = ast::explain(pattern, flags, language);
+    let regex = match Regex::compile_with_diagnostics(pattern, flags, language) {
+        Ok((regex, _)) => regex,
+        Err(_) => return,
+    };
+    let haystack = rng.pick(HAYSTACKS);
+    let replacement = rng.pick(REPLACEMENTS);
+    let _ = regex.is_match(haystack);
+    let _ = regex.diagnose(haystack);
+    let start = rng.below(haystack.len() + 2);
+    let _ = regex.find_at(haystack, start);
+    let _ = regex.match_prefix(haystack, start);
+    let chunks = Chunked::new(haystack.split_inclusive('b'));
+    let _ = regex.find_in(&chunks, start);
+    let _ = regex.replace_all(haystack, replacement);
+    if let Ok(tokens) = regex.tokenize(haystack) {
+        let _ = tokens.take(100).count();
+    }
+    if let Ok(entries) = regex.analyze(haystack) {
+        let _ = entries.take(100).count();
+    }
+    let _ = regex.program_dump();
+    let _ = regex.to_dot();
+    let _ = regex.analyze_complexity();
+    for dialect in [Dialect::EcmaScript, Dialect::Pcre, Dialect::Rust] {
+        let _ = regex.translate(dialect);
+    }
+}
+
+fn check(pattern: &str, flags: &str, language: Language, rng: &mut Rng) {
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        exercise(pattern, flags, language, rng)
+    }));
+    assert!(
+        result.is_ok(),
+        "panicked on pattern {:?} with flags {:?} in {:?}",
+        pattern,
+        flags,
+        language
+    );
+}
+
+#[test]
+fn test_generated_patterns() {
+    let mut rng = Rng(0x5eed);
+    for _ in 0..5_000 {
+        let pattern = pattern(&mut rng);
+        let flags = rng.pick(FLAGS);
+        let language = LANGUAGES[rng.below(LANGUAGES.len())];
+        check(&pattern, flags, language, &mut rng);
+    }
+}
+
+#[test]
+fn test_known_problems() {
+    let mut rng = Rng(1);
+    for pattern in [
+        "(",
+        "(?",
+        ")",
+        "[",
+        "\\",
+        "a{",
+        "a{1",
+        "a{1,",
+        "a{99999999999999999999}",
+        "a{4000000000}{4000000000}",
+        "(?:abc){6148914691236517206}",
+        "a{9223372036854775808}b{9223372036854775808}",
+        "x.{9223372036854775808,9223372036854775808}.{9223372036854775808,9223372036854775808}",
+        "(a)\\2",
+        "(?:)",
+        "()",
+        "[]",
+        "[a-]",
+        "[-a]",
+        "[a-[",
+        "\\p{Is}",
+        "^*",
+        "$+",
+        "(^)*",
+        "(a|)*b",
+        "((a)|b)*\\2",
+    ] {
+        for flags in FLAGS {
+            for language in LANGUAGES {
+                check(pattern, flags, *language, &mut rng);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_limits_checked() {
+    let mut rng = Rng(2);
+    let limits = CompileLimits::default();
+    for _ in 0..1_000 {
+        let pattern = pattern(&mut rng);
+        let result = std::panic::catch_unwind(|| {
+            let _ = Regex::with_limits(&pattern, "", Language::XPath, &limits);
+        });
+        assert!(result.is_ok(), "panicked on pattern {:?}", pattern);
+    }
+}
+
+#[test]
+fn test_analyze_empty_group() {
+    let regex = Regex::xpath("a(b?)", "").unwrap();
+    let entries = regex.analyze("xa").unwrap().collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        vec![
+            AnalyzeEntry::NonMatch("x".to_string()),
+            AnalyzeEntry::Match(vec![
+                MatchEntry::String("a".to_string()),
+                MatchEntry::Group {
+                    nr: 1,
+                    value: vec![]
+                }
+            ])
+        ]
+    );
+}
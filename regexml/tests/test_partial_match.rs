@@ -0,0 +1,64 @@
+use regexml::{PartialMatch, Regex};
+
+fn partial(pattern: &str, s: &str) -> PartialMatch {
+    Regex::xsd(pattern, "").unwrap().partial_match(s)
+}
+
+#[test]
+fn test_partial_match() {
+    assert_eq!(partial(r"\d{3}-\d{4}", "555-1234"), PartialMatch::Full);
+    assert_eq!(partial(r"\d{3}-\d{4}", ""), PartialMatch::Prefix);
+    assert_eq!(partial(r"\d{3}-\d{4}", "55"), PartialMatch::Prefix);
+    assert_eq!(partial(r"\d{3}-\d{4}", "555-"), PartialMatch::Prefix);
+    assert_eq!(partial(r"\d{3}-\d{4}", "5a"), PartialMatch::Impossible);
+    assert_eq!(
+        partial(r"\d{3}-\d{4}", "555-12345"),
+        PartialMatch::Impossible
+    );
+    assert_eq!(
+        partial(r"\d{3}-\d{4}", "x555-1234"),
+        PartialMatch::Impossible
+    );
+}
+
+#[test]
+fn test_partial_match_repeat_and_choice() {
+    assert_eq!(partial("a+b", "aaa"), PartialMatch::Prefix);
+    assert_eq!(partial("a+b", "aab"), PartialMatch::Full);
+    assert_eq!(partial("a+b", "aabb"), PartialMatch::Impossible);
+    assert_eq!(partial("(ab)+", "aba"), PartialMatch::Prefix);
+    assert_eq!(partial("(ab)+", "abab"), PartialMatch::Full);
+    assert_eq!(partial("cat|dog", "do"), PartialMatch::Prefix);
+    assert_eq!(partial("cat|dog", "cow"), PartialMatch::Impossible);
+    assert_eq!(partial("yes|yesterday", "yes"), PartialMatch::Full);
+    assert_eq!(partial("abc", "ABC"), PartialMatch::Impossible);
+    let regex = Regex::xpath("abc", "i").unwrap();
+    assert_eq!(regex.partial_match("AB"), PartialMatch::Prefix);
+}
+
+#[test]
+fn test_partial_match_back_reference() {
+    let regex = Regex::xpath(r"(a+)-\1", "").unwrap();
+    assert_eq!(regex.partial_match("aa-a"), PartialMatch::Prefix);
+    assert_eq!(regex.partial_match("aa-aa"), PartialMatch::Full);
+    assert_eq!(regex.partial_match("aa-b"), PartialMatch::Impossible);
+}
+
+#[test]
+fn test_hit_end() {
+    let regex = Regex::xpath("a+", "").unwrap();
+    let found = regex.find_at("baa", 0).unwrap();
+    assert_eq!(found.as_str(), "aa");
+    assert!(found.hit_end());
+    let found = regex.find_at("baab", 0).unwrap();
+    assert!(!found.hit_end());
+
+    let regex = Regex::xpath("a+?", "").unwrap();
+    assert!(!regex.find_at("baa", 0).unwrap().hit_end());
+
+    let regex = Regex::xpath("ab$", "").unwrap();
+    assert!(regex.find_at("ab", 0).unwrap().hit_end());
+
+    let regex = Regex::xpath("a", "").unwrap();
+    assert!(!regex.match_prefix("a", 0).unwrap().hit_end());
+}
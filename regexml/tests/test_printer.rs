@@ -0,0 +1,135 @@
+use regexml::ast::parse;
+use regexml::{Error, Language};
+
+fn print(pattern: &str, flags: &str, from: Language, to: Language) -> Result<String, Error> {
+    parse(pattern, flags, from).unwrap().to_pattern(to)
+}
+
+fn canonical(pattern: &str, flags: &str, from: Language, to: Language) -> Result<String, Error> {
+    parse(pattern, flags, from)
+        .unwrap()
+        .to_canonical_pattern(to)
+}
+
+const PATTERNS: &[&str] = &[
+    r"abc",
+    r"a*b+?c{2,3}d{4,}e{5}",
+    r"(a|b|)(?:c)\1",
+    r"[^a-z\d_-[aeiou]]",
+    r"[-a][a-][\-\[\]\\^]",
+    r"\p{Lu}\P{IsBasicLatin}\s\S\i\I\c\C\d\D\w\W",
+    r"^\^\$$.\.",
+    r"\n\r\t\|\?\*\+\{\}\(\)",
+];
+
+#[test]
+fn test_round_trip() {
+    for pattern in PATTERNS {
+        assert_eq!(
+            print(pattern, "", Language::XPath, Language::XPath).unwrap(),
+            *pattern
+        );
+    }
+}
+
+#[test]
+fn test_canonical_is_stable() {
+    for pattern in PATTERNS {
+        let once = canonical(pattern, "", Language::XPath, Language::XPath).unwrap();
+        let twice = canonical(&once, "", Language::XPath, Language::XPath).unwrap();
+        assert_eq!(once, twice, "{}", pattern);
+    }
+}
+
+#[test]
+fn test_canonical_quantifiers() {
+    assert_eq!(
+        canonical(
+            "a{0,}b{1,}c{0,1}d{1}e{1,1}?f{2,2}",
+            "",
+            Language::XPath,
+            Language::XPath
+        )
+        .unwrap(),
+        "a*b+c?def{2}"
+    );
+}
+
+#[test]
+fn test_canonical_escapes() {
+    assert_eq!(
+        canonical(r"\-a\n[-\^x-]", "", Language::XSD, Language::XSD).unwrap(),
+        r"-a\n[\-^x\-]"
+    );
+}
+
+#[test]
+fn test_canonical_whitespace() {
+    assert_eq!(
+        canonical("a b  (c | d)*", "x", Language::XPath, Language::XPath).unwrap(),
+        "ab(c|d)*"
+    );
+    assert_eq!(
+        canonical("[a b]", "x", Language::XPath, Language::XPath).unwrap(),
+        "[a b]"
+    );
+}
+
+#[test]
+fn test_literal_flag() {
+    assert_eq!(
+        canonical("a.b*", "q", Language::XPath, Language::XPath).unwrap(),
+        r"a\.b\*"
+    );
+}
+
+#[test]
+fn test_xsd_to_xpath() {
+    assert_eq!(
+        print("^a$", "", Language::XSD, Language::XPath).unwrap(),
+        r"\^a\$"
+    );
+}
+
+#[test]
+fn test_xpath_to_xsd() {
+    assert_eq!(
+        print(r"\$(?:a)", "", Language::XPath, Language::XSD).unwrap(),
+        "$(a)"
+    );
+    assert!(matches!(
+        print("^a", "", Language::XPath, Language::XSD),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(
+        print("a*?", "", Language::XPath, Language::XSD),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(
+        print(r"(a)\1", "", Language::XPath, Language::XSD),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_back_reference_followed_by_digit() {
+    let pattern = "(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\\1[0]";
+    let expr = parse(pattern, "", Language::XPath).unwrap();
+    // turn the class into a plain literal digit
+    let mut expr = expr;
+    let pieces = &mut expr.branches[0].pieces;
+    let regexml::ast::Atom::CharClass(class) = &pieces[11].atom else {
+        panic!("expected character class");
+    };
+    let regexml::ast::ClassItem::Char(zero) = &class.items[0] else {
+        panic!("expected character");
+    };
+    pieces[11].atom = regexml::ast::Atom::Literal(zero.clone());
+    let printed = expr.to_pattern(Language::XPath).unwrap();
+    assert_eq!(printed, "(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)(?:\\1)0");
+    let reparsed = parse(&printed, "", Language::XPath).unwrap();
+    assert!(matches!(
+        reparsed.branches[0].pieces[10].atom,
+        regexml::ast::Atom::Group(_)
+    ));
+}
@@ -0,0 +1,63 @@
+use regexml::Regex;
+
+fn dump(pattern: &str) -> String {
+    Regex::xpath(pattern, "").unwrap().program_dump()
+}
+
+#[test]
+fn test_dump() {
+    assert_eq!(
+        dump("x[a-z]+?y"),
+        r#"pattern: "x[a-z]+?y"
+minimum length: 3
+prefix: "x"
+initial character class: none
+optimizations: none
+preconditions:
+  from 0: Atom "x"
+  from 1: ReluctantFixed {1,}? length 1
+    CharClass [a-z]
+  from 2: Atom "y"
+operations:
+  Sequence
+    Atom "x"
+    ReluctantFixed {1,}? length 1
+      CharClass [a-z]
+    Atom "y"
+    EndProgram
+"#
+    );
+}
+
+#[test]
+fn test_repeat_strategies() {
+    // 'a' can't follow itself ambiguously into 'b', so no backtracking
+    assert!(dump("a*b").contains("\n    UnambiguousRepeat {0,}\n      Atom \"a\"\n"));
+    // the repeat overlaps with what follows, so it has to backtrack
+    assert!(dump("a*ab").contains("\n    GreedyFixed {0,} length 1\n"));
+    assert!(dump("a*?ab").contains("\n    ReluctantFixed {0,}? length 1\n"));
+    // the repeated unit has no fixed length
+    assert!(dump("(a|bc)*a").contains("\n    Repeat {0,}\n      Capture 1\n"));
+}
+
+#[test]
+fn test_optimizations() {
+    let bol = dump("^ab{2,3}");
+    assert!(bol.contains("prefix: none\n"));
+    assert!(bol.contains("optimizations: starts with ^\n"));
+    assert!(bol.contains("  at 1: Repeat {2,2}\n    Atom \"b\"\n"));
+    let class = dump("[0-9]x");
+    assert!(class.contains("initial character class: [0-9]\n"));
+    assert!(class.contains("minimum length: 2\n"));
+    assert!(dump(r"(a)\1").contains("optimizations: has back-references\n"));
+}
+
+#[test]
+fn test_equivalent_patterns() {
+    let star = dump("a*b");
+    let braces = dump("a{0,}b");
+    assert_eq!(
+        star.lines().skip(1).collect::<Vec<_>>(),
+        braces.lines().skip(1).collect::<Vec<_>>()
+    );
+}
@@ -211,6 +211,21 @@ fn test_caseless_match_character_classes() {
     assert!(regex.is_match("a"));
 }
 
+#[test]
+fn test_caseless_match_character_before_hyphen() {
+    // as in the Java original, a character followed by a hyphen that doesn't
+    // start a range doesn't match its other cases
+    let regex = Regex::xpath("^[a-]$", "i").unwrap();
+    assert!(regex.is_match("a"));
+    assert!(regex.is_match("-"));
+    assert!(!regex.is_match("A"));
+    let regex = Regex::xpath("^[ac-[b]]$", "i").unwrap();
+    assert!(regex.is_match("A"));
+    assert!(!regex.is_match("C"));
+    let regex = Regex::xpath("^[-a]$", "i").unwrap();
+    assert!(regex.is_match("A"));
+}
+
 #[test]
 fn test_matches_multiline() {
     let regex = Regex::xpath("^$", "m").unwrap();
@@ -0,0 +1,155 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use regexml::{OperationKind, PrintTracer, Regex, TracedOperation, Tracer};
+
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+}
+
+impl Tracer for Recorder {
+    fn attempt(&mut self, position: usize) {
+        self.events.push(format!("attempt {}", position));
+    }
+
+    fn enter(&mut self, operation: &TracedOperation, position: usize) {
+        if operation.kind == OperationKind::Atom {
+            self.events
+                .push(format!("enter {} {}", operation.description, position));
+        }
+    }
+
+    fn success(&mut self, operation: &TracedOperation, start: usize, end: usize) {
+        if operation.kind == OperationKind::Repeat {
+            self.events.push(format!("success {}..{}", start, end));
+        }
+    }
+
+    fn backtrack(&mut self, operation: &TracedOperation, start: usize) {
+        if operation.kind == OperationKind::Repeat {
+            self.events.push(format!("backtrack {}", start));
+        }
+    }
+}
+
+#[test]
+fn test_print_tracer() {
+    let regex = Regex::xpath("ab", "").unwrap();
+    let mut tracer = PrintTracer::new(Vec::new());
+    assert!(regex.is_match_traced("ab", &mut tracer));
+    let log = String::from_utf8(tracer.into_inner()).unwrap();
+    assert_eq!(
+        log,
+        "\
+attempt at 0
+  Sequence 2 operations at 0
+    Atom \"ab\" at 0
+    Atom \"ab\" matched 0..2
+    EndProgram at 2
+    EndProgram matched 2..2
+  Sequence 2 operations matched 0..2
+"
+    );
+}
+
+#[test]
+fn test_print_tracer_nesting() {
+    let regex = Regex::xpath("a(b|c)d", "").unwrap();
+    let mut tracer = PrintTracer::new(Vec::new());
+    assert!(regex.is_match_traced("acd", &mut tracer));
+    let log = String::from_utf8(tracer.into_inner()).unwrap();
+    assert!(log.contains(
+        "      Choice 2 branches at 1
+        Atom \"b\" at 1
+        Atom \"b\" failed at 1
+        Atom \"c\" at 1
+        Atom \"c\" matched 1..2
+"
+    ));
+}
+
+#[test]
+fn test_backtrack() {
+    // the greedy repeat takes both 'a's first, then has to give one back
+    let regex = Regex::xpath("a*ab", "").unwrap();
+    let mut tracer = Recorder::default();
+    assert!(regex.is_match_traced("aab", &mut tracer));
+    assert_eq!(
+        tracer.events,
+        vec![
+            "attempt 0",
+            "enter \"a\" 0",
+            "enter \"a\" 1",
+            "enter \"a\" 2",
+            "success 0..2",
+            "enter \"ab\" 2",
+            "backtrack 0",
+            "success 0..1",
+            "enter \"ab\" 1",
+        ]
+    );
+}
+
+#[test]
+fn test_positions_tried() {
+    let regex = Regex::xpath("b", "").unwrap();
+    let mut tracer = Recorder::default();
+    assert!(!regex.is_match_traced("aaa", &mut tracer));
+    assert!(tracer
+        .events
+        .iter()
+        .all(|event| !event.starts_with("enter")));
+    let regex = Regex::xpath("x?b", "").unwrap();
+    let mut tracer = Recorder::default();
+    assert!(regex.is_match_traced("ab", &mut tracer));
+    assert_eq!(tracer.events[0], "attempt 0");
+    assert!(tracer.events.contains(&"attempt 1".to_string()));
+}
+
+// a writer that can still be read after it's handed to a regex
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_debug_flag() {
+    // the debug flag logs matching, but doesn't change the outcome
+    let out = SharedBuffer::default();
+    let regex = Regex::xpath("a(b|c)*d", ";g")
+        .unwrap()
+        .with_debug_output(out.clone());
+    assert!(regex.is_match("xabcd"));
+    let log = out.contents();
+    // the literal prefix skips the attempt at 0
+    assert!(log.starts_with("attempt at 1\n"));
+    assert!(log.contains("Atom \"d\" matched 4..5"));
+    assert!(!regex.is_match("xabce"));
+    assert!(out.contents().len() > log.len());
+    assert_eq!(regex.replace_all("abd acd", "x").unwrap(), "x x");
+}
+
+#[test]
+fn test_debug_output_without_debug_flag() {
+    let out = SharedBuffer::default();
+    let regex = Regex::xpath("a(b|c)*d", "")
+        .unwrap()
+        .with_debug_output(out.clone());
+    assert!(regex.is_match("xabcd"));
+    assert_eq!(out.contents(), "");
+}
@@ -0,0 +1,150 @@
+use regexml::{Dialect, Error, Regex, Translation};
+
+fn translate(regex: &Regex, dialect: Dialect) -> Translation {
+    regex.translate(dialect).unwrap()
+}
+
+#[test]
+fn test_xsd_anchoring() {
+    let regex = Regex::xsd(r"\d{3}-[a-z-[aeiou]]+", "").unwrap();
+    let translation = translate(&regex, Dialect::EcmaScript);
+    assert_eq!(translation.pattern, r"^(?:\p{Nd}{3}-[b-df-hj-np-tv-z]+)$");
+    assert_eq!(translation.flags, "u");
+    let translation = translate(&regex, Dialect::Pcre);
+    assert_eq!(translation.pattern, r"\A(?:\p{Nd}{3}-[b-df-hj-np-tv-z]+)\z");
+    assert_eq!(translation.flags, "");
+    let translation = translate(&regex, Dialect::Rust);
+    assert_eq!(translation.pattern, r"\A(?:\p{Nd}{3}-[b-df-hj-np-tv-z]+)\z");
+}
+
+#[test]
+fn test_xpath_anchors() {
+    let regex = Regex::xpath(r"^a.b$", "").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::EcmaScript).pattern,
+        r"^a[^\u{A}\u{D}]b$"
+    );
+    assert_eq!(
+        translate(&regex, Dialect::Pcre).pattern,
+        r"^a[^\x{A}\x{D}]b\z"
+    );
+    assert_eq!(
+        translate(&regex, Dialect::Rust).pattern,
+        r"^a[^\x{A}\x{D}]b$"
+    );
+
+    // ECMAScript also breaks lines at a carriage return, U+2028 and U+2029
+    let regex = Regex::xpath(r"^a$", "m").unwrap();
+    let translation = translate(&regex, Dialect::EcmaScript);
+    assert_eq!(translation.pattern, r"(?<![^\u{A}])a(?![^\u{A}])");
+    assert_eq!(translation.flags, "u");
+    assert_eq!(translate(&regex, Dialect::Pcre).pattern, "(?m)^a$");
+    assert_eq!(translate(&regex, Dialect::Rust).pattern, "(?m)^a$");
+
+    let regex = Regex::ecmascript(r"^a$", "m").unwrap();
+    let translation = translate(&regex, Dialect::EcmaScript);
+    assert_eq!(translation.pattern, "^a$");
+    assert_eq!(translation.flags, "um");
+    assert_eq!(
+        translate(&regex, Dialect::Pcre).pattern,
+        r"(?<![^\x{A}\x{D}\x{2028}\x{2029}])a(?![^\x{A}\x{D}\x{2028}\x{2029}])"
+    );
+    assert!(matches!(
+        regex.translate(Dialect::Rust),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_quantified_assertions() {
+    let regex = Regex::xpath(r"^*a$+", "").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::EcmaScript).pattern,
+        "(?:^)*a(?:$)+"
+    );
+    assert_eq!(translate(&regex, Dialect::Rust).pattern, "(?:^)*a(?:$)+");
+    let regex = Regex::xpath(r"^?a", "m").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::EcmaScript).pattern,
+        r"(?:(?<![^\u{A}]))?a"
+    );
+}
+
+#[test]
+fn test_expanded_classes() {
+    let regex = Regex::xsd(r"\p{IsGreek}+", "").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::Rust).pattern,
+        r"\A(?:[\x{370}-\x{3FF}]+)\z"
+    );
+    let regex = Regex::xsd(r"\i", "").unwrap();
+    assert!(translate(&regex, Dialect::EcmaScript)
+        .pattern
+        .starts_with(r"^(?:[:A-Z_a-z\u{C0}-\u{D6}\u{D8}-\u{F6}"));
+    let regex = Regex::xsd(r"\s\S", "").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::Pcre).pattern,
+        r"\A(?:[\x{9}\x{A}\x{D} ][^\x{9}\x{A}\x{D} ])\z"
+    );
+    let regex = Regex::xsd(r"\P{L}[\-\[\]^&]", "").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::Rust).pattern,
+        r"\A(?:\P{L}[\&\-\[\]\^])\z"
+    );
+}
+
+#[test]
+fn test_flags() {
+    let regex = Regex::xpath("ab1/", "i").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::EcmaScript).pattern,
+        r"[Aa][Bb]1\/"
+    );
+    assert_eq!(translate(&regex, Dialect::Pcre).pattern, r"[Aa][Bb]1/");
+    let regex = Regex::xpath("a.b", "s").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::Rust).pattern,
+        r"a[\x{0}-\x{D7FF}\x{E000}-\x{10FFFF}]b"
+    );
+}
+
+#[test]
+fn test_empty_class() {
+    let regex = Regex::xsd("[a-[a]]", "").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::Pcre).pattern,
+        r"\A(?:[^\x{0}-\x{D7FF}\x{E000}-\x{10FFFF}])\z"
+    );
+}
+
+#[test]
+fn test_back_references() {
+    let regex = Regex::xpath(r"(a)(?:b)\11", "").unwrap();
+    assert_eq!(
+        translate(&regex, Dialect::EcmaScript).pattern,
+        r"(a)(?:b)(?:\1)1"
+    );
+    assert_eq!(translate(&regex, Dialect::Pcre).pattern, r"(a)(?:b)(?:\1)1");
+    assert!(matches!(
+        regex.translate(Dialect::Rust),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_reluctant() {
+    let regex = Regex::xpath(r"a+?b{2,}?", "").unwrap();
+    assert_eq!(translate(&regex, Dialect::Rust).pattern, r"a+?b{2,}?");
+}
+
+#[test]
+fn test_ecmascript_case_folding() {
+    // the 'i' flag adds the case variants of the characters in a category,
+    // so the category can't be written by name
+    let regex = Regex::ecmascript(r"\p{Lu}", "i").unwrap();
+    let translation = translate(&regex, Dialect::EcmaScript);
+    assert!(!translation.pattern.contains(r"\p{Lu}"));
+    assert!(translation.pattern.contains("a-z"));
+    let regex = Regex::xpath(r"\p{Lu}", "i").unwrap();
+    assert_eq!(translate(&regex, Dialect::EcmaScript).pattern, r"\p{Lu}");
+}
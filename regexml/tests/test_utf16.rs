@@ -0,0 +1,56 @@
+use regexml::{Haystack, Regex, Utf16};
+
+fn utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+#[test]
+fn test_utf16_haystack() {
+    // 𝄞 is a surrogate pair
+    let units = utf16("a𝄞b");
+    let haystack = Utf16::new(&units);
+    assert_eq!(haystack.len(), 3);
+    assert_eq!(haystack.char_at(1), Some('𝄞'));
+    assert_eq!(haystack.char_at(2), Some('b'));
+    assert_eq!(haystack.char_at(3), None);
+    assert_eq!(haystack.unit_offset(2), 3);
+    assert_eq!(haystack.unit_offset(3), 4);
+    assert_eq!(haystack.position(3), Some(2));
+    assert_eq!(haystack.position(4), Some(3));
+    // the middle of the pair
+    assert_eq!(haystack.position(2), None);
+    assert_eq!(haystack.position(5), None);
+}
+
+#[test]
+fn test_find_utf16() {
+    let units = utf16("x𝄞𝄞yb");
+    let regex = Regex::xpath("𝄞+y", "").unwrap();
+    assert!(regex.is_match_utf16(&units));
+    assert_eq!(regex.find_utf16(&units, 0), Some(1..6));
+    assert_eq!(regex.find_utf16(&units, 3), Some(3..6));
+    assert_eq!(regex.find_utf16(&units, 2), None);
+    assert_eq!(regex.find_utf16(&units, 6), None);
+
+    let regex = Regex::xpath("b", "").unwrap();
+    assert_eq!(regex.find_utf16(&units, 0), Some(6..7));
+}
+
+#[test]
+fn test_lone_surrogates() {
+    // a lone high surrogate, then a lone low surrogate
+    let units = [0x61, 0xD834, 0x62, 0xDD1E];
+    let haystack = Utf16::new(&units);
+    assert_eq!(haystack.len(), 4);
+    assert_eq!(haystack.char_at(1), Some('\u{FFFD}'));
+    assert_eq!(haystack.char_at(3), Some('\u{FFFD}'));
+
+    let regex = Regex::xpath("a.b", "").unwrap();
+    assert_eq!(regex.find_utf16(&units, 0), Some(0..3));
+    let regex = Regex::xpath(r"\p{IsSpecials}", "").unwrap();
+    assert_eq!(regex.find_utf16(&units, 0), Some(1..2));
+    assert_eq!(regex.find_utf16(&units, 2), Some(3..4));
+    let regex = Regex::xpath(r"\p{IsHighSurrogates}|\p{IsLowSurrogates}", "").unwrap();
+    assert!(!regex.is_match_utf16(&units));
+    assert!(Regex::xpath(r"\p{Cs}", "").is_err());
+}
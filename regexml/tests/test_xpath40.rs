@@ -0,0 +1,84 @@
+use regexml::{AnalyzeEntry, Error, Language, MatchEntry, Regex};
+
+#[test]
+fn test_replace_zero_length_matches() {
+    let regex = Regex::xpath40("x*", "").unwrap();
+    assert_eq!(regex.replace_all("abc", "-").unwrap(), "-a-b-c-");
+    let regex = Regex::xpath40("b*", "").unwrap();
+    assert_eq!(regex.replace_all("abc", "-").unwrap(), "-a--c-");
+    assert_eq!(regex.replace_all("", "-").unwrap(), "-");
+}
+
+#[test]
+fn test_xpath31_rejects_zero_length_matches() {
+    let regex = Regex::xpath("x*", "").unwrap();
+    assert_eq!(
+        regex.replace_all("abc", "-").unwrap_err(),
+        Error::MatchesEmptyString
+    );
+    assert_eq!(regex.analyze("abc").unwrap_err(), Error::MatchesEmptyString);
+}
+
+#[test]
+fn test_tokenize_zero_length_matches() {
+    let regex = Regex::xpath40("", "").unwrap();
+    assert_eq!(
+        regex.tokenize("abc").unwrap().collect::<Vec<_>>(),
+        vec!["a", "b", "c"]
+    );
+    let regex = Regex::xpath40(",?", "").unwrap();
+    assert_eq!(
+        regex.tokenize("a,b").unwrap().collect::<Vec<_>>(),
+        vec!["a", "b"]
+    );
+    assert_eq!(regex.tokenize("").unwrap().count(), 0);
+}
+
+#[test]
+fn test_analyze_zero_length_matches() {
+    let regex = Regex::xpath40("b*", "").unwrap();
+    let matched = |s: &str| AnalyzeEntry::Match(vec![MatchEntry::String(s.to_string())]);
+    assert_eq!(
+        regex.analyze("ab").unwrap().collect::<Vec<_>>(),
+        vec![
+            matched(""),
+            AnalyzeEntry::NonMatch("a".to_string()),
+            matched("b"),
+            matched(""),
+        ]
+    );
+}
+
+#[test]
+fn test_replace_with() {
+    let regex = Regex::xpath40(r"(\d+)(px)?", "").unwrap();
+    let replaced = regex
+        .replace_with("10px 2", |matched, groups| {
+            format!("[{}|{}|{}]", matched, groups[0], groups[1])
+        })
+        .unwrap();
+    assert_eq!(replaced, "[10px|10|px] [2|2|]");
+
+    // the result isn't a replacement string
+    let regex = Regex::xpath("a", "").unwrap();
+    let replaced = regex.replace_with("banana", |_, _| "$1\\".to_string());
+    assert_eq!(replaced.unwrap(), "b$1\\n$1\\n$1\\");
+}
+
+#[test]
+fn test_free_spacing_in_class() {
+    let regex = Regex::xpath40("[a b]+", "x").unwrap();
+    assert!(regex.is_match("ab"));
+    assert!(!regex.is_match(" "));
+    // XPath 3.1 keeps whitespace in a character class
+    let regex = Regex::xpath("[a b]+", "x").unwrap();
+    assert!(regex.is_match(" "));
+}
+
+#[test]
+fn test_xpath31_syntax() {
+    let regex = Regex::xpath40(r"^(?:a)(b)\1$", "").unwrap();
+    assert!(regex.is_match("abb"));
+    assert_eq!(regex.language(), Language::XPath40);
+    assert!(Regex::xpath40("a.b", "q").unwrap().is_match("a.b"));
+}